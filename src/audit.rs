@@ -0,0 +1,63 @@
+//! An append-only record of every piece of text sent to Google, so that what left the machine can be reviewed.
+//!
+//! Each request is written as one line of JSON, such as
+//! `{"timestamp":"2026-10-15T18:00:00Z","method":"GET","destination":"http://…","text":"Saluton"}`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use json::Json;
+
+/// An audit log which has been opened for appending. Requests made from several threads at once are
+/// written one whole line at a time.
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open the audit log at `path`, creating it if it does not exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<AuditLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(AuditLog { file: Mutex::new(file) })
+    }
+
+    /// Record that `text` is about to be sent to `destination` with the HTTP `method`.
+    pub fn record(&self, method: &str, destination: &str, text: &str) -> io::Result<()> {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let entry = Json::Object(vec![
+            ("timestamp".to_string(),   format_timestamp(seconds).into()),
+            ("method".to_string(),      method.into()),
+            ("destination".to_string(), destination.into()),
+            ("text".to_string(),        text.into()),
+        ]);
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(format!("{}\n", entry).as_bytes())?;
+        file.flush()
+    }
+}
+
+/// Write a number of seconds since the Unix epoch as an ISO 8601 date and time in UTC.
+fn format_timestamp(seconds: u64) -> String {
+    // Convert days since the epoch into a civil date, counting in 400-year eras which begin on the 1st of March.
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    let time = seconds % 86400;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0).as_str(), "1970-01-01T00:00:00Z");
+    assert_eq!(format_timestamp(951782400).as_str(), "2000-02-29T00:00:00Z");
+    assert_eq!(format_timestamp(1792087200).as_str(), "2026-10-15T18:00:00Z");
+}
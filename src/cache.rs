@@ -0,0 +1,111 @@
+//! A cache of Google's responses on disk, so that translating the same text again, such as when running the
+//! filter over a file which has barely changed, costs no request.
+//!
+//! Each response is kept in a file of its own, named after a hash of its key. The file begins with the key
+//! itself on one line, so that keys which share a hash are told apart, and its age is that of the file.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use json::Json;
+
+/// How long a cached response is used for, unless configured otherwise: one week.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A directory of cached responses, each of which is used until it is older than the time to live.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResponseCache {
+    directory: PathBuf,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    /// A cache kept in `directory`, which is created when the first response is stored.
+    pub fn new<P: Into<PathBuf>>(directory: P, ttl: Duration) -> ResponseCache {
+        ResponseCache { directory: directory.into(), ttl }
+    }
+
+    /// The response stored under `key`, unless there is none or it has expired.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let age = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+        if age > self.ttl {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        let mut contents = String::new();
+        File::open(&path).and_then(|mut file| file.read_to_string(&mut contents)).ok()?;
+        let (stored, response) = contents.split_at(contents.find('\n')?);
+        if stored != Json::from(key).to_string() {
+            return None;
+        }
+        Some(response[1..].to_string())
+    }
+
+    /// Store `response` under `key`, replacing any response already stored under it.
+    pub fn put(&self, key: &str, response: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        // Written in full to a temporary file of its own first, so that a reader never sees half of a response
+        // and two writers of the same key never write into the same file.
+        let path = self.path(key);
+        let partial = temporary_path(&path);
+        let written = File::create(&partial)
+            .and_then(|mut file| file.write_all(format!("{}\n{}", Json::from(key), response).as_bytes()))
+            .and_then(|_| fs::rename(&partial, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        written
+    }
+
+    /// The directory that the responses are kept in.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}", fnv1a(key.as_bytes())))
+    }
+}
+
+/// A path beside `path` to write its new contents to before they are renamed over it, which no other thread
+/// or process is writing to: it is named after the process and a count of the paths given out by it.
+pub fn temporary_path(path: &Path) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let mut name = path.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+    name.push(format!(".{}-{}.tmp", process::id(), COUNT.fetch_add(1, Ordering::Relaxed)));
+    path.with_file_name(name)
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which unlike the standard library's hasher is the same in every build,
+/// so that responses cached by one version of the program are found by the next.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[test]
+fn test_response_cache() {
+    use std::env;
+
+    let directory = env::temp_dir().join(format!("rust-google-translate-cache-test-{}", ::std::process::id()));
+    let cache = ResponseCache::new(directory.as_path(), Duration::from_secs(60));
+    assert_eq!(cache.get("google\n&sl=eo&tl=en\nSaluton"), None);
+    cache.put("google\n&sl=eo&tl=en\nSaluton", "[[[\"Hello\",\"Saluton\"]]]\n").unwrap();
+    // Nothing is left behind but the response itself.
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    assert_eq!(cache.get("google\n&sl=eo&tl=en\nSaluton").as_deref(), Some("[[[\"Hello\",\"Saluton\"]]]\n"));
+    assert_eq!(cache.get("google\n&sl=eo&tl=de\nSaluton"), None);
+
+    let expired = ResponseCache::new(directory.as_path(), Duration::from_secs(0));
+    ::std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(expired.get("google\n&sl=eo&tl=en\nSaluton"), None);
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    assert_ne!(temporary_path(&directory), temporary_path(&directory));
+    let _ = fs::remove_dir_all(directory);
+}
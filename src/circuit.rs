@@ -0,0 +1,128 @@
+//! Circuit breakers for endpoints, so that an endpoint which keeps failing is rested for a while rather than
+//! sent every request only for each of them to fail in turn.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many failures in a row open the circuit of an endpoint, unless configured otherwise.
+pub const DEFAULT_CIRCUIT_THRESHOLD: u32 = 5;
+/// How long an endpoint is rested once its circuit opens, unless configured otherwise.
+pub const DEFAULT_CIRCUIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Whether an endpoint is being sent requests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CircuitState {
+    /// The endpoint is in use, having failed this many times in a row.
+    Closed(u32),
+    /// The endpoint failed too many times in a row and is passed over for as long as is given.
+    Open(Duration),
+    /// The endpoint has been rested and a single request is under way to see whether it has recovered.
+    HalfOpen,
+}
+
+/// The circuits of a list of endpoints, shared by every thread sending requests to them.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    circuits: Mutex<Vec<Circuit>>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Circuit {
+    /// The number of requests in a row which have failed.
+    failures: u32,
+    /// When the circuit last opened, if it is open.
+    opened: Option<Instant>,
+    /// Whether the request which decides whether the circuit closes again is under way.
+    trial: bool,
+}
+
+impl CircuitBreaker {
+    /// Circuits for `count` endpoints, each of which opens after `threshold` failures in a row and stays open
+    /// for `cooldown`. A threshold of zero never opens them.
+    pub fn new(count: usize, threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker { threshold, cooldown, circuits: Mutex::new(vec![Circuit::default(); count]) }
+    }
+
+    /// Choose the endpoint to send the next request to: the first, counting on from `preferred`, whose circuit
+    /// is closed or has been open for long enough to be tried again. If every circuit is open, returns how long
+    /// it will be until the first of them may be tried.
+    pub fn select(&self, preferred: usize, now: Instant) -> Result<usize, Duration> {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let count = circuits.len();
+        let mut wait: Option<Duration> = None;
+        for index in (0..count).map(|offset| (preferred + offset) % count) {
+            let circuit = &mut circuits[index];
+            let opened = match circuit.opened {
+                None => return Ok(index),
+                Some(opened) => opened
+            };
+            let rested = now.saturating_duration_since(opened);
+            if circuit.trial {
+                // Whether it may be tried again depends on how the request under way fares.
+                continue;
+            } else if rested >= self.cooldown {
+                circuit.trial = true;
+                return Ok(index);
+            }
+            let remaining = self.cooldown.saturating_sub(rested);
+            wait = Some(wait.map_or(remaining, |wait| wait.min(remaining)));
+        }
+        Err(wait.unwrap_or(self.cooldown))
+    }
+
+    /// Record that a request to the endpoint at `index` succeeded, closing its circuit.
+    pub fn succeeded(&self, index: usize) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(circuit) = circuits.get_mut(index) {
+            *circuit = Circuit::default();
+        }
+    }
+
+    /// Record that a request to the endpoint at `index` failed at `now`, opening its circuit once it has failed
+    /// too many times in a row, or at once if it failed the request made to see whether it had recovered.
+    pub fn failed(&self, index: usize, now: Instant) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(circuit) = circuits.get_mut(index) {
+            circuit.failures = circuit.failures.saturating_add(1);
+            if circuit.trial || (self.threshold != 0 && circuit.failures >= self.threshold) {
+                circuit.opened = Some(now);
+                circuit.trial = false;
+            }
+        }
+    }
+
+    /// The state of the circuit of each endpoint at `now`.
+    pub fn states(&self, now: Instant) -> Vec<CircuitState> {
+        let circuits = self.circuits.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        circuits.iter().map(|circuit| match circuit.opened {
+            None => CircuitState::Closed(circuit.failures),
+            Some(_) if circuit.trial => CircuitState::HalfOpen,
+            Some(opened) => CircuitState::Open(self.cooldown.saturating_sub(now.saturating_duration_since(opened)))
+        }).collect()
+    }
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let breaker = CircuitBreaker::new(2, 2, Duration::from_secs(60));
+    let now = Instant::now();
+    breaker.failed(0, now);
+    assert_eq!(breaker.select(0, now), Ok(0));
+    breaker.failed(0, now);
+    assert_eq!(breaker.states(now), vec![CircuitState::Open(Duration::from_secs(60)), CircuitState::Closed(0)]);
+    // The first endpoint is passed over for the second, and once both are open there is nothing to send to.
+    assert_eq!(breaker.select(0, now), Ok(1));
+    breaker.failed(1, now + Duration::from_secs(10));
+    breaker.failed(1, now + Duration::from_secs(10));
+    assert_eq!(breaker.select(0, now + Duration::from_secs(30)), Err(Duration::from_secs(30)));
+
+    // Once rested, a single request is let through, and failing it opens the circuit again at once.
+    assert_eq!(breaker.select(0, now + Duration::from_secs(60)), Ok(0));
+    assert_eq!(breaker.select(0, now + Duration::from_secs(60)), Err(Duration::from_secs(10)));
+    breaker.failed(0, now + Duration::from_secs(61));
+    assert_eq!(breaker.select(0, now + Duration::from_secs(70)), Ok(1));
+    breaker.succeeded(1);
+    assert_eq!(breaker.states(now + Duration::from_secs(70)), vec![CircuitState::Open(Duration::from_secs(51)), CircuitState::Closed(0)]);
+}
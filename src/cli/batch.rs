@@ -0,0 +1,402 @@
+//! Translation of files and of standard input a line at a time, with the text prepared and tidied as asked.
+
+use std::fs::File;
+use std::io::{BufRead, Read, Write};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use rust_google_translate::{
+    convert_units,
+    find_placeholders,
+    normalize,
+    protect,
+    restore,
+    split_document,
+    summarize,
+    translate_html,
+    translate_markdown,
+    translate_po,
+    Error,
+    JobLedger,
+    Locale,
+    Normalization,
+    PendingTranslation,
+    Segment,
+    Subtitles,
+    TranslationResult,
+    Translator,
+    MAX_SEGMENT_LENGTH
+};
+
+use cli::encoding::{decode, OutputEncoding};
+use cli::format::{report_error, Errors};
+use cli::progress::Progress;
+use cli::review::Reviewer;
+
+/// Translate the file at `input` into `language`. The file is split into paragraphs, which are translated in order and joined together with the same
+/// whitespace as the original. Paragraphs which fail to translate are kept in their original language,
+/// and are returned along with the line that they start on and the reason that they failed. If a `summary`
+/// ratio is given, the file is first trimmed down to its most representative sentences. Each paragraph is
+/// prepared and tidied according to `processing`, and HTML and Markdown are translated a run of text at a time.
+/// How far through the file the translation has got is shown on the progress bar of `job`, in bytes, and the
+/// segments which its ledger records are not translated again; if it has a reviewer, each translated segment of
+/// plain text is written only as they accept or edit it. The translation keeps the line endings of the
+/// file, and the encoding of its byte order mark if it has one.
+pub fn translate_file(translator: &Translator, source: Option<&str>, language: &str, input: &str, summary: Option<f64>,
+    processing: &Processing, job: &Job) -> std::io::Result<FileTranslation>
+{
+    let mut bytes = Vec::new();
+    File::open(input)?.read_to_end(&mut bytes)?;
+    let (text, encoding) = decode(&bytes)?;
+    // The file is translated with Unix line endings and its Windows line endings are put back afterwards, so that
+    // the translation differs from the original in its text alone.
+    let crlf = uses_crlf(text.as_str());
+    let mut text = if crlf { text.replace("\r\n", "\n") } else { text };
+    if let Some(ratio) = summary { text = summarize(text.as_str(), ratio); }
+    let mut translated = translate_document(translator, source, language, text.as_str(), processing, job)?;
+    if crlf {
+        translated.text = translated.text.replace("\r\n", "\n").replace('\n', "\r\n");
+    }
+    translated.encoding = encoding;
+    Ok(translated)
+}
+
+/// Translate the text of a file, as `translate_file` does once it has been read.
+fn translate_document(translator: &Translator, source: Option<&str>, language: &str, text: &str, processing: &Processing,
+    job: &Job) -> std::io::Result<FileTranslation>
+{
+    job.progress.set_total(text.len());
+
+    if processing.markup == Some(Markup::Subtitles) && Subtitles::parse(text).cues().is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "no subtitle cues found"));
+    }
+    if let Some(markup) = processing.markup {
+        let (result, failures) = translate_markup(translator, markup, text, source, language, processing, job);
+        job.progress.clear();
+        return Ok(FileTranslation { text: result.text, failures, near_matches: Vec::new(), encoding: None });
+    }
+
+    let mut translated = FileTranslation {
+        text: String::with_capacity(text.len()), failures: Vec::new(), near_matches: Vec::new(), encoding: None
+    };
+    for segment in split_document(text, MAX_SEGMENT_LENGTH) {
+        let segment = match segment {
+            Segment::Space(space) => { translated.text.push_str(space); continue },
+            Segment::Text(segment) => segment
+        };
+        let offset = segment.as_ptr() as usize - text.as_ptr() as usize;
+        let line = text[..offset].matches('\n').count() + 1;
+        job.progress.set(offset);
+        match job.translate(segment, || translate_processed(translator, segment, source, language, processing)) {
+            Ok(result) => {
+                if let Some(similarity) = result.similarity.filter(|&similarity| similarity < 1.0) {
+                    translated.near_matches.push((line, similarity));
+                }
+                match job.review.as_ref() {
+                    Some(reviewer) => {
+                        job.progress.clear();
+                        let reviewed = reviewer.borrow_mut().review(line, segment, result.text.as_str())?;
+                        translated.text.push_str(reviewed.as_deref().unwrap_or(segment));
+                    },
+                    None => translated.text.push_str(result.text.as_str())
+                }
+            },
+            Err(why) => {
+                translated.failures.push((line, why));
+                translated.text.push_str(segment);
+            }
+        }
+    }
+    job.progress.clear();
+    Ok(translated)
+}
+
+/// A file translated by `translate_file`.
+pub struct FileTranslation {
+    pub text: String,
+    /// The segments which could not be translated and were kept as they were, by the line which they start on,
+    /// with the reason that they failed.
+    pub failures: Vec<(usize, Error)>,
+    /// The segments translated from a near but not exact match in the translation memory, by the line which they
+    /// start on, with how similar the match was, which should be looked over.
+    pub near_matches: Vec<(usize, f64)>,
+    /// The encoding which the file was in, if it began with a byte order mark saying so, which the translation is
+    /// written in too unless another is asked for.
+    pub encoding: Option<OutputEncoding>,
+}
+
+/// Whether most of the lines of `text` end with a Windows line ending.
+fn uses_crlf(text: &str) -> bool {
+    let crlf = text.matches("\r\n").count();
+    crlf > 0 && crlf * 2 >= text.matches('\n').count()
+}
+
+/// What a long job shows and keeps as it goes: its progress bar, the ledger of the chunks which it has
+/// translated, which lets it be resumed if it is interrupted, and whoever reviews its translations.
+pub struct Job {
+    pub progress: Progress,
+    pub ledger: Option<JobLedger>,
+    pub review: Option<RefCell<Reviewer>>,
+}
+
+impl Job {
+    /// A job which draws no progress bar, keeps no ledger and is not reviewed.
+    pub fn untracked() -> Job {
+        Job { progress: Progress::hidden(), ledger: None, review: None }
+    }
+
+    /// Translate `chunk` with `translate`, unless the ledger records it as translated already, recording it once
+    /// it is. A translation taken from the ledger carries its text alone.
+    fn translate<F: FnOnce() -> Result<TranslationResult, Error>>(&self, chunk: &str, translate: F) -> Result<TranslationResult, Error> {
+        if let Some(text) = self.ledger.as_ref().and_then(|ledger| ledger.completed(chunk)) {
+            return Ok(TranslationResult { text, ..TranslationResult::default() });
+        }
+        let result = translate()?;
+        if let Some(ledger) = self.ledger.as_ref() {
+            // A ledger which cannot be written to only means that the chunk is translated again if the job is resumed.
+            let _ = ledger.record(chunk, result.text.as_str());
+        }
+        Ok(result)
+    }
+}
+
+/// How text is prepared before it is sent to Google and how its translation is tidied afterwards, when
+/// translating a file or lines of standard input.
+#[derive(Clone, Debug, Default)]
+pub struct Processing {
+    /// Whether the placeholders of format strings are masked.
+    pub placeholders: bool,
+    pub normalization: Normalization,
+    /// The markup which the text is written in, of which only the text is translated, if any.
+    pub markup: Option<Markup>,
+}
+
+/// A markup language or file format which --html, --markdown, --subtitles or --po translates the text of,
+/// leaving the rest as it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Markup {
+    Html,
+    Markdown,
+    /// SRT, WebVTT, ASS or SSA subtitles.
+    Subtitles,
+    /// A gettext PO file, of which only the messages which have not been translated yet are translated.
+    Po,
+}
+
+/// Translate `input` with the placeholders of format strings masked if they are to be kept, putting them back
+/// into the translation once it has arrived, then tidy the translation. Sensitive text and glossary terms are
+/// masked by the translator itself.
+pub fn translate_processed(translator: &Translator, input: &str, source: Option<&str>, language: &str, processing: &Processing)
+    -> Result<TranslationResult, Error>
+{
+    let masked = if processing.placeholders { find_placeholders(input) } else { Vec::new() };
+    // Placeholders already in the input, such as those standing for the tags of HTML, are masked by `protect` in
+    // turn and put back as they were, ready for the tags to be put back in their place.
+    let (protected, masked) = protect(input, &masked);
+    let mut result = translator.translate(protected.as_str(), source, language)?;
+    result.text = restore(result.text.as_str(), &masked).0;
+    if processing.normalization.is_enabled() {
+        result.text = normalize(result.text.as_str(), language, &processing.normalization);
+    }
+    Ok(result)
+}
+
+/// Translate the text of the HTML, Markdown, subtitles or PO file in `input` into `language`, keeping the rest
+/// as it is, with each run of text prepared and tidied according to `processing`. Runs which fail to translate
+/// are kept in their original language, or left untranslated in a PO file, and are returned along with the
+/// line that they start on and the reason that they failed. The offset of each run in `input` is shown on the
+/// progress bar of `job`, and the runs which its ledger records are not translated again.
+pub fn translate_markup(translator: &Translator, markup: Markup, input: &str, source: Option<&str>, language: &str,
+    processing: &Processing, job: &Job) -> (TranslationResult, Vec<(usize, Error)>)
+{
+    let mut detected = TranslationResult::default();
+    let mut failures = Vec::new();
+    // Subtitles are translated many cues at a time, one to a line, so each cue is tidied on its own.
+    let normalization = processing.normalization;
+    let batched = Processing { normalization: Normalization::default(), ..processing.clone() };
+    // The messages of a PO file are format strings more often than not, and a lost placeholder breaks the program.
+    let formatted = Processing { placeholders: true, ..processing.clone() };
+    let processing = match markup {
+        Markup::Subtitles => &batched,
+        Markup::Po        => &formatted,
+        _                 => processing
+    };
+    let mut translate_run = |run: &str, offset: usize| {
+        job.progress.set(offset);
+        match job.translate(run, || translate_processed(translator, run, source, language, processing)) {
+            Ok(result) => {
+                if detected.source_language.is_none() {
+                    detected.source_language = result.source_language;
+                    detected.confidence = result.confidence;
+                }
+                if markup == Markup::Subtitles && normalization.is_enabled() {
+                    Some(result.text.split('\n').map(|cue| normalize(cue, language, &normalization)).collect::<Vec<String>>().join("\n"))
+                } else {
+                    Some(result.text)
+                }
+            },
+            Err(why) => {
+                failures.push((input[..offset].matches('\n').count() + 1, why));
+                None
+            }
+        }
+    };
+    let mut translate_or_keep = |run: &str, offset: usize| translate_run(run, offset).unwrap_or_else(|| run.to_string());
+    let text = match markup {
+        Markup::Html      => translate_html(input, &mut translate_or_keep),
+        Markup::Markdown  => translate_markdown(input, &mut translate_or_keep),
+        Markup::Subtitles => Subtitles::parse(input).translate(translator.chunk_length(), &mut translate_or_keep),
+        Markup::Po        => translate_po(input, language, &mut translate_run)
+    };
+    (TranslationResult { text, ..detected }, failures)
+}
+
+/// Translate the same text into each of `languages` at once, first converting any measurements in it into
+/// the units used by each language.
+pub fn translate_localized(translator: &Translator, input: &str, source: Option<&str>, languages: &[&str], units: Units)
+    -> Vec<Result<TranslationResult, Error>>
+{
+    let pending = languages.iter().map(|language| {
+        let locale = Locale::for_language(language);
+        let metric = match units {
+            Units::Target   => Some(locale.metric),
+            Units::Metric   => Some(true),
+            Units::Imperial => Some(false),
+            Units::Keep     => None
+        };
+        let input = metric.map_or_else(|| input.to_string(), |metric| convert_units(input, metric, &locale));
+        translator.translate_async(input.as_str(), source, language)
+    }).collect::<Vec<PendingTranslation>>();
+    pending.into_iter().map(PendingTranslation::wait).collect()
+}
+
+/// Which units --localize converts measurements into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Units {
+    Target,
+    Metric,
+    Imperial,
+    Keep,
+}
+
+/// Translate each line of standard input into `language` independently, using `jobs` worker threads. Lines
+/// are written to standard output in the same order that they were read, so that the output stays aligned
+/// with the input line for line. Lines which fail to translate are left blank, and are returned along with
+/// the reason that they failed once every line has been processed. Each line is prepared and tidied
+/// according to `processing`. The lines written so far are counted on `progress`.
+pub fn translate_lines(translator: &Translator, source: Option<&str>, language: &str, jobs: usize, processing: &Processing,
+    progress: &Progress) -> Vec<(usize, Error)>
+{
+    let (job_sender, job_receiver) = mpsc::channel::<(usize, String)>();
+    let (result_sender, result_receiver) = mpsc::channel::<(usize, Result<String, Error>)>();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+    for _ in 0..jobs {
+        let job_receiver = job_receiver.clone();
+        let translator = translator.clone();
+        let result_sender = result_sender.clone();
+        let source = source.map(String::from);
+        let language = language.to_string();
+        let processing = processing.clone();
+        thread::spawn(move || loop {
+            let job = job_receiver.lock().unwrap().recv();
+            match job {
+                Ok((index, line)) => {
+                    let translation = if line.trim().is_empty() {
+                        Ok(String::new())
+                    } else {
+                        // A translation spanning several lines would break the alignment with the input.
+                        translate_processed(&translator, line.as_str(), source.as_deref(), language.as_str(), &processing)
+                            .map(|result| result.text.lines().collect::<Vec<&str>>().join(" "))
+                    };
+                    if result_sender.send((index, translation)).is_err() { break }
+                },
+                Err(_) => break
+            }
+        });
+    }
+    drop(result_sender);
+
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for (index, line) in stdin.lock().lines().enumerate() {
+            match line {
+                Ok(line) => if job_sender.send((index, line)).is_err() { break },
+                Err(_)   => break
+            }
+        }
+    });
+
+    // Results arrive in whatever order the workers finish, so hold them back until every earlier line is written.
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    let mut pending = BTreeMap::new();
+    let mut failures = Vec::new();
+    let mut next = 0;
+    for (index, translation) in result_receiver {
+        pending.insert(index, translation);
+        while let Some(translation) = pending.remove(&next) {
+            // The bar shares the terminal with the translations, so it is taken down while each is written.
+            progress.clear();
+            // Failed lines are left blank so that the remaining lines stay aligned.
+            match translation {
+                Ok(translation) => { let _ = writeln!(stdout, "{}", translation); },
+                Err(why) => {
+                    let _ = writeln!(stdout);
+                    failures.push((next, why));
+                }
+            }
+            next += 1;
+        }
+        let _ = stdout.flush();
+        progress.set(next);
+    }
+    progress.clear();
+    failures
+}
+
+/// Summarise the lines which could not be translated on standard error, along with a command which retries
+/// only those lines, or report each of them as JSON.
+pub fn report_failures(failures: &[(usize, Error)], errors: Errors) {
+    if errors == Errors::Json {
+        for &(index, ref why) in failures {
+            report_error(errors, why, Some(index), "");
+        }
+        return
+    }
+    eprintln!("rust-google-translate: {} line(s) could not be translated and were left blank:", failures.len());
+    for &(index, ref why) in failures {
+        eprintln!("    line {}: {}", index + 1, why);
+    }
+
+    let lines = failures.iter().map(|&(index, _)| format!("{}p", index + 1)).collect::<Vec<String>>().join(";");
+    let arguments = std::env::args().skip(1).map(|argument| shell_quote(argument.as_str())).collect::<Vec<String>>().join(" ");
+    eprintln!("To retry them, run:\n    sed -n '{}' INPUT | rust-google-translate {}", lines, arguments);
+}
+
+/// Quote an argument so that it may be pasted into a POSIX shell.
+pub fn shell_quote(argument: &str) -> String {
+    let safe = |c: char| c.is_alphanumeric() || "-_./,:=+@%".contains(c);
+    if !argument.is_empty() && argument.chars().all(safe) {
+        argument.to_string()
+    } else {
+        format!("'{}'", argument.replace('\'', "'\\''"))
+    }
+}
+
+#[test]
+fn test_uses_crlf() {
+    assert!(uses_crlf("Saluton\r\nmondo\r\n"));
+    assert!(uses_crlf("Saluton\r\nmondo\n"));
+    assert!(!uses_crlf("Saluton\nmondo\n\r\n"));
+    assert!(!uses_crlf("Saluton"));
+}
+
+#[test]
+fn test_shell_quote() {
+    assert_eq!(shell_quote("--lines").as_str(), "--lines");
+    assert_eq!(shell_quote("two words").as_str(), "'two words'");
+    assert_eq!(shell_quote("it's").as_str(), "'it'\\''s'");
+}
@@ -0,0 +1,119 @@
+//! Writing the translation of a file in an encoding other than UTF-8, with --output-encoding, for the programs
+//! which still expect their subtitles and resource files in a legacy encoding, and reading a file in the UTF-8 or
+//! UTF-16 which its byte order mark says, so that its translation is written in the same way.
+
+use std::io;
+
+use encoding_rs::{EncoderResult, Encoding, REPLACEMENT, UTF_16BE, UTF_16LE, UTF_8};
+
+/// What a character which the encoding cannot write is written as instead.
+const SUBSTITUTE: &'static str = "?";
+
+/// The encoding which file writes its output in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputEncoding {
+    encoding: &'static Encoding,
+    /// Whether the output begins with a byte order mark.
+    bom: bool,
+}
+
+impl OutputEncoding {
+    /// The encoding named `label`, such as `UTF-16LE`, `Shift_JIS` or `windows-1251`, followed by `+BOM` to
+    /// begin the output with a byte order mark, which only UTF-8 and UTF-16 have. `UTF-16` alone is
+    /// little-endian UTF-16 with a byte order mark, as Windows writes it.
+    pub fn parse(label: &str) -> Option<OutputEncoding> {
+        let label = label.trim();
+        let marked = label.len().checked_sub(4).filter(|&at| label.is_char_boundary(at) && label[at..].eq_ignore_ascii_case("+bom"));
+        let (name, bom) = match marked {
+            Some(at) => (&label[..at], true),
+            None     => (label, label.eq_ignore_ascii_case("utf-16"))
+        };
+        let encoding = Encoding::for_label(name.as_bytes()).filter(|&encoding| encoding != REPLACEMENT)?;
+        if bom && encoding != UTF_8 && encoding != UTF_16LE && encoding != UTF_16BE {
+            return None;
+        }
+        Some(OutputEncoding { encoding, bom })
+    }
+
+    /// The name of the encoding, such as `Shift_JIS`.
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    /// Encode `text`, returning its bytes along with the characters which the encoding has no way of writing,
+    /// each of which is written as a question mark.
+    pub fn encode(&self, text: &str) -> (Vec<u8>, Vec<char>) {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            let units = self.bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+            for unit in units {
+                bytes.extend_from_slice(&if self.encoding == UTF_16LE { unit.to_le_bytes() } else { unit.to_be_bytes() });
+            }
+            return (bytes, Vec::new());
+        }
+        if self.encoding == UTF_8 {
+            if self.bom { bytes.extend_from_slice("\u{feff}".as_bytes()); }
+            bytes.extend_from_slice(text.as_bytes());
+            return (bytes, Vec::new());
+        }
+        let mut unmappable = Vec::new();
+        let mut encoder = self.encoding.new_encoder();
+        let mut rest = text;
+        loop {
+            bytes.reserve(encoder.max_buffer_length_from_utf8_without_replacement(rest.len()).unwrap_or(rest.len() * 4));
+            let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(rest, &mut bytes, true);
+            rest = &rest[read..];
+            match result {
+                EncoderResult::InputEmpty => break,
+                EncoderResult::OutputFull => (),
+                EncoderResult::Unmappable(character) => {
+                    unmappable.push(character);
+                    // Encoded rather than pushed, so that an encoding which shifts between character sets, such as
+                    // ISO-2022-JP, shifts back first.
+                    bytes.reserve(encoder.max_buffer_length_from_utf8_without_replacement(SUBSTITUTE.len()).unwrap_or(8));
+                    let _ = encoder.encode_from_utf8_to_vec_without_replacement(SUBSTITUTE, &mut bytes, false);
+                }
+            }
+        }
+        (bytes, unmappable)
+    }
+}
+
+/// Read the file `bytes` as text, in UTF-16 if it begins with a byte order mark which says so and UTF-8
+/// otherwise, without the byte order mark. If there was one, the encoding which it marks is returned, with the
+/// byte order mark, for the translation to be written in.
+pub fn decode(bytes: &[u8]) -> io::Result<(String, Option<OutputEncoding>)> {
+    let invalid = |encoding: &str| io::Error::new(io::ErrorKind::InvalidData, format!("the file is not valid {}", encoding));
+    match Encoding::for_bom(bytes) {
+        Some((encoding, length)) => {
+            let (text, malformed) = encoding.decode_without_bom_handling(&bytes[length..]);
+            if malformed { return Err(invalid(encoding.name())) }
+            Ok((text.into_owned(), Some(OutputEncoding { encoding, bom: true })))
+        },
+        None => String::from_utf8(bytes.to_vec()).map(|text| (text, None)).map_err(|_| invalid("UTF-8"))
+    }
+}
+
+#[test]
+fn test_output_encoding() {
+    let utf16 = OutputEncoding::parse("UTF-16LE+BOM").unwrap();
+    assert_eq!(utf16.encode("Hé").0, vec![0xFF, 0xFE, b'H', 0, 0xE9, 0]);
+    assert_eq!(OutputEncoding::parse("utf-16"), Some(utf16));
+    assert_eq!(OutputEncoding::parse("UTF-16BE").unwrap().encode("H").0, vec![0, b'H']);
+    assert_eq!(OutputEncoding::parse("utf-8+bom").unwrap().encode("H").0, vec![0xEF, 0xBB, 0xBF, b'H']);
+    let cyrillic = OutputEncoding::parse("Windows-1251").unwrap();
+    assert_eq!(cyrillic.name(), "windows-1251");
+    assert_eq!(cyrillic.encode("Привет, 世界"), (vec![0xCF, 0xF0, 0xE8, 0xE2, 0xE5, 0xF2, b',', b' ', b'?', b'?'], vec!['世', '界']));
+    assert_eq!(OutputEncoding::parse("Shift_JIS").unwrap().encode("日本"), (vec![0x93, 0xFA, 0x96, 0x7B], Vec::new()));
+    assert_eq!(OutputEncoding::parse("Shift_JIS+BOM"), None);
+    assert_eq!(OutputEncoding::parse("klingon"), None);
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(decode(b"\xEF\xBB\xBFSaluton").unwrap(), ("Saluton".to_string(), OutputEncoding::parse("UTF-8+BOM")));
+    assert_eq!(decode(b"\xFF\xFEH\x00\xE9\x00").unwrap(), ("Hé".to_string(), OutputEncoding::parse("UTF-16LE+BOM")));
+    assert_eq!(decode(b"\xFE\xFF\x00H").unwrap(), ("H".to_string(), OutputEncoding::parse("UTF-16BE+BOM")));
+    assert_eq!(decode(b"Saluton").unwrap(), ("Saluton".to_string(), None));
+    assert!(decode(b"Sal\xFFuton").is_err());
+}
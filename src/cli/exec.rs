@@ -0,0 +1,66 @@
+//! Running a command and translating what it writes, with `exec`.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+use rust_google_translate::Translator;
+
+use cli::batch::{translate_processed, Processing};
+
+/// What the lines which a command run by `exec` writes to standard error begin with once translated.
+pub const STDERR_MARK: &'static str = "[stderr] ";
+
+/// The stream of a command which a line of its output was written to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stream {
+    Output,
+    Error
+}
+
+/// Run `command` with its standard output and standard error captured, and write each line of them, once
+/// translated into `language`, to the same stream of this program, in the order that they arrive. Lines which
+/// fail to translate are written as they were. Returns the exit status of the command, which is 1 if it was
+/// killed by a signal.
+pub fn execute_translated(translator: &Translator, command: &[String], source: Option<&str>, language: &str, processing: &Processing)
+    -> std::io::Result<i32>
+{
+    let mut child = Command::new(&command[0]).args(&command[1..]).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let (sender, receiver) = mpsc::channel::<(Stream, String)>();
+    if let Some(stdout) = child.stdout.take() {
+        let sender = sender.clone();
+        thread::spawn(move || forward_lines(stdout, Stream::Output, &sender));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let sender = sender.clone();
+        thread::spawn(move || forward_lines(stderr, Stream::Error, &sender));
+    }
+    drop(sender);
+
+    for (stream, line) in receiver {
+        // Blank lines, and lines such as progress bars which have no words in them, are not worth a request.
+        let translation = if line.chars().any(char::is_alphabetic) {
+            translate_processed(translator, line.as_str(), source, language, processing).map_or(line, |result| result.text)
+        } else {
+            line
+        };
+        match stream {
+            Stream::Output => println!("{}", translation),
+            Stream::Error  => eprintln!("{}{}", STDERR_MARK, translation)
+        }
+    }
+    Ok(child.wait()?.code().unwrap_or(1))
+}
+
+/// Send each line read from `reader` to `sender` along with the stream it was read from, without its line
+/// ending. Output which is not UTF-8, such as that of a tool using a legacy encoding, is read lossily.
+pub fn forward_lines<R: Read>(reader: R, stream: Stream, sender: &mpsc::Sender<(Stream, String)>) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+        let text = String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string();
+        if sender.send((stream, text)).is_err() { return }
+        line.clear();
+    }
+}
@@ -0,0 +1,303 @@
+//! Laying out translations for the terminal: wrapping, limiting their length, manual pages and the pager.
+
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use rust_google_translate::{Error, Json, TranslationResult};
+
+/// The width to wrap output to when standard output is a terminal, taken from `COLUMNS` or defaulting to 80.
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() { return None; }
+    std::env::var("COLUMNS").ok().and_then(|columns| columns.parse::<usize>().ok()).filter(|&x| x > 0).or(Some(80))
+}
+
+/// The number of terminal columns that a character occupies: zero for combining marks and joiners,
+/// two for East Asian wide characters and emoji, and one otherwise.
+pub fn char_width(character: char) -> usize {
+    match character as u32 {
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x200B..=0x200F | 0x20D0..=0x20FF
+            | 0xFE00..=0xFE0F | 0xFE20..=0xFE2F | 0xE0100..=0xE01EF => 0,
+        0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0x33FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F | 0x1F900..=0x1F9FF | 0x20000..=0x3FFFD => 2,
+        _ => 1
+    }
+}
+
+/// Re-flow each paragraph of `input` so that no line exceeds `width` columns. Lines are broken on
+/// whitespace, or between wide characters for scripts such as Chinese and Japanese that do not use
+/// spaces, and combining marks always stay attached to the character that they modify.
+pub fn wrap_text(input: &str, width: usize) -> String {
+    let mut output = String::with_capacity(input.len());
+    for (index, paragraph) in input.lines().enumerate() {
+        if index != 0 { output.push('\n'); }
+
+        // Split the paragraph into unbreakable units, remembering which ones were preceded by a space.
+        let mut units: Vec<(bool, String, usize)> = Vec::new();
+        let mut spaced = false;
+        let mut breakable = true;
+        for character in paragraph.chars() {
+            let columns = char_width(character);
+            if character.is_whitespace() {
+                spaced = true;
+                breakable = true;
+            } else if columns == 0 && !units.is_empty() {
+                units.last_mut().unwrap().1.push(character);
+            } else if breakable || columns == 2 {
+                units.push((spaced, character.to_string(), columns));
+                spaced = false;
+                breakable = columns == 2;
+            } else {
+                let unit = units.last_mut().unwrap();
+                unit.1.push(character);
+                unit.2 += columns;
+            }
+        }
+
+        let mut line = 0;
+        for (spaced, unit, columns) in units {
+            if line != 0 && line + spaced as usize + columns > width {
+                output.push('\n');
+                line = 0;
+            } else if spaced && line != 0 {
+                output.push(' ');
+                line += 1;
+            }
+            output.push_str(unit.as_str());
+            line += columns;
+        }
+    }
+    output
+}
+
+/// Lay out a translation made by -c for printing, limited to `max_length` characters and wrapped to `wrap`
+/// columns, with each piece on its own line.
+pub fn format_translation(translation: String, max_length: Option<usize>, overflow: Overflow, wrap: Option<usize>)
+    -> Result<String, String>
+{
+    let pieces = match max_length {
+        Some(max) => limit_length(translation.as_str(), max, overflow)?,
+        None      => vec![translation]
+    };
+    Ok(pieces.into_iter().map(|piece| match wrap {
+        Some(width) => wrap_text(piece.as_str(), width) + "\n",
+        None        => piece + "\n"
+    }).collect())
+}
+
+/// Fit a translation within `max` characters according to the overflow policy, returning the pieces to output.
+pub fn limit_length(translation: &str, max: usize, overflow: Overflow) -> Result<Vec<String>, String> {
+    let length = translation.chars().count();
+    if length <= max { return Ok(vec![translation.to_string()]); }
+    match overflow {
+        Overflow::Truncate => {
+            let mut output: String = translation.chars().take(max - 1).collect();
+            output.push('…');
+            Ok(vec![output])
+        },
+        Overflow::Error => Err(format!("translation is {} characters long, which exceeds the maximum of {}", length, max)),
+        Overflow::Chunk => {
+            let mut pieces = Vec::new();
+            let mut remaining: Vec<char> = translation.chars().collect();
+            while remaining.len() > max {
+                // Prefer to split on the last whitespace that fits, rather than in the middle of a word.
+                let split = remaining[..max + 1].iter().rposition(|c| c.is_whitespace()).filter(|&x| x > 0).unwrap_or(max);
+                pieces.push(remaining[..split].iter().collect::<String>().trim_end().to_string());
+                remaining = remaining[split..].iter().cloned().skip_while(|c| c.is_whitespace()).collect();
+            }
+            if !remaining.is_empty() { pieces.push(remaining.into_iter().collect()); }
+            Ok(pieces)
+        }
+    }
+}
+
+/// Lay out `text` like a manual page `width` columns wide. Headings, taken to be lines which stand alone, are
+/// short and do not end like a sentence, or which begin with Markdown's `#`, are set at the left margin, in
+/// bold if `bold`. Paragraphs are indented beneath them and re-flowed, apart from list items, which each
+/// begin a line and are wrapped with a hanging indent.
+pub fn render_man(text: &str, width: usize, bold: bool) -> String {
+    const INDENT: usize = 7;
+    let width = width.saturating_sub(INDENT).max(20);
+    let mut paragraphs: Vec<Vec<&str>> = vec![Vec::new()];
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() {
+            paragraphs.last_mut().unwrap().push(line);
+        } else if paragraphs.last().is_some_and(|paragraph| !paragraph.is_empty()) {
+            paragraphs.push(Vec::new());
+        }
+    }
+    paragraphs.retain(|paragraph| !paragraph.is_empty());
+
+    let mut page = String::with_capacity(text.len() * 2);
+    let mut follows_heading = false;
+    for paragraph in paragraphs {
+        let heading = match paragraph[..] {
+            [line] if line.starts_with('#') => Some(line.trim_start_matches('#').trim()),
+            [line] if list_marker(line) == 0 && line.chars().count() <= 60
+                && !line.ends_with(|c: char| ".,;:!?。…".contains(c)) => Some(line),
+            _ => None
+        };
+        // A paragraph directly follows its heading, and is otherwise set apart by a blank line.
+        let beneath = follows_heading && heading.is_none();
+        if !page.is_empty() && !beneath { page.push('\n'); }
+        follows_heading = heading.is_some();
+        if let Some(heading) = heading {
+            let line = if bold { format!("\u{1b}[1m{}\u{1b}[0m\n", heading) } else { format!("{}\n", heading) };
+            page.push_str(line.as_str());
+            continue;
+        }
+        // Lines are joined into one paragraph to be re-flowed, except where a list item begins.
+        let mut items: Vec<String> = Vec::new();
+        for line in paragraph {
+            match items.last_mut() {
+                Some(item) if list_marker(line) == 0 => { item.push(' '); item.push_str(line); },
+                _ => items.push(line.to_string())
+            }
+        }
+        for item in items {
+            let marker = list_marker(item.as_str());
+            let hanging = item[..marker].chars().count();
+            let wrapped = wrap_text(&item[marker..], width - hanging.min(width / 2));
+            for (index, line) in wrapped.lines().enumerate() {
+                let lead = if index == 0 { item[..marker].to_string() } else { " ".repeat(hanging) };
+                page.push_str(format!("{}{}{}\n", " ".repeat(INDENT), lead, line).as_str());
+            }
+        }
+    }
+    page
+}
+
+/// The length of the list marker which `line` begins with, such as `- `, `• ` or `2. `, or 0 if it begins
+/// with none.
+pub fn list_marker(line: &str) -> usize {
+    let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(line.len());
+    let rest = &line[digits..];
+    let marker = if digits > 0 && digits < 4 {
+        rest.chars().next().filter(|&c| c == '.' || c == ')').map(char::len_utf8)
+    } else if digits == 0 {
+        rest.chars().next().filter(|&c| "-*•–".contains(c)).map(char::len_utf8)
+    } else {
+        None
+    };
+    match marker {
+        Some(length) if rest[length..].starts_with(' ') => digits + length + 1,
+        _ => 0
+    }
+}
+
+/// Write `text` to standard output, through the user's pager if `paging` and standard output is a terminal.
+/// The pager is $PAGER, or less, which is told to pass the bold of --format man through and to quit at once
+/// if the text fits on one screen unless LESS says otherwise. If the pager cannot be started, the text is
+/// written straight to standard output instead.
+pub fn show(text: &str, paging: bool) -> std::io::Result<()> {
+    if paging && std::io::stdout().is_terminal() {
+        let pager = std::env::var("PAGER").ok().filter(|pager| !pager.trim().is_empty()).unwrap_or_else(|| "less".to_string());
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(pager.as_str()).stdin(Stdio::piped());
+        if std::env::var_os("LESS").is_none() { command.env("LESS", "FRX"); }
+        if let Ok(mut child) = command.spawn() {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The reader quitting the pager before reaching the end is not an error.
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+            return Ok(());
+        }
+    }
+    std::io::stdout().write_all(text.as_bytes())
+}
+
+/// Describe the detected source language, how closely the translation memory matched if the translation came
+/// from it, and the alternative translations on standard error, keeping standard output free for the
+/// translation itself.
+pub fn print_details(result: &TranslationResult) {
+    match (result.source_language.as_ref(), result.confidence) {
+        (Some(language), Some(confidence)) => eprintln!("Detected language: {} ({:.0}% confidence)", language.to_uppercase(), confidence * 100.0),
+        (Some(language), None)             => eprintln!("Detected language: {}", language.to_uppercase()),
+        (None, _)                          => eprintln!("Detected language: unknown")
+    }
+    if let Some(similarity) = result.similarity {
+        eprintln!("Translation memory: {:.0}% match", similarity * 100.0);
+    }
+    if !result.alternatives.is_empty() {
+        eprintln!("Alternatives:");
+        for alternative in &result.alternatives {
+            eprintln!("    {} → {}", alternative.source, alternative.candidates.join(" | "));
+        }
+    }
+}
+
+/// Write a failure to standard error as a JSON object on a line of its own, with the members `kind`, `message`,
+/// `item`, the index of the language, line or segment which failed if there was one, and `retryable`.
+pub fn print_error_json(kind: &str, message: &str, item: Option<usize>, retryable: bool) {
+    eprintln!("{}", Json::Object(vec![
+        ("kind".to_string(),      kind.into()),
+        ("message".to_string(),   message.into()),
+        ("item".to_string(),      item.map(|item| item as f64).into()),
+        ("retryable".to_string(), retryable.into()),
+    ]));
+}
+
+/// Report the failure of a translation on standard error, as a line of text prefixed with `label` or as JSON.
+pub fn report_error(errors: Errors, why: &Error, item: Option<usize>, label: &str) {
+    match errors {
+        Errors::Text => eprintln!("rust-google-translate: {}{}", label, why),
+        Errors::Json => print_error_json(why.kind(), why.to_string().as_str(), item, why.is_transient())
+    }
+}
+
+/// How failures are reported on standard error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Errors {
+    Text,
+    /// One JSON object to a line, for pipelines and CI jobs.
+    Json,
+}
+
+/// How the translation made by -c or file, or the language found by detect, is printed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    /// Laid out like a manual page, with bold headings and indented paragraphs.
+    Man,
+}
+
+/// Describes what should happen to a translation that is longer than the requested maximum length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overflow {
+    /// Cut the translation short and mark the cut with an ellipsis.
+    Truncate,
+    /// Refuse to output the translation at all.
+    Error,
+    /// Split the translation into several pieces which each fit within the limit.
+    Chunk
+}
+
+#[test]
+fn test_limit_length() {
+    assert_eq!(limit_length("Short", 10, Overflow::Error), Ok(vec!["Short".to_string()]));
+    assert_eq!(limit_length("I am not you.", 6, Overflow::Truncate), Ok(vec!["I am …".to_string()]));
+    assert!(limit_length("I am not you.", 6, Overflow::Error).is_err());
+    assert_eq!(limit_length("I am not you. You are not me.", 13, Overflow::Chunk),
+        Ok(vec!["I am not you.".to_string(), "You are not".to_string(), "me.".to_string()]));
+}
+
+#[test]
+fn test_wrap_text() {
+    assert_eq!(wrap_text("I am not you. You are not me.", 13).as_str(), "I am not you.\nYou are not\nme.");
+    assert_eq!(wrap_text("我不是你。你不是我。", 10).as_str(), "我不是你。\n你不是我。");
+    assert_eq!(wrap_text("cafe\u{301} cafe\u{301}", 4).as_str(), "cafe\u{301}\ncafe\u{301}");
+}
+
+#[test]
+fn test_render_man() {
+    let text = "# Installation\n\nDownload the  archive\nand unpack it.\n- Run make\n2. Run make install\n\nUsage\n\nRun it.\nDone.";
+    assert_eq!(render_man(text, 50, false).as_str(), "Installation\n       Download the archive and unpack it.\n       - Run make\n       2. Run make install\n\n\
+        Usage\n       Run it. Done.\n");
+    assert_eq!(render_man("Read the manual, which is long.", 30, true).as_str(), "       Read the manual, which\n       is long.\n");
+    assert_eq!(render_man("Title", 30, true).as_str(), "\u{1b}[1mTitle\u{1b}[0m\n");
+    assert_eq!(list_marker("- item"), 2);
+    assert_eq!(list_marker("12) item"), 4);
+    assert_eq!(list_marker("2024. A year"), 0);
+}
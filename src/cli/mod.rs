@@ -0,0 +1,10 @@
+//! What the command line front end does besides parsing its arguments: batch translation, running commands,
+//! laying out and encoding output, showing progress, reviewing translations and gathering the translator's settings.
+
+pub mod batch;
+pub mod encoding;
+pub mod exec;
+pub mod format;
+pub mod progress;
+pub mod review;
+pub mod settings;
@@ -0,0 +1,159 @@
+//! Progress bars on standard error for the jobs which take a while, `file` and `--lines`, with how long is left
+//! and why the job has stalled when a request is being tried again.
+
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rust_google_translate::{Error, RetryObserver};
+
+use cli::format::char_width;
+
+/// The columns taken by the bar itself, between its brackets.
+const BAR_WIDTH: usize = 24;
+
+/// How far a job has got, drawn on a line of standard error which is redrawn as it moves on. A `Progress`
+/// is cheap to clone, and clones draw the same bar.
+#[derive(Clone)]
+pub struct Progress {
+    /// `None` when nothing is drawn, such as when standard error is not a terminal.
+    state: Option<Arc<Mutex<State>>>,
+}
+
+struct State {
+    label: String,
+    /// How much there is to do in all, if it is known, in whatever the job counts, such as bytes.
+    total: Option<usize>,
+    done: usize,
+    /// What the job counts, such as `lines`, shown when there is no total.
+    unit: &'static str,
+    started: Instant,
+    /// Why the job has stalled, until it moves on again.
+    status: Option<String>,
+    /// Whether the bar is on the screen.
+    drawn: bool,
+}
+
+impl Progress {
+    /// A bar labelled `label` for a job with `total` to do, counted in `unit`, which is drawn only if
+    /// standard error is a terminal.
+    pub fn new(label: &str, total: Option<usize>, unit: &'static str) -> Progress {
+        if !std::io::stderr().is_terminal() { return Progress::hidden() }
+        let state = State { label: label.to_string(), total, done: 0, unit, started: Instant::now(), status: None, drawn: false };
+        Progress { state: Some(Arc::new(Mutex::new(state))) }
+    }
+
+    /// A bar which is never drawn.
+    pub fn hidden() -> Progress {
+        Progress { state: None }
+    }
+
+    /// Say how much there is to do in all, once it is known.
+    pub fn set_total(&self, total: usize) {
+        self.update(|state| state.total = Some(total));
+    }
+
+    /// Move the job on to `done`.
+    pub fn set(&self, done: usize) {
+        self.update(|state| { state.done = done; state.status = None; });
+    }
+
+    /// Move the job on by `count`.
+    pub fn advance(&self, count: usize) {
+        self.update(|state| { state.done += count; state.status = None; });
+    }
+
+    /// Say that a request failed with `why` and will be tried again in `wait`.
+    pub fn retrying(&self, why: &Error, wait: Duration) {
+        let status = format!("retrying in {}s: {}", wait.as_secs().max(1), why);
+        self.update(|state| state.status = Some(status));
+    }
+
+    /// An observer for the translator which shows its retries on the bar, if the bar is drawn.
+    pub fn observer(&self) -> Option<RetryObserver> {
+        let progress = self.clone();
+        self.state.as_ref().map(|_| Arc::new(move |why: &Error, wait: Duration| progress.retrying(why, wait)) as RetryObserver)
+    }
+
+    /// Take the bar off the screen, so that something else may be written; it is drawn again as the job moves on.
+    pub fn clear(&self) {
+        if let Some(state) = self.state.as_ref() {
+            let mut state = state.lock().unwrap();
+            if state.drawn {
+                eprint!("\r\x1b[K");
+                state.drawn = false;
+            }
+        }
+    }
+
+    fn update<F: FnOnce(&mut State)>(&self, change: F) {
+        let state = match self.state.as_ref() {
+            Some(state) => state,
+            None        => return
+        };
+        let mut state = state.lock().unwrap();
+        change(&mut state);
+        let width = std::env::var("COLUMNS").ok().and_then(|columns| columns.parse::<usize>().ok()).filter(|&x| x > 0).unwrap_or(80);
+        let line = render(&state, state.started.elapsed(), width);
+        let stderr = std::io::stderr();
+        let mut stderr = stderr.lock();
+        let _ = write!(stderr, "\r\x1b[K{}", line);
+        let _ = stderr.flush();
+        state.drawn = true;
+    }
+}
+
+/// The line which shows `state` after `elapsed`, cut short to fit in `width` columns.
+fn render(state: &State, elapsed: Duration, width: usize) -> String {
+    let mut line = match state.total {
+        Some(total) => {
+            let fraction = if total == 0 { 1.0 } else { (state.done as f64 / total as f64).min(1.0) };
+            let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+            // How long is left is guessed from how long the job has taken so far.
+            let eta = match state.done {
+                0 => "--".to_string(),
+                done => format_duration(elapsed.mul_f64(total.saturating_sub(done) as f64 / done as f64))
+            };
+            format!("{} [{}{}] {:>3}%  ETA {}", state.label, "#".repeat(filled), "-".repeat(BAR_WIDTH - filled),
+                (fraction * 100.0).floor(), eta)
+        },
+        None => format!("{} {} {}", state.label, state.done, state.unit)
+    };
+    if let Some(status) = state.status.as_ref() {
+        line.push_str(format!("  ({})", status).as_str());
+    }
+    // The bar is redrawn over itself, which only works as long as it never wraps onto a second line.
+    let mut columns = 0;
+    let end = line.char_indices().find(|&(_, character)| {
+        columns += char_width(character);
+        columns >= width
+    }).map_or(line.len(), |(index, _)| index);
+    line.truncate(end);
+    line
+}
+
+/// Write `duration` as minutes and seconds, such as `1m05s`, or hours and minutes once it is that long.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=59    => format!("{}s", seconds),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _         => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+#[test]
+fn test_render() {
+    let mut state = State { label: "manual.txt".to_string(), total: Some(400), done: 100, unit: "bytes", started: Instant::now(),
+        status: None, drawn: false };
+    assert_eq!(render(&state, Duration::from_secs(20), 80), "manual.txt [######------------------]  25%  ETA 1m00s");
+    state.status = Some("retrying in 4s: Google is limiting requests (HTTP 429); try again in 4 seconds".to_string());
+    assert_eq!(render(&state, Duration::from_secs(20), 80),
+        "manual.txt [######------------------]  25%  ETA 1m00s  (retrying in 4s: Google ");
+    state.done = 0;
+    state.status = None;
+    assert_eq!(render(&state, Duration::from_secs(20), 80), "manual.txt [------------------------]   0%  ETA --");
+    let lines = State { label: "--lines".to_string(), total: None, done: 12, unit: "lines", started: Instant::now(), status: None, drawn: false };
+    assert_eq!(render(&lines, Duration::from_secs(3), 80), "--lines 12 lines");
+    assert_eq!(format_duration(Duration::from_secs(4000)), "1h06m");
+}
@@ -0,0 +1,128 @@
+//! Reviewing the translation of a file a segment at a time with --interactive-review, for those who must vouch
+//! for every word of it: each translation is shown beside its source, to be accepted, edited or skipped before
+//! it is written.
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::{self, Command};
+
+/// What the reviewer said to do with a translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Answer {
+    Accept,
+    Edit,
+    /// Keep the segment as it was, untranslated.
+    Skip,
+    /// Accept this translation and every one after it without asking.
+    AcceptRest,
+}
+
+/// Asks whoever is at the terminal about each translation.
+pub struct Reviewer {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    /// The editor which translations are edited in, or `None` to type the new translation on a line instead.
+    editor: Option<String>,
+    /// Whether every translation from now on is accepted.
+    accepting: bool,
+}
+
+impl Reviewer {
+    /// A reviewer answering on standard input, asked on standard error, who edits translations in $VISUAL or
+    /// $EDITOR if either is set.
+    pub fn terminal() -> Reviewer {
+        let editor = env::var("VISUAL").ok().filter(|editor| !editor.trim().is_empty())
+            .or_else(|| env::var("EDITOR").ok().filter(|editor| !editor.trim().is_empty()));
+        Reviewer::new(Box::new(io::BufReader::new(io::stdin())), Box::new(io::stderr()), editor)
+    }
+
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>, editor: Option<String>) -> Reviewer {
+        Reviewer { input, output, editor, accepting: false }
+    }
+
+    /// Show `translation` beside `source`, the segment at `line`, and return what is to be written in its
+    /// place: the translation as it is or as edited, or `None` to keep the source. Fails if the reviewer
+    /// leaves, by closing the input, before answering.
+    pub fn review(&mut self, line: usize, source: &str, translation: &str) -> io::Result<Option<String>> {
+        if self.accepting { return Ok(Some(translation.to_string())) }
+        writeln!(self.output, "\nLine {}:\n{}\n\n{}\n", line, indent(source), indent(translation))?;
+        loop {
+            write!(self.output, "Accept, edit, skip, or accept the rest? [a/e/s/r] ")?;
+            self.output.flush()?;
+            let answer = self.read_line()?;
+            match parse_answer(answer.as_str()) {
+                Some(Answer::Accept)     => return Ok(Some(translation.to_string())),
+                Some(Answer::Skip)       => return Ok(None),
+                Some(Answer::AcceptRest) => {
+                    self.accepting = true;
+                    return Ok(Some(translation.to_string()))
+                },
+                Some(Answer::Edit)       => return self.edit(translation).map(Some),
+                None => writeln!(self.output, "Answer a to accept the translation, e to edit it, s to keep the original \
+                                               or r to accept this and every translation after it.")?
+            }
+        }
+    }
+
+    /// Let the reviewer change `translation`, in their editor if they have one and on a line of its own otherwise.
+    fn edit(&mut self, translation: &str) -> io::Result<String> {
+        let editor = match self.editor.as_ref() {
+            Some(editor) => editor,
+            None => {
+                write!(self.output, "Translation: ")?;
+                self.output.flush()?;
+                return self.read_line();
+            }
+        };
+        let path = env::temp_dir().join(format!("rust-google-translate-review-{}.txt", process::id()));
+        fs::write(&path, format!("{}\n", translation))?;
+        // The editor may be given with arguments of its own, such as 'code --wait', so it is run by the shell.
+        let status = Command::new("sh").arg("-c").arg(format!("{} \"$1\"", editor)).arg("sh").arg(&path).status();
+        let edited = status.and_then(|status| match status.success() {
+            true  => fs::read_to_string(&path),
+            false => Err(io::Error::other(format!("{} exited with {}", editor, status)))
+        });
+        let _ = fs::remove_file(&path);
+        Ok(edited?.trim_end_matches(['\n', '\r']).to_string())
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the review ended before every segment was reviewed"));
+        }
+        Ok(line.trim_end_matches(['\n', '\r']).to_string())
+    }
+}
+
+/// The answer which `text` gives, an empty answer accepting the translation.
+fn parse_answer(text: &str) -> Option<Answer> {
+    match text.trim().to_lowercase().as_str() {
+        "" | "a" | "accept" => Some(Answer::Accept),
+        "e" | "edit"        => Some(Answer::Edit),
+        "s" | "skip"        => Some(Answer::Skip),
+        "r" | "rest"        => Some(Answer::AcceptRest),
+        _ => None
+    }
+}
+
+/// Indent each line of `text`, so that it stands apart from the prompts.
+fn indent(text: &str) -> String {
+    text.lines().map(|line| format!("    {}", line)).collect::<Vec<String>>().join("\n")
+}
+
+#[test]
+fn test_review() {
+    let answers = "a\nx\ns\ne\nHi, everyone!\nr\n";
+    let mut reviewer = Reviewer::new(Box::new(io::Cursor::new(answers)), Box::new(io::sink()), None);
+    assert_eq!(reviewer.review(1, "Saluton", "Hello").unwrap().as_deref(), Some("Hello"));
+    assert_eq!(reviewer.review(3, "Ĝis", "Bye").unwrap(), None);
+    assert_eq!(reviewer.review(5, "Saluton al ĉiuj!", "Hello to all!").unwrap().as_deref(), Some("Hi, everyone!"));
+    assert_eq!(reviewer.review(7, "Dankon", "Thanks").unwrap().as_deref(), Some("Thanks"));
+    assert_eq!(reviewer.review(9, "Bonvolu", "Please").unwrap().as_deref(), Some("Please"));
+
+    let mut leaving = Reviewer::new(Box::new(io::Cursor::new("")), Box::new(io::sink()), None);
+    assert_eq!(leaving.review(1, "Saluton", "Hello").unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(parse_answer(" Accept\n"), Some(Answer::Accept));
+}
@@ -0,0 +1,239 @@
+//! The translator's settings from the command line and the environment, and where files are kept.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_google_translate::{
+    AuditLog,
+    Glossary,
+    JobLedger,
+    Proxy,
+    Rates,
+    ResponseCache,
+    RetryObserver,
+    RetryPolicy,
+    Sensitive,
+    Throttle,
+    TlsConfig,
+    TranslationMemory,
+    Translator,
+    DEFAULT_ENDPOINT
+};
+
+pub const AUDIT_LOG_VARIABLE: &'static str = "RUST_GOOGLE_TRANSLATE_AUDIT_LOG";
+
+pub const ENDPOINT_VARIABLE: &'static str = "RUST_GOOGLE_TRANSLATE_ENDPOINT";
+
+pub const REDACT_VARIABLE: &'static str = "RUST_GOOGLE_TRANSLATE_REDACT";
+
+/// The settings, gathered from the command line and the environment, of the translator used by the commands
+/// which do not open the GUI.
+pub struct TranslatorOptions {
+    pub timeout: Option<Duration>,
+    pub chunk_length: usize,
+    /// Whether the chunks of a long text are sent at the same time rather than one after another.
+    pub concurrent_chunks: bool,
+    pub audit_path: Option<String>,
+    /// Whether the audit log is turned off even if a path is given for it.
+    pub no_log: bool,
+    pub tls: TlsConfig,
+    pub proxy: Option<Proxy>,
+    pub retry: RetryPolicy,
+    pub endpoints: Vec<String>,
+    pub user_agents: Vec<String>,
+    pub no_cache: bool,
+    pub cache_ttl: Duration,
+    pub throttle: Option<Arc<Throttle>>,
+    /// Whether the translation memory is used.
+    pub memory: bool,
+    pub memory_threshold: f64,
+    /// The kinds of sensitive text which are masked before anything is sent.
+    pub redact: Vec<Sensitive>,
+    pub glossary: Glossary,
+    /// The languages which the input is likely to be written in, most likely first.
+    pub hints: Vec<String>,
+    /// Told when a request is about to be tried again, such as by the progress bar of a long job.
+    pub on_retry: Option<RetryObserver>,
+}
+
+/// The translator which `options` describe, with its audit log, cache and translation memory opened.
+pub fn build_translator(options: &TranslatorOptions) -> Translator {
+    Translator::builder().timeout(options.timeout).chunk_length(options.chunk_length)
+        .concurrent_chunks(options.concurrent_chunks)
+        .audit_log(open_audit_log(options.audit_path.as_deref(), options.no_log))
+        .tls(options.tls.clone()).proxy(options.proxy.clone()).retry(options.retry)
+        .endpoints(options.endpoints.clone()).user_agents(options.user_agents.clone())
+        .cache(response_cache(options.no_cache, options.cache_ttl)).throttle(options.throttle.clone())
+        .memory(open_memory(options.memory), options.memory_threshold).redact(options.redact.clone())
+        .glossary(options.glossary.clone()).hints(options.hints.clone())
+        .on_retry(options.on_retry.clone()).build()
+}
+
+/// Open the audit log at `path`, unless there is none or logging has been turned off. If it cannot be
+/// opened, nothing may be sent, so the program exits.
+pub fn open_audit_log(path: Option<&str>, disabled: bool) -> Option<Arc<AuditLog>> {
+    let path = path.filter(|path| !path.is_empty() && !disabled)?;
+    match AuditLog::open(path) {
+        Ok(log) => Some(Arc::new(log)),
+        Err(why) => {
+            eprintln!("rust-google-translate: unable to open the audit log {}: {}", path, why);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The endpoints listed, separated by commas, in the environment variable ENDPOINT_VARIABLE, or the default
+/// endpoint if there are none.
+pub fn environment_endpoints() -> Vec<String> {
+    let endpoints = std::env::var(ENDPOINT_VARIABLE).map(|endpoints| split_list(endpoints.as_str())).unwrap_or_default();
+    if endpoints.is_empty() { vec![DEFAULT_ENDPOINT.to_string()] } else { endpoints }
+}
+
+/// The host name of an endpoint such as `https://translate.googleapis.com:443/path`.
+pub fn endpoint_host(endpoint: &str) -> &str {
+    let authority = endpoint.splitn(2, "://").last().unwrap_or("").split('/').next().unwrap_or("");
+    match authority.rfind(':') {
+        Some(index) if !authority.ends_with(']') => &authority[..index],
+        _ => authority
+    }.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// The kinds of sensitive text to redact listed in the environment variable REDACT_VARIABLE, as they are given
+/// to --redact, if any. A list which cannot be understood is ignored with a warning.
+pub fn environment_redact() -> Vec<Sensitive> {
+    let list = match std::env::var(REDACT_VARIABLE) {
+        Ok(list) => list,
+        Err(_)   => return Vec::new()
+    };
+    Sensitive::from_list(list.as_str()).unwrap_or_else(|| {
+        eprintln!("rust-google-translate: warning: ignoring {}: expected a list of email, phone and card, or all", REDACT_VARIABLE);
+        Vec::new()
+    })
+}
+
+/// The proxy configured in the environment for the first of `endpoints`, if any. A proxy which cannot be
+/// understood is ignored with a warning.
+pub fn environment_proxy(endpoints: &[String]) -> Option<Proxy> {
+    let host = endpoints.first().map_or(endpoint_host(DEFAULT_ENDPOINT), |endpoint| endpoint_host(endpoint));
+    Proxy::from_env(host).unwrap_or_else(|why| {
+        eprintln!("rust-google-translate: warning: ignoring the proxy in the environment: {}", why);
+        None
+    })
+}
+
+/// Split a comma-separated list, such as the languages `EN,DE,FR` or a list of endpoint URLs, leaving out
+/// empty items.
+pub fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect()
+}
+
+/// Read a list of names to protect from translation, one per line.
+pub fn read_names(path: &str) -> std::io::Result<Vec<String>> {
+    let mut text = String::new();
+    File::open(path)?.read_to_string(&mut text)?;
+    Ok(text.lines().map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect())
+}
+
+/// Where the exchange rates last given with --rates are kept.
+pub fn rates_cache() -> Option<PathBuf> {
+    cache_directory().map(|cache| cache.join("rates"))
+}
+
+/// The directory that this program keeps its cached files in, under $XDG_CACHE_HOME or ~/.cache.
+pub fn cache_directory() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache.join("rust-google-translate"))
+}
+
+/// The directory that this program keeps the files it is asked to keep in, under $XDG_DATA_HOME or
+/// ~/.local/share.
+pub fn data_directory() -> Option<PathBuf> {
+    let data = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+    Some(data.join("rust-google-translate"))
+}
+
+/// The cache of Google's responses, keeping them for `ttl`, unless caching is `disabled` or there is nowhere
+/// to keep it.
+pub fn response_cache(disabled: bool, ttl: Duration) -> Option<Arc<ResponseCache>> {
+    if disabled || ttl == Duration::from_secs(0) { return None }
+    cache_directory().map(|cache| Arc::new(ResponseCache::new(cache.join("responses"), ttl)))
+}
+
+/// The ledger of the job described by `job`, kept among the cached files, which carries on from where the last
+/// run of the same job stopped if `resume`. Without one, the job is still carried out, but cannot be resumed.
+pub fn open_ledger(job: &str, resume: bool) -> Option<JobLedger> {
+    let directory = match cache_directory() {
+        Some(cache) => cache.join("jobs"),
+        None => {
+            eprintln!("rust-google-translate: warning: there is nowhere to keep a ledger of the job, so it cannot be resumed");
+            return None
+        }
+    };
+    match JobLedger::open(&directory, job, resume) {
+        Ok(ledger) => Some(ledger),
+        Err(why) => {
+            eprintln!("rust-google-translate: warning: unable to keep a ledger of the job, so it cannot be resumed: {}", why);
+            None
+        }
+    }
+}
+
+/// The translation memory, if it is `enabled`. A memory which cannot be read is fatal, as translating without
+/// it would silently ask Google for what it already holds.
+pub fn open_memory(enabled: bool) -> Option<Arc<TranslationMemory>> {
+    if !enabled {
+        return None;
+    }
+    match memory_file().map(TranslationMemory::open) {
+        Some(Ok(memory)) => Some(Arc::new(memory)),
+        Some(Err(why)) => {
+            eprintln!("rust-google-translate: unable to open the translation memory: {}", why);
+            std::process::exit(1);
+        },
+        None => {
+            eprintln!("rust-google-translate: there is nowhere to keep the translation memory");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The file that the translation memory is kept in, whose directory is created if it does not exist.
+pub fn memory_file() -> Option<PathBuf> {
+    let directory = data_directory()?;
+    let _ = fs::create_dir_all(&directory);
+    Some(directory.join("memory.jsonl"))
+}
+
+/// Read a table of exchange rates, keeping a copy of it in the cache for later use. Failing to update the
+/// cache only warrants a warning, as the rates can still be used this time.
+pub fn read_rates(path: &str) -> std::io::Result<Rates> {
+    let mut table = String::new();
+    File::open(path)?.read_to_string(&mut table)?;
+    let rates = Rates::parse(table.as_str()).map_err(|why| std::io::Error::new(std::io::ErrorKind::InvalidData, why))?;
+    if let Some(cache) = rates_cache() {
+        let written = cache.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&cache, table.as_bytes()));
+        if let Err(why) = written {
+            eprintln!("rust-google-translate: warning: unable to cache exchange rates in {}: {}", cache.display(), why);
+        }
+    }
+    Ok(rates)
+}
+
+/// Read the exchange rates last given with --rates.
+pub fn read_cached_rates() -> std::io::Result<Rates> {
+    let cache = rates_cache().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no cache directory"))?;
+    let table = fs::read_to_string(cache)?;
+    Rates::parse(table.as_str()).map_err(|why| std::io::Error::new(std::io::ErrorKind::InvalidData, why))
+}
+
+#[test]
+fn test_endpoint_host() {
+    assert_eq!(endpoint_host(DEFAULT_ENDPOINT), "translate.googleapis.com");
+    assert_eq!(endpoint_host("http://[::1]:8080/google"), "::1");
+    assert_eq!(endpoint_host("mirror.example.com"), "mirror.example.com");
+}
@@ -0,0 +1,163 @@
+//! Annotation of the amounts of money in a text with their value in another currency.
+
+use std::collections::BTreeMap;
+
+use localize::Locale;
+
+/// Symbols which stand for a currency, most specific first so that `US$` is not read as `$`.
+const SYMBOLS: &'static [(&'static str, &'static str)] = &[
+    ("US$", "USD"), ("C$", "CAD"), ("A$", "AUD"), ("$", "USD"), ("€", "EUR"), ("£", "GBP"), ("¥", "JPY"),
+    ("₹", "INR"), ("₩", "KRW"), ("₽", "RUB"), ("₺", "TRY"), ("₴", "UAH"), ("zł", "PLN"), ("Kč", "CZK"),
+];
+
+/// A table of exchange rates, each giving the value of one unit of a common base currency in another
+/// currency. Which currency is the base does not matter, so long as every rate is given against it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rates {
+    rates: BTreeMap<String, f64>,
+}
+
+impl Rates {
+    /// Read a table with one currency on each line, given by its ISO 4217 code and its rate, such as
+    /// `EUR 0.92`. Blank lines and lines starting with `#` are ignored.
+    pub fn parse(table: &str) -> Result<Rates, String> {
+        let mut rates = BTreeMap::new();
+        for (index, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let mut fields = line.split_whitespace();
+            let code = fields.next().filter(|code| code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()));
+            let rate = fields.next().and_then(|rate| rate.parse::<f64>().ok()).filter(|&rate| rate > 0.0);
+            match (code, rate, fields.next()) {
+                (Some(code), Some(rate), None) => { rates.insert(code.to_uppercase(), rate); },
+                _ => return Err(format!("line {}: expected a currency code and a positive rate", index + 1))
+            }
+        }
+        Ok(Rates { rates })
+    }
+
+    /// Whether the table has a rate for the currency with the given code, which may be in either case.
+    pub fn contains(&self, currency: &str) -> bool {
+        self.rates.contains_key(currency.to_uppercase().as_str())
+    }
+
+    /// Convert `amount` from one currency into another, if the table has rates for both.
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        let from = self.rates.get(from.to_uppercase().as_str())?;
+        let to = self.rates.get(to.to_uppercase().as_str())?;
+        Some(amount / from * to)
+    }
+}
+
+/// Follow each amount of money in `text` with its value in the currency `to`, such as `$20 (≈ 18,40 EUR)`.
+/// Amounts are recognised by a currency symbol or an ISO 4217 code in `rates` on either side of them, and
+/// are read according to `from` and written according to `locale`. Amounts which are already in `to`, or
+/// whose currency has no rate, are left as they are.
+pub fn annotate_currency(text: &str, rates: &Rates, to: &str, from: &Locale, locale: &Locale) -> String {
+    let to = to.to_uppercase();
+    let mut annotated = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|character: char| character.is_ascii_digit()) {
+        // A number which continues a word, such as the 3 in 'MP3', is not an amount.
+        let word = rest[..start].chars().next_back().is_some_and(char::is_alphanumeric)
+            && currency_before(&rest[..start], rates).is_none();
+        let (length, amount) = match read_amount(&rest[start..], from) {
+            Some(amount) if !word => amount,
+            _ => {
+                let length = start + rest[start..].find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len() - start);
+                annotated.push_str(&rest[..length]);
+                rest = &rest[length..];
+                continue
+            }
+        };
+        let prefix = currency_before(rest[..start].trim_end_matches(' '), rates);
+        annotated.push_str(&rest[..start + length]);
+        rest = &rest[start + length..];
+
+        let currency = match prefix {
+            Some(currency) => Some(currency),
+            None => {
+                let spaced = rest.trim_start_matches(' ');
+                currency_after(spaced, rates).map(|(currency, length)| {
+                    let end = rest.len() - spaced.len() + length;
+                    annotated.push_str(&rest[..end]);
+                    rest = &rest[end..];
+                    currency
+                })
+            }
+        };
+        if let Some(value) = currency.filter(|currency| *currency != to).and_then(|currency| rates.convert(amount, currency.as_str(), to.as_str())) {
+            annotated.push_str(format!(" (≈ {} {})", format_money(value, locale), to).as_str());
+        }
+    }
+    annotated.push_str(rest);
+    annotated
+}
+
+/// The currency whose symbol or code ends `text`, if any.
+fn currency_before(text: &str, rates: &Rates) -> Option<String> {
+    if let Some(&(_, code)) = SYMBOLS.iter().find(|&&(symbol, _)| text.ends_with(symbol)) {
+        return Some(code.to_string());
+    }
+    let start = text.char_indices().rev().find(|&(_, c)| !c.is_ascii_uppercase()).map_or(0, |(index, c)| index + c.len_utf8());
+    let code = &text[start..];
+    Some(code.to_string()).filter(|code| code.len() == 3 && rates.contains(code))
+}
+
+/// The currency whose symbol or code starts `text`, if any, along with the length of the symbol or code.
+fn currency_after(text: &str, rates: &Rates) -> Option<(String, usize)> {
+    if let Some(&(symbol, code)) = SYMBOLS.iter().find(|&&(symbol, _)| text.starts_with(symbol)) {
+        return Some((code.to_string(), symbol.len()));
+    }
+    let length = text.find(|c: char| !c.is_ascii_uppercase()).unwrap_or(text.len());
+    let after = text[length..].chars().next();
+    Some(text[..length].to_string()).filter(|code| code.len() == 3 && rates.contains(code) && !after.is_some_and(char::is_alphanumeric))
+        .map(|code| (code, length))
+}
+
+/// Read an amount at the start of `input` according to `locale`, returning its length and its value.
+fn read_amount(input: &str, locale: &Locale) -> Option<(usize, f64)> {
+    let digits = |input: &str| input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let mut end = digits(input);
+    let mut number = input[..end].to_string();
+    // Further groups of exactly three digits, each following the grouping character.
+    while input[end..].starts_with(locale.grouping) && digits(&input[end + 1..]) == 3 {
+        number.push_str(&input[end + 1..end + 4]);
+        end += 4;
+    }
+    if input[end..].starts_with(locale.decimal) && digits(&input[end + 1..]) > 0 {
+        let length = digits(&input[end + 1..]);
+        number.push('.');
+        number.push_str(&input[end + 1..end + 1 + length]);
+        end += 1 + length;
+    }
+    number.parse::<f64>().ok().map(|value| (end, value))
+}
+
+/// Write an amount of money to two decimal places, grouping its digits according to `locale`.
+fn format_money(value: f64, locale: &Locale) -> String {
+    let written = format!("{:.2}", value);
+    let (whole, fraction) = written.split_at(written.len() - 3);
+    let mut grouped = String::with_capacity(written.len() + whole.len() / 3);
+    for (index, digit) in whole.chars().enumerate() {
+        if index != 0 && (whole.len() - index) % 3 == 0 { grouped.push(locale.grouping); }
+        grouped.push(digit);
+    }
+    grouped.push(locale.decimal);
+    grouped.push_str(&fraction[1..]);
+    grouped
+}
+
+#[test]
+fn test_annotate_currency() {
+    let rates = Rates::parse("# Against the US dollar\nUSD 1\nEUR 0.9\nGBP 0.8\n\nJPY 150").unwrap();
+    let english = Locale::for_language("EN");
+    let german = Locale::for_language("DE");
+    assert_eq!(annotate_currency("It costs $1,234.50 or £20.", &rates, "eur", &english, &german),
+        "It costs $1,234.50 (≈ 1.111,05 EUR) or £20 (≈ 22,50 EUR).");
+    assert_eq!(annotate_currency("Es kostet 20 € und 300 JPY, nicht 5 Äpfel.", &rates, "USD", &german, &english),
+        "Es kostet 20 € (≈ 22.22 USD) und 300 JPY (≈ 2.00 USD), nicht 5 Äpfel.");
+    assert_eq!(annotate_currency("Pay 10 EUR, MP3 or EUR10.", &rates, "EUR", &english, &english), "Pay 10 EUR, MP3 or EUR10.");
+    assert_eq!(annotate_currency("5 CHF and 5 EURO", &rates, "USD", &english, &english), "5 CHF and 5 EURO");
+    assert!(Rates::parse("EUR zero").is_err() && Rates::parse("EURO 1").is_err());
+}
@@ -0,0 +1,112 @@
+//! Recognition of emoji, emoticons and kaomoji, which carry no language of their own.
+//!
+//! They pass through translation untouched, but a short message which is mostly made of them is easily
+//! mistaken for another language, so they may be stripped before the text is sent.
+
+#[cfg(test)]
+use json::Json;
+#[cfg(test)]
+use parse::parse_response;
+#[cfg(test)]
+use query::percent_encode;
+
+/// Emoticons written with ASCII punctuation, which are only recognised when they stand on their own.
+const EMOTICONS: &'static [&'static str] = &[
+    ":-)", ":)", ":-(", ":(", ";-)", ";)", ":-D", ":D", ":-P", ":P", ":-p", ":p", ":-O", ":O", ":-o", ":o",
+    ":'(", ":-/", ":/", ":-|", ":|", ":-*", ":*", "<3", "</3", "xD", "XD", "^_^", "^^", "-_-", "o_O", "O_o", "T_T",
+];
+
+/// Kaomoji which do not fit the pattern recognised by `is_kaomoji`.
+const KAOMOJI: &'static [&'static str] = &[r"¯\_(ツ)_/¯", "(╯°□°)╯︵ ┻━┻", "┬─┬ノ( º _ ºノ)", "ʕ•ᴥ•ʔ"];
+
+/// Whether `character` is an emoji, or a character which joins or modifies emoji such as a skin tone, a
+/// regional indicator or the zero width joiner.
+pub fn is_emoji(character: char) -> bool {
+    matches!(character as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x2300..=0x23FF
+            | 0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0xE0020..=0xE007F | 0x3030 | 0x303D | 0x3297 | 0x3299)
+}
+
+/// Whether a parenthesised word is a kaomoji such as `(^_^)` or `(・ω・)`: one with none of the letters or
+/// digits of ordinary text, and at least one character that is typically used to draw a face.
+fn is_kaomoji(word: &str) -> bool {
+    const FACE: &'static str = "^_°ω・‿◕□´`ツ≧≦▽｀∀｡;><ᴗ•";
+    let inner = match word.strip_prefix('(').and_then(|word| word.strip_suffix(')')) {
+        Some(inner) if !inner.is_empty() => inner,
+        _ => return KAOMOJI.contains(&word)
+    };
+    !inner.chars().any(|c| c.is_ascii_alphanumeric() || (c.is_alphabetic() && c != 'ω' && c != 'ツ'))
+        && inner.chars().any(|c| FACE.contains(c))
+}
+
+/// Remove the emoji, emoticons and kaomoji from `input`, along with the whitespace that separated them
+/// from the surrounding text. Emoticons and kaomoji are only removed when they stand on their own, so that
+/// punctuation such as the `:)` of `(see note:)` is left alone.
+pub fn strip_emoji(input: &str) -> String {
+    let mut stripped = String::with_capacity(input.len());
+    let mut rest = input;
+    while !rest.is_empty() {
+        let start = rest.len() == input.len();
+        let after = rest.trim_start();
+        let spacing = &rest[..rest.len() - after.len()];
+        // Some kaomoji have spaces within them, so are looked for before splitting at whitespace.
+        let length = KAOMOJI.iter().find(|kaomoji| after.starts_with(*kaomoji)).map(|kaomoji| kaomoji.len())
+            .unwrap_or_else(|| after.find(char::is_whitespace).unwrap_or(after.len()));
+        let word = &after[..length];
+        rest = &after[length..];
+
+        let kept = if EMOTICONS.contains(&word) || is_kaomoji(word) {
+            String::new()
+        } else {
+            word.chars().filter(|&c| !is_emoji(c)).collect()
+        };
+        // Whitespace is kept between the words which remain, and at either end of the text.
+        let trailing = word.is_empty() && !stripped.is_empty();
+        if (!kept.is_empty() && (start || !stripped.is_empty())) || trailing {
+            stripped.push_str(spacing);
+        }
+        stripped.push_str(kept.as_str());
+    }
+    stripped
+}
+
+#[test]
+fn test_strip_emoji() {
+    assert_eq!(strip_emoji("👋 Hi there 😀!").as_str(), "Hi there !");
+    assert_eq!(strip_emoji("ok 👍🏽 :) see you <3\n").as_str(), "ok see you\n");
+    assert_eq!(strip_emoji("  Thanks 🙏 (^_^) ¯\\_(ツ)_/¯ (see note:)").as_str(), "  Thanks (see note:)");
+    assert_eq!(strip_emoji("great👍 (╯°□°)╯︵ ┻━┻ and 👨‍👩‍👧 flags 🇩🇪").as_str(), "great and flags");
+    assert_eq!(strip_emoji("(hello) 😀").as_str(), "(hello)");
+}
+
+#[test]
+fn test_passthrough() {
+    const CORPUS: &'static [&'static str] = &[
+        "👋", "👍🏽", "👨‍👩‍👧", "🇩🇪", "1️⃣", "❤️", "🏳️‍🌈", "🏴󠁧󠁢󠁳󠁣󠁴󠁿", r"¯\_(ツ)_/¯", "(╯°□°)╯︵ ┻━┻", "(・ω・)", ":-)", "<3", "ʕ•ᴥ•ʔ",
+    ];
+    for &sample in CORPUS {
+        // The request must carry every byte of the original.
+        let encoded = percent_encode(sample);
+        let mut decoded = Vec::new();
+        let mut bytes = encoded.bytes();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                b'%' => {
+                    let hex = [bytes.next().unwrap(), bytes.next().unwrap()];
+                    decoded.push(u8::from_str_radix(::std::str::from_utf8(&hex).unwrap(), 16).unwrap());
+                },
+                _ => decoded.push(byte)
+            }
+        }
+        assert_eq!(decoded.as_slice(), sample.as_bytes());
+
+        // Google may send the translation back either as it is or escaped as UTF-16 surrogate pairs.
+        let escaped = sample.encode_utf16().map(|unit| format!("\\u{:04x}", unit)).collect::<String>();
+        for written in &[Json::from(sample).to_string(), format!("\"{}\"", escaped)] {
+            let response = format!(r#"[[[{},{},null,null,1]],null,"en"]"#, written, written);
+            let response = parse_response(response.as_str()).unwrap();
+            assert_eq!(response.sentences[0].translation.as_bytes(), sample.as_bytes());
+            assert_eq!(response.sentences[0].source.as_bytes(), sample.as_bytes());
+        }
+    }
+}
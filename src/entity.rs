@@ -0,0 +1,192 @@
+//! Protection of names, such as those of people, places and products, which Google would otherwise translate.
+//!
+//! Names are replaced by numbered placeholders such as `[0]` before the text is sent, and put back afterwards.
+
+use segment::is_sentence_end;
+
+/// Find the names in `input` which should be kept as they are. These are the words of `known` which occur
+/// in the input, along with any run of capitalised words which does not begin a sentence and any word with
+/// a capital letter inside it, such as `iPhone`. Longer names are listed first.
+pub fn find_entities(input: &str, known: &[String]) -> Vec<String> {
+    let mut entities = known.iter()
+        .filter(|name| !name.is_empty() && find_word(input, name.as_str(), 0).is_some())
+        .cloned()
+        .collect::<Vec<String>>();
+
+    // The byte range of the current run of capitalised words, and whether it is certainly a name.
+    let mut run: Option<(usize, usize, bool)> = None;
+    let mut runs = Vec::new();
+    let mut sentence_start = true;
+    for token in input.split_whitespace() {
+        let offset = token.as_ptr() as usize - input.as_ptr() as usize;
+        let word = token.trim_matches(|character: char| !character.is_alphanumeric());
+        let start = offset + word.as_ptr() as usize - token.as_ptr() as usize;
+        let mut characters = word.chars();
+        let capitalised = characters.next().is_some_and(char::is_uppercase) && word.chars().count() > 1;
+        let mixed = characters.any(char::is_uppercase) && word.chars().any(char::is_lowercase);
+
+        if capitalised || mixed {
+            let certain = !sentence_start || mixed;
+            // A capitalised word which begins a sentence is only a name if it has a capital inside it, so a
+            // name which follows it, as in "Yesterday Ada Lovelace", starts a run of its own.
+            run = match run {
+                Some((first, _, true)) if start == offset => Some((first, start + word.len(), true)),
+                Some(previous) => {
+                    runs.push(previous);
+                    Some((start, start + word.len(), certain))
+                },
+                None => Some((start, start + word.len(), certain))
+            };
+        } else if let Some(previous) = run.take() {
+            runs.push(previous);
+        }
+        // Punctuation after a word, such as a comma, ends the run of names that it belongs to.
+        if word.len() != token.len() - (start - offset) {
+            if let Some(previous) = run.take() { runs.push(previous); }
+        }
+        sentence_start = token.chars().next_back().is_some_and(is_sentence_end);
+    }
+    runs.extend(run);
+
+    for (start, end, certain) in runs {
+        let name = &input[start..end];
+        if certain && !entities.iter().any(|entity| entity == name) {
+            entities.push(name.to_string());
+        }
+    }
+    entities.sort_by_key(|entity| ::std::cmp::Reverse(entity.len()));
+    entities
+}
+
+/// Replace every occurrence of each of `entities` in `input` with its placeholder. A bracketed number which is
+/// already in the input, such as the citation in `see [1]`, would be taken for a placeholder, so it is masked
+/// as well, with a placeholder numbered after those of the entities. Returns the masked text along with what
+/// each placeholder stands for, which is what `restore` is given to put them back.
+pub fn protect(input: &str, entities: &[String]) -> (String, Vec<String>) {
+    let mut masks = entities.to_vec();
+    // The byte ranges of the input which are masked, with the placeholder for each.
+    let mut ranges: Vec<(usize, usize, usize)> = Vec::new();
+    let free = |ranges: &[(usize, usize, usize)], start: usize, end: usize| ranges.iter().all(|&(from, to, _)| end <= from || start >= to);
+    for (index, entity) in entities.iter().enumerate().filter(|&(_, entity)| !entity.is_empty()) {
+        let mut from = 0;
+        while let Some(start) = find_word(input, entity.as_str(), from) {
+            if free(&ranges, start, start + entity.len()) {
+                ranges.push((start, start + entity.len(), index));
+            }
+            from = start + entity.len();
+        }
+    }
+    for (start, end) in find_bracketed_numbers(input) {
+        if free(&ranges, start, end) {
+            let literal = &input[start..end];
+            let index = masks.iter().skip(entities.len()).position(|mask| mask == literal).map(|index| entities.len() + index);
+            let index = index.unwrap_or_else(|| {
+                masks.push(literal.to_string());
+                masks.len() - 1
+            });
+            ranges.push((start, end, index));
+        }
+    }
+    ranges.sort();
+
+    let mut protected = String::with_capacity(input.len());
+    let mut last = 0;
+    for (start, end, index) in ranges {
+        protected.push_str(&input[last..start]);
+        protected.push_str(format!("[{}]", index).as_str());
+        last = end;
+    }
+    protected.push_str(&input[last..]);
+    (protected, masks)
+}
+
+/// The byte ranges of the bracketed numbers in `input`, such as `[1]` or `[ 2 ]`, which `restore` would read
+/// as placeholders.
+pub fn find_bracketed_numbers(input: &str) -> Vec<(usize, usize)> {
+    let mut numbers = Vec::new();
+    let mut from = 0;
+    while let Some(open) = input[from..].find('[').map(|open| from + open) {
+        let inside = &input[open + 1..];
+        match inside.find(']') {
+            Some(close) if inside[..close].trim().parse::<usize>().is_ok() => {
+                numbers.push((open, open + close + 2));
+                from = open + close + 2;
+            },
+            _ => from = open + 1
+        }
+    }
+    numbers
+}
+
+/// Put `entities` back in place of their placeholders in a translation made from the output of `protect`.
+/// Google sometimes adds spaces inside the brackets, which is tolerated. The entities whose placeholders
+/// did not survive translation are returned along with the restored text.
+pub fn restore(translation: &str, entities: &[String]) -> (String, Vec<String>) {
+    let mut restored = String::with_capacity(translation.len());
+    let mut found = vec![false; entities.len()];
+    let mut rest = translation;
+    while let Some(open) = rest.find('[') {
+        restored.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let placeholder = after.find(']').and_then(|close| {
+            after[..close].trim().parse::<usize>().ok().filter(|&index| index < entities.len()).map(|index| (index, close))
+        });
+        match placeholder {
+            Some((index, close)) => {
+                restored.push_str(entities[index].as_str());
+                found[index] = true;
+                rest = &after[close + 1..];
+            },
+            None => {
+                restored.push('[');
+                rest = after;
+            }
+        }
+    }
+    restored.push_str(rest);
+
+    let missing = entities.iter().zip(found).filter(|&(_, found)| !found).map(|(entity, _)| entity.clone()).collect();
+    (restored, missing)
+}
+
+/// Find `word` in `input` at or after the byte offset `from`, where it is not part of a longer word. Only an
+/// end of `word` which is itself a letter or digit must be set apart, so that a placeholder such as `{0}`
+/// is found even when it is written against a word.
+pub fn find_word(input: &str, word: &str, from: usize) -> Option<usize> {
+    let mut from = from;
+    let first = word.chars().next().is_some_and(char::is_alphanumeric);
+    let last = word.chars().next_back().is_some_and(char::is_alphanumeric);
+    while let Some(index) = input[from..].find(word) {
+        let start = from + index;
+        let end = start + word.len();
+        let before = first && input[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        let after = last && input[end..].chars().next().is_some_and(char::is_alphanumeric);
+        if !before && !after { return Some(start); }
+        from = start + input[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+#[test]
+fn test_entities() {
+    let input = "Yesterday Ada Lovelace met Charles Babbage in London, then bought an iPhone. Annual reports by Acme are dull.";
+    let entities = find_entities(input, &["Acme".to_string()]);
+    assert_eq!(entities, vec!["Charles Babbage", "Ada Lovelace", "London", "iPhone", "Acme"]);
+
+    let (protected, masks) = protect(input, &entities);
+    assert_eq!(protected, "Yesterday [1] met [0] in [2], then bought an [3]. Annual reports by [4] are dull.");
+    assert_eq!(masks, entities);
+    assert_eq!(restore("Ayer [ 1 ] conoció a [0] en [2], luego compró un [3].", &entities).0,
+        "Ayer Ada Lovelace conoció a Charles Babbage en London, luego compró un iPhone.");
+    assert_eq!(restore("Ayer [1] conoció a [0] en [2], luego compró un [3].", &entities).1, vec!["Acme"]);
+    assert_eq!(restore("[x] [9]", &entities).0, "[x] [9]");
+
+    assert!(find_entities("Hello there. Goodbye now, I said.", &[]).is_empty());
+    assert_eq!(protect("Deleted{0}files", &["{0}".to_string()]).0, "Deleted[0]files");
+
+    // Bracketed numbers already in the input are masked too, so that they are not taken for the names.
+    let (protected, masks) = protect("Ada [0] met Babbage [1], as [ 1 ] says.", &["Babbage".to_string(), "Ada".to_string()]);
+    assert_eq!(protected, "[1] [2] met [0] [3], as [4] says.");
+    assert_eq!(masks, vec!["Babbage", "Ada", "[0]", "[1]", "[ 1 ]"]);
+    assert_eq!(restore("[1] [2] conoció a [0] [3], como dice [4].", &masks).0, "Ada [0] conoció a Babbage [1], como dice [ 1 ].");
+}
@@ -0,0 +1,110 @@
+//! The ways in which a translation may fail.
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use hyper;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent to Google.
+    Request(hyper::Error),
+    /// The response could not be read.
+    Response(io::Error),
+    /// The response was read, but it was not in the format that Google normally replies with.
+    Parse(String),
+    /// The background thread carrying out the translation stopped before it produced a result.
+    Interrupted,
+    /// The request could not be recorded in the audit log, so it was not sent.
+    Audit(io::Error),
+    /// The secure connection to Google could not be set up as configured.
+    Tls(String),
+    /// Google replied with an HTTP status other than success.
+    Status(u16),
+    /// Google is refusing requests because too many have been made (HTTP 429). If it said how long to wait
+    /// before trying again, that is given.
+    RateLimited(Option<Duration>),
+    /// Google has judged the requests to be automated and replied with a CAPTCHA page instead of a translation.
+    /// Requests from the same network are usually refused until some time has passed.
+    Blocked,
+    /// Every endpoint has failed too many times in a row and is being rested, so no request was sent. The
+    /// time until the first of them is tried again is given.
+    Unavailable(Duration),
+    /// Sending the request would have taken the characters sent by the translator past its limit, which is
+    /// given, so it was not sent.
+    LimitReached(u64),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Request(ref why)  => write!(f, "unable to send request: {}", why),
+            Error::Response(ref why) => write!(f, "unable to read response: {}", why),
+            Error::Parse(ref why)    => write!(f, "unable to parse response: {}", why),
+            Error::Interrupted       => write!(f, "translation was interrupted"),
+            Error::Audit(ref why)    => write!(f, "unable to write to the audit log: {}", why),
+            Error::Tls(ref why)      => write!(f, "unable to connect securely: {}", why),
+            Error::Status(status)    => write!(f, "Google responded with HTTP status {}", status),
+            Error::RateLimited(Some(wait)) => {
+                write!(f, "Google is limiting requests (HTTP 429); try again in {} seconds", wait.as_secs().max(1))
+            },
+            Error::RateLimited(None) => write!(f, "Google is limiting requests (HTTP 429); try again later"),
+            Error::Blocked => {
+                write!(f, "Google has blocked requests from this network as unusual traffic and is asking for a CAPTCHA; \
+                           wait a while before translating again")
+            },
+            Error::Unavailable(wait) => {
+                write!(f, "every endpoint has failed repeatedly and is being rested; try again in {} seconds", wait.as_secs().max(1))
+            },
+            Error::LimitReached(limit) => write!(f, "the limit of {} characters sent to Google has been reached", limit),
+        }
+    }
+}
+
+impl Error {
+    /// A short name for the kind of failure, such as `rate_limited`, for tools which read errors as JSON.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Error::Request(_)      => "request",
+            Error::Response(_)     => "response",
+            Error::Parse(_)        => "parse",
+            Error::Interrupted     => "interrupted",
+            Error::Audit(_)        => "audit",
+            Error::Tls(_)          => "tls",
+            Error::Status(_)       => "status",
+            Error::RateLimited(_)  => "rate_limited",
+            Error::Blocked         => "blocked",
+            Error::Unavailable(_)  => "unavailable",
+            Error::LimitReached(_) => "limit_reached",
+        }
+    }
+
+    /// Whether the failure is likely to pass if the request is tried again, such as a timeout, a dropped
+    /// connection or a server error. Failures caused by the request itself or by the configuration are not.
+    pub fn is_transient(&self) -> bool {
+        let transient_io = |why: &io::Error| match why.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                | io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::BrokenPipe | io::ErrorKind::UnexpectedEof => true,
+            _ => false
+        };
+        match *self {
+            Error::Request(hyper::Error::Io(ref why)) => transient_io(why),
+            Error::Response(ref why)                  => transient_io(why),
+            Error::Status(status)                     => status >= 500,
+            _ => false
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<hyper::Error> for Error {
+    fn from(why: hyper::Error) -> Error { Error::Request(why) }
+}
+
+impl From<io::Error> for Error {
+    fn from(why: io::Error) -> Error { Error::Response(why) }
+}
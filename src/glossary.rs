@@ -0,0 +1,114 @@
+//! A glossary of terms which are always translated the same way, or not at all, such as product names, so
+//! that the terminology of a batch of translations stays consistent.
+//!
+//! A glossary file has a term on each line, which is kept as it is. A term may be followed by a tab, a
+//! language code, another tab and a translation, in which case it is replaced by that translation in text
+//! translated into that language. Blank lines and lines beginning with `#` are skipped.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::slice;
+
+use entity::{find_word, protect};
+
+/// A term and its fixed translations, each into the language it is paired with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlossaryTerm {
+    pub term: String,
+    pub translations: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Glossary {
+    terms: Vec<GlossaryTerm>,
+}
+
+impl Glossary {
+    /// A glossary without any terms.
+    pub fn new() -> Glossary {
+        Glossary::default()
+    }
+
+    /// Read a glossary in the format described above. A term which is listed more than once gathers the
+    /// translations of every line it is on.
+    pub fn parse(text: &str) -> Result<Glossary, String> {
+        let mut glossary = Glossary::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = line.split('\t').map(str::trim).collect::<Vec<&str>>();
+            let (term, translation) = match fields[..] {
+                [term] => (term, None),
+                [term, language, translation] if !language.is_empty() && !translation.is_empty() => (term, Some((language, translation))),
+                _ => return Err(format!("line {}: expected a term, optionally followed by a language and a translation, separated by tabs", number + 1))
+            };
+            if term.is_empty() {
+                return Err(format!("line {}: the term is empty", number + 1));
+            }
+            let index = match glossary.terms.iter().position(|known| known.term == term) {
+                Some(index) => index,
+                None => {
+                    glossary.terms.push(GlossaryTerm { term: term.to_string(), translations: Vec::new() });
+                    glossary.terms.len() - 1
+                }
+            };
+            if let Some((language, translation)) = translation {
+                glossary.terms[index].translations.push((language.to_string(), translation.to_string()));
+            }
+        }
+        Ok(glossary)
+    }
+
+    /// Read the glossary file at `path`. A file which is not a glossary fails as `InvalidData`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Glossary> {
+        let text = fs::read_to_string(path)?;
+        Glossary::parse(text.as_str()).map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))
+    }
+
+    pub fn terms(&self) -> &[GlossaryTerm] {
+        &self.terms
+    }
+
+    /// The terms which occur in `input` as whole words, longest first, so that a term which contains another
+    /// is protected before it. A term which only occurs within a longer one, such as `Acme` within
+    /// `Acme Cloud`, is left out.
+    pub fn find(&self, input: &str) -> Vec<String> {
+        let mut found = self.terms.iter().map(|term| term.term.clone()).collect::<Vec<String>>();
+        found.sort_by_key(|term| ::std::cmp::Reverse(term.len()));
+        let mut rest = input.to_string();
+        found.retain(|term| {
+            let occurs = find_word(rest.as_str(), term.as_str(), 0).is_some();
+            if occurs { rest = protect(rest.as_str(), slice::from_ref(term)).0; }
+            occurs
+        });
+        found
+    }
+
+    /// What `term` becomes in text translated into `language`: its translation into that language, or into
+    /// the language without its region or script, such as `pt` for `pt-BR`, or else the term itself.
+    pub fn translation(&self, term: &str, language: &str) -> String {
+        let primary = language.split('-').next().unwrap_or(language);
+        let translations = self.terms.iter().find(|known| known.term == term).map_or(&[][..], |known| &known.translations[..]);
+        translations.iter().find(|&(known, _)| known.eq_ignore_ascii_case(language))
+            .or_else(|| translations.iter().find(|&(known, _)| known.eq_ignore_ascii_case(primary)))
+            .map_or_else(|| term.to_string(), |(_, translation)| translation.clone())
+    }
+}
+
+#[test]
+fn test_glossary() {
+    let glossary = Glossary::parse("# Product names\nAcme Cloud\nAcme\n\ncheckout\tDE\tKasse\ncheckout\tfr\tpaiement\r\n").unwrap();
+    assert_eq!(glossary.terms().len(), 3);
+    assert_eq!(glossary.find("Open Acme Cloud and go to the checkout."), vec!["Acme Cloud", "checkout"]);
+    assert_eq!(glossary.find("Acme sells Acme Cloud."), vec!["Acme Cloud", "Acme"]);
+    assert!(glossary.find("Acmes are not checkouts.").is_empty());
+    assert_eq!(glossary.translation("checkout", "de").as_str(), "Kasse");
+    assert_eq!(glossary.translation("checkout", "FR-CA").as_str(), "paiement");
+    assert_eq!(glossary.translation("checkout", "ES").as_str(), "checkout");
+    assert_eq!(glossary.translation("Acme", "DE").as_str(), "Acme");
+    assert_eq!(Glossary::parse("Acme\tDE").unwrap_err().as_str(),
+        "line 1: expected a term, optionally followed by a language and a translation, separated by tabs");
+}
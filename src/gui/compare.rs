@@ -0,0 +1,171 @@
+//! The window which translates the same text into two languages side by side.
+
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+
+use gdk::enums::key;
+use gtk::traits::*;
+use gtk::{
+    Builder,
+    Button,
+    ButtonSignals,
+    ComboBoxSignals,
+    ComboBoxText,
+    Continue,
+    Inhibit,
+    Statusbar,
+    TextView,
+    WidgetSignals,
+    Window
+};
+
+use rust_google_translate::{resolve_language, Error, PendingTranslation, Translator, LANGUAGES};
+
+use gui::{backend_status, debounce, live_delay, show_blocked_dialog, Generation, BACKEND, RETRANSLATE_DELAY};
+
+/// Launch the GUI in compare mode, which translates the same input from `source` (or the detected language)
+/// into two languages at once and shows the translations side by side, starting with `languages`. If `live`,
+/// the input is translated whenever typing pauses.
+pub fn launch_compare(translator: Translator, source: Option<String>, languages: [String; 2], live: bool) {
+    if let Err(message) = gtk::init() {
+        panic!("{:?}", message);
+    }
+
+    let builder = Builder::new_from_string(include_str!("compare.glade"));
+    let window: Window = builder.get_object("compare_window").unwrap();
+    let translate_button: Button = builder.get_object("compare_button").unwrap();
+    let input: TextView = builder.get_object("compare_input").unwrap();
+    let status_bar: Statusbar = builder.get_object("compare_status").unwrap();
+    let status_context = status_bar.get_context_id("translation");
+    status_bar.push(status_context, BACKEND);
+
+    // Each side offers every language, starting on the one it was given.
+    let mut sides: Vec<(ComboBoxText, TextView)> = Vec::new();
+    for (side, language) in ["first", "second"].iter().zip(languages.iter()) {
+        let language_box: ComboBoxText = builder.get_object(format!("compare_language_{}", side).as_str()).unwrap();
+        let output: TextView = builder.get_object(format!("compare_output_{}", side).as_str()).unwrap();
+        for &(_, name) in LANGUAGES {
+            language_box.append_text(name);
+        }
+        let active = LANGUAGES.iter().position(|&(code, _)| code.eq_ignore_ascii_case(language.as_str())).unwrap_or(0);
+        language_box.set_active(active as i32);
+        sides.push((language_box, output));
+    }
+    let translate_button = Rc::new(translate_button);
+
+    // Changing either language translates again once the user has settled on one.
+    let changes = Rc::new(Cell::new(0));
+    for &(ref language_box, _) in sides.iter() {
+        let translate_button = translate_button.clone();
+        let changes = changes.clone();
+        language_box.connect_changed(move |_| {
+            let translate_button = translate_button.clone();
+            debounce(&changes, RETRANSLATE_DELAY, move || translate_button.clicked());
+        });
+    }
+
+    // The text most recently sent to be translated, and the generation of the translation of it.
+    let last_text = Rc::new(RefCell::new(String::new()));
+    let generation = Generation::default();
+
+    if live {
+        let translate_button = translate_button.clone();
+        let last_text = last_text.clone();
+        let typing = Rc::new(Cell::new(0));
+        input.get_buffer().unwrap().connect_changed(move |buffer| {
+            let text = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap_or_default();
+            let translate_button = translate_button.clone();
+            let last_text = last_text.clone();
+            debounce(&typing, live_delay(text.chars().count()), move || {
+                // Typing which ends up back where it started, such as a letter typed and deleted, needs no request.
+                if text.trim() != last_text.borrow().trim() { translate_button.clicked() }
+            });
+        });
+    }
+
+    let compare_window = window.clone();
+    translate_button.connect_clicked(move |_| {
+        let buffer = input.get_buffer().unwrap();
+        let text = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap();
+        *last_text.borrow_mut() = text.clone();
+        let request = generation.next();
+        if text.trim().is_empty() {
+            for &(_, ref output) in sides.iter() {
+                output.get_buffer().unwrap().set_text("");
+            }
+            return
+        }
+
+        // Both translations are made at once, and each side is filled in as soon as its own is ready.
+        let mut pending = sides.iter().map(|&(ref language_box, ref output)| {
+            let name = language_box.get_active_text().unwrap_or_default();
+            let language = resolve_language(name.as_str()).unwrap_or_else(|_| "EN".to_string());
+            (Some(translator.translate_async(text.as_str(), source.as_deref(), language.as_str())), output.clone(), name)
+        }).collect::<Vec<(Option<PendingTranslation>, TextView, String)>>();
+        status_bar.pop(status_context);
+        status_bar.push(status_context, &format!("{}  |  Translating…", BACKEND));
+
+        let status_bar = status_bar.clone();
+        let window = compare_window.clone();
+        let generation = generation.clone();
+        let translator = translator.clone();
+        let mut failures = Vec::new();
+        let mut blocked = false;
+        gtk::timeout_add(50, move || {
+            // A newer translation has begun, so whatever this one would show is out of date.
+            if !generation.is_current(request) {
+                for &(ref translation, _, _) in pending.iter() {
+                    if let Some(ref translation) = *translation { translation.cancel() }
+                }
+                return Continue(false)
+            }
+            for &mut (ref mut translation, ref output, ref name) in pending.iter_mut() {
+                let result = match translation.as_ref().and_then(PendingTranslation::poll) {
+                    Some(result) => result,
+                    None         => continue
+                };
+                *translation = None;
+                match result {
+                    Ok(result) => output.get_buffer().unwrap().set_text(result.text.as_str()),
+                    Err(why)   => {
+                        // Both sides are usually blocked at once, but the dialog is only worth showing once.
+                        if let (Error::Blocked, false) = (&why, blocked) {
+                            blocked = true;
+                            show_blocked_dialog(&window);
+                        }
+                        output.get_buffer().unwrap().set_text("");
+                        failures.push(format!("{} failed: {}", name, why));
+                    }
+                }
+            }
+            if pending.iter().any(|&(ref translation, _, _)| translation.is_some()) {
+                return Continue(true)
+            }
+            let status = if failures.is_empty() {
+                backend_status(&translator)
+            } else {
+                format!("{}  |  {}", backend_status(&translator), failures.join("  |  "))
+            };
+            status_bar.pop(status_context);
+            status_bar.push(status_context, status.as_str());
+            Continue(false)
+        });
+    });
+
+    window.connect_delete_event(|_,_| {
+        gtk::main_quit();
+        Inhibit(false)
+    });
+
+    window.connect_key_press_event(move |_,key| {
+        match key.get_keyval() {
+            key::Escape => { gtk::main_quit(); Inhibit(false) },
+            // The Enter key translates rather than starting a new line.
+            key::Return => { translate_button.clicked(); Inhibit(true) },
+            _ => Inhibit(false)
+        }
+    });
+
+    window.show_all();
+    gtk::main();
+}
@@ -0,0 +1,84 @@
+//! The kiosk, a full-screen window which translates into a single language for passers-by.
+
+use gdk::enums::key;
+use gtk::traits::*;
+use gtk::{Builder, Continue, Inhibit, Label, TextBuffer, WidgetSignals, Window};
+
+use rust_google_translate::{language_name, Translator};
+
+use gui::Generation;
+
+/// Launch the GUI in kiosk mode: a fullscreen window with large text, which translates from `source` (or the
+/// detected language) into `language` and offers nothing else. The window cannot be closed from within it.
+pub fn launch_kiosk(translator: Translator, source: Option<String>, language: String) {
+    if let Err(message) = gtk::init() {
+        panic!("{:?}", message);
+    }
+
+    let builder = Builder::new_from_string(include_str!("kiosk.glade"));
+    let window: Window = builder.get_object("kiosk_window").unwrap();
+    let languages: Label = builder.get_object("kiosk_languages").unwrap();
+    let status: Label = builder.get_object("kiosk_status").unwrap();
+    let input: TextBuffer = builder.get_object("input_buffer").unwrap();
+    let output: TextBuffer = builder.get_object("output_buffer").unwrap();
+
+    let name = |code: &str| language_name(code).unwrap_or(code).to_string();
+    let from = source.as_deref().map_or_else(|| "Any language".to_string(), name);
+    languages.set_text(format!("{}  →  {}", from, name(language.as_str())).as_str());
+
+    // Everything typed is shown in large text, as well as the translation.
+    let enlarge = |buffer: &TextBuffer| buffer.apply_tag_by_name("large", &buffer.get_start_iter(), &buffer.get_end_iter());
+    input.connect_changed(move |buffer| enlarge(buffer));
+
+    // Escape or a new translation supersedes any translation still under way.
+    let generation = Generation::default();
+
+    // Visitors may not leave kiosk mode, so closing the window is refused.
+    window.connect_delete_event(|_,_| Inhibit(true));
+
+    window.connect_key_press_event(move |_,key| {
+        match key.get_keyval() {
+            key::Escape => {
+                generation.next();
+                input.set_text("");
+                output.set_text("");
+                status.set_text("Type, then press Enter to translate. Press Escape to start again.");
+                Inhibit(true)
+            },
+            key::Return => {
+                let text = input.get_text(&input.get_start_iter(), &input.get_end_iter(), false).unwrap();
+                if text.trim().is_empty() { return Inhibit(true) }
+                let request = generation.next();
+                let pending = translator.translate_async(text.as_str(), source.as_deref(), language.as_str());
+                status.set_text("Translating…");
+
+                let output = output.clone();
+                let status = status.clone();
+                let generation = generation.clone();
+                gtk::timeout_add(50, move || {
+                    if !generation.is_current(request) {
+                        pending.cancel();
+                        return Continue(false)
+                    }
+                    match pending.poll() {
+                        None => return Continue(true),
+                        Some(Ok(result)) => {
+                            output.set_text(result.text.as_str());
+                            enlarge(&output);
+                            status.set_text("Press Escape to start again.");
+                        },
+                        Some(Err(_)) => status.set_text("Sorry, the translation is not available right now. Please try again.")
+                    }
+                    Continue(false)
+                });
+                // The Enter key translates rather than starting a new line.
+                Inhibit(true)
+            },
+            _ => Inhibit(false)
+        }
+    });
+
+    window.show_all();
+    window.fullscreen();
+    gtk::main();
+}
@@ -0,0 +1,145 @@
+//! The context menu of a translation, shared by the windows which show one.
+
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use gtk::traits::*;
+use gtk::{Builder, Clipboard, Continue, Inhibit, Menu, MenuItem, MenuItemSignals, Statusbar, TextView, WidgetSignals};
+
+use rust_google_translate::{percent_encode, HistoryEntry, Phrase, Translator};
+
+use gui::{phrasebook, BACKEND};
+
+/// Offer a menu of quick actions on the translation in `view` when it is right-clicked, each of which acts on
+/// the selected text, or all of it if none is selected. The languages are those of the newest entry in
+/// `history`, provided that the view still holds its translation.
+pub fn connect_output_menu(builder: &Builder, view: &TextView, translator: Translator, history: Rc<RefCell<Vec<HistoryEntry>>>,
+    status_bar: Statusbar, status_context: u32)
+{
+    let menu: Menu = builder.get_object("output_menu").unwrap();
+    let item = |name: &str| -> MenuItem { builder.get_object(format!("{}_item", name).as_str()).unwrap() };
+
+    // The text to act on, and the translation it belongs to if it is one.
+    let target = {
+        let view = view.clone();
+        Rc::new(move || {
+            let buffer = view.get_buffer().unwrap();
+            let whole = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap_or_default();
+            let text = match buffer.get_selection_bounds() {
+                Some((start, end)) => buffer.get_text(&start, &end, false).unwrap_or_default(),
+                None               => whole.clone()
+            };
+            let entry = history.borrow().last().filter(|entry| entry.translation == whole).cloned();
+            (text, entry)
+        })
+    };
+    let report = Rc::new(move |message: &str| {
+        status_bar.pop(status_context);
+        status_bar.push(status_context, format!("{}  |  {}", BACKEND, message).as_str());
+    });
+
+    {
+        let target = target.clone();
+        item("copy").connect_activate(move |_| {
+            Clipboard::get(&gdk::Atom::intern("CLIPBOARD")).set_text(target().0.as_str());
+        });
+    }
+
+    {
+        let (target, report) = (target.clone(), report.clone());
+        item("speak").connect_activate(move |_| {
+            let (text, entry) = target();
+            if let Err(why) = speak(text.as_str(), entry.as_ref().map(|entry| entry.target.as_str())) {
+                report(why.as_str());
+            }
+        });
+    }
+
+    {
+        let (target, report) = (target.clone(), report.clone());
+        item("dictionary").connect_activate(move |_| {
+            let (text, entry) = target();
+            let language = entry.as_ref().map_or("en".to_string(), |entry| wiktionary_language(entry.target.as_str()));
+            let url = format!("https://{}.wiktionary.org/wiki/{}", language, percent_encode(text.trim()));
+            if let Err(why) = Command::new("xdg-open").arg(url.as_str()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+                report(format!("Unable to open {}: {}", url, why).as_str());
+            }
+        });
+    }
+
+    {   // Translate the text back into the language that it was translated from, to check what it says.
+        let (target, report) = (target.clone(), report.clone());
+        item("swap").connect_activate(move |_| {
+            let (text, entry) = target();
+            let entry = match entry {
+                Some(entry) => entry,
+                None        => return report("Only a translation can be translated back")
+            };
+            // Google cannot translate into a language to be detected, so the language translated from must be known.
+            let back = match entry.source.clone() {
+                Some(source) => source,
+                None         => return report("The language it was translated from is not known")
+            };
+            let pending = translator.translate_async(text.as_str(), Some(entry.target.as_str()), back.as_str());
+            report("Translating back…");
+            let report = report.clone();
+            gtk::timeout_add(50, move || {
+                match pending.poll() {
+                    None             => return Continue(true),
+                    Some(Ok(result)) => report(format!("Back to {}: {}", back.to_uppercase(), result.text).as_str()),
+                    Some(Err(why))   => report(format!("Translating back failed: {}", why).as_str())
+                }
+                Continue(false)
+            });
+        });
+    }
+
+    {
+        let (target, report) = (target.clone(), report.clone());
+        item("phrasebook").connect_activate(move |_| {
+            let (text, entry) = target();
+            let phrasebook = match phrasebook() {
+                Some(phrasebook) => phrasebook,
+                None             => return report("There is nowhere to keep the phrasebook")
+            };
+            let phrase = match entry {
+                Some(entry) => Phrase { source: entry.source, target: entry.target, original: entry.input, translation: text },
+                None        => Phrase { source: None, target: String::new(), original: String::new(), translation: text }
+            };
+            match phrasebook.add(&phrase) {
+                Ok(())   => report(format!("Saved to {}", phrasebook.path().display()).as_str()),
+                Err(why) => report(format!("Unable to save to the phrasebook: {}", why).as_str())
+            }
+        });
+    }
+
+    let swap = item("swap");
+    view.connect_button_press_event(move |_, event| {
+        if event.button != 3 { return Inhibit(false) }
+        swap.set_sensitive(target().1.is_some_and(|entry| entry.source.is_some()));
+        menu.popup_easy(event.button, event.time);
+        Inhibit(true)
+    });
+}
+
+/// Read `text` aloud in `language` with speech-dispatcher, or with eSpeak NG if it is not installed.
+pub fn speak(text: &str, language: Option<&str>) -> Result<(), String> {
+    let language = language.map(wiktionary_language);
+    let mut speech_dispatcher = Command::new("spd-say");
+    let mut espeak = Command::new("espeak-ng");
+    if let Some(ref language) = language {
+        speech_dispatcher.args(["-l", language.as_str()]);
+        espeak.args(["-v", language.as_str()]);
+    }
+    speech_dispatcher.arg(text).spawn()
+        .or_else(|_| espeak.arg(text).spawn())
+        .map(|_| ())
+        .map_err(|why| format!("Unable to speak: neither spd-say nor espeak-ng could be run: {}", why))
+}
+
+/// The two-letter code, in lower case, which dictionaries and speech synthesisers know `language` by, such as
+/// `zh` for `ZH-CN`.
+pub fn wiktionary_language(language: &str) -> String {
+    language.split('-').next().unwrap_or(language).to_lowercase()
+}
@@ -0,0 +1,217 @@
+//! The GTK windows: the main window, the kiosk and the comparison of two languages, and what they share.
+
+pub mod compare;
+pub mod kiosk;
+mod menu;
+pub mod window;
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::time::{Duration, SystemTime};
+
+use gtk::traits::*;
+use gtk::{Continue, ListStore, MessageDialog, TextView, TextWindowType, TreeIter, Window};
+
+use rust_google_translate::{CircuitState, DictionaryEntry, HistoryEntry, Phrasebook, Translator};
+
+use cli::settings::data_directory;
+
+pub const BACKEND: &'static str = "Google Translate";
+
+/// How long the GUI waits after the language is changed before translating again, so that stepping through
+/// several languages on the way to the one wanted sends a single request.
+pub const RETRANSLATE_DELAY: u32 = 400;
+
+/// How long the pointer must rest on a word in the GUI before it is looked up in the dictionary, in milliseconds.
+pub const HOVER_DELAY: u32 = 500;
+
+/// How many translations of a word are shown for each part of speech in the GUI's dictionary popover.
+pub const DICTIONARY_TRANSLATIONS: usize = 5;
+
+/// How long live translation waits after typing pauses before translating short text, in milliseconds.
+pub const LIVE_DELAY: u32 = 300;
+
+/// The longest that live translation waits after typing pauses, however long the text is.
+pub const MAX_LIVE_DELAY: u32 = 1500;
+
+pub const SNIPPET_VARIABLE: &'static str = "RUST_GOOGLE_TRANSLATE_HISTORY_SNIPPET";
+
+pub const HOVER_VARIABLE: &'static str = "RUST_GOOGLE_TRANSLATE_HOVER";
+
+/// When the GUI looks up the word under the pointer in the dictionary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hover {
+    /// Whenever the pointer rests on a word.
+    Always,
+    /// Only while Ctrl is held, so that moving the pointer across the text sends no requests.
+    Control,
+    Off,
+}
+
+/// When to look up words under the pointer, as set by HOVER_VARIABLE.
+pub fn hover_mode() -> Hover {
+    match std::env::var(HOVER_VARIABLE).unwrap_or_default().to_lowercase().as_str() {
+        "always" => Hover::Always,
+        "off"    => Hover::Off,
+        _        => Hover::Control
+    }
+}
+
+/// The word of `view` at the window coordinates `x` and `y`, if there is one, along with the area that it
+/// takes up in the window.
+pub fn word_at(view: &TextView, x: i32, y: i32) -> Option<(String, gdk::Rectangle)> {
+    let buffer = view.get_buffer()?;
+    let (x, y) = view.window_to_buffer_coords(TextWindowType::Widget, x, y);
+    let iter = view.get_iter_at_location(x, y)?;
+    if !iter.inside_word() { return None }
+    let mut start = buffer.get_iter_at_offset(iter.get_offset());
+    let mut end = buffer.get_iter_at_offset(iter.get_offset());
+    if !start.starts_word() { start.backward_word_start(); }
+    end.forward_word_end();
+    let word = buffer.get_text(&start, &end, false)?;
+    let (first, last) = (view.get_iter_location(&start), view.get_iter_location(&end));
+    let (x, y) = view.buffer_to_window_coords(TextWindowType::Widget, first.x, first.y);
+    // A word broken across two lines is pointed to by its start alone.
+    let width = if last.y == first.y { (last.x - first.x).max(1) } else { first.width.max(1) };
+    Some((word, gdk::Rectangle { x, y, width, height: first.height }))
+}
+
+/// Lay out the dictionary entries for `word` as Pango markup: the word, then a line for each part of speech
+/// with its most common translations, each followed by the words that it translates back into.
+pub fn dictionary_markup(word: &str, entries: &[DictionaryEntry]) -> String {
+    if entries.is_empty() {
+        return format!("<b>{}</b>\nNo dictionary entry", escape_markup(word));
+    }
+    let mut markup = format!("<b>{}</b>", escape_markup(word));
+    for entry in entries {
+        let translations = entry.translations.iter().take(DICTIONARY_TRANSLATIONS).map(|translation| {
+            let text = escape_markup(translation.translation.as_str());
+            if translation.back_translations.is_empty() {
+                text
+            } else {
+                format!("{} <span foreground=\"gray\">({})</span>", text, escape_markup(translation.back_translations.join(", ").as_str()))
+            }
+        }).collect::<Vec<String>>();
+        markup.push_str(format!("\n<i>{}</i>  {}", escape_markup(entry.part_of_speech.as_str()), translations.join("; ")).as_str());
+    }
+    markup
+}
+
+/// The phrasebook kept in the data directory.
+pub fn phrasebook() -> Option<Phrasebook> {
+    Some(Phrasebook::new(data_directory()?.join("phrasebook.tsv")))
+}
+
+/// List `entry` at the top of the history view, by its pair of languages, a preview of its translation cut
+/// off at `snippet_length` characters and its age, with the whole of both texts shown when hovered over.
+pub fn add_to_history(store: &ListStore, entry: &HistoryEntry, snippet_length: usize) {
+    let tooltip = format!("<b>{}</b>\n{}\n\n{}", escape_markup(entry.badge().as_str()), escape_markup(entry.input.as_str()),
+        escape_markup(entry.translation.as_str()));
+    let mut row = TreeIter::new();
+    store.insert(&mut row, 0);
+    store.set_string(&row, 0, entry.badge().as_str());
+    store.set_string(&row, 1, entry.preview(snippet_length).as_str());
+    store.set_string(&row, 2, entry.age(SystemTime::now()).as_str());
+    store.set_string(&row, 3, tooltip.as_str());
+}
+
+/// Escape the characters which Pango markup treats specially, so that `text` is shown as it is.
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// The name of the translation service for the status bar, noting any endpoints which are being rested after
+/// failing repeatedly, so that a translation which fails without a request being sent can be told apart.
+pub fn backend_status(translator: &Translator) -> String {
+    let circuits = translator.circuits();
+    let resting = circuits.iter().filter_map(|&(_, state)| match state {
+        CircuitState::Open(wait) => Some(wait),
+        CircuitState::HalfOpen   => Some(Duration::from_secs(0)),
+        CircuitState::Closed(_)  => None
+    }).collect::<Vec<Duration>>();
+    match resting.len() {
+        0 => BACKEND.to_string(),
+        _ if circuits.len() == 1 => format!("{} (resting for {} s)", BACKEND, resting[0].as_secs()),
+        count => format!("{} ({} of {} servers resting)", BACKEND, count, circuits.len())
+    }
+}
+
+/// Explain in a dialog over `window` that Google has stopped translating for now because it has taken the
+/// requests to be automated, which would otherwise look like the translator had simply broken.
+pub fn show_blocked_dialog(window: &Window) {
+    let dialog = MessageDialog::new(Some(window), gtk::DIALOG_MODAL, gtk::MessageType::Warning, gtk::ButtonsType::Ok,
+        "Google has stopped translating for now.\n\n\
+         Too many translations were requested from this network in a short time, so Google has taken them to be \
+         automated and is asking for a CAPTCHA to be solved, which this program cannot do. Translating usually \
+         works again after waiting a while, from a few minutes to an hour.");
+    dialog.set_title("Translation blocked");
+    dialog.run();
+    dialog.destroy();
+}
+
+/// Numbers the translations begun from one window, so that a translation which finishes after a newer one
+/// was begun can tell that it is out of date, rather than overwriting the newer one's result.
+#[derive(Clone, Default)]
+pub struct Generation(Rc<Cell<u32>>);
+
+impl Generation {
+    /// Begin a new generation, superseding every earlier one, and return its number.
+    pub fn next(&self) -> u32 {
+        self.0.set(self.0.get().wrapping_add(1));
+        self.0.get()
+    }
+
+    /// Whether `generation` is still the newest.
+    pub fn is_current(&self, generation: u32) -> bool {
+        self.0.get() == generation
+    }
+}
+
+/// Run `action` after `delay` milliseconds, unless `debounce` is called again with the same `changes` counter
+/// in the meantime, in which case only the latest action is run.
+pub fn debounce<F: Fn() + 'static>(changes: &Rc<Cell<u32>>, delay: u32, action: F) {
+    changes.set(changes.get().wrapping_add(1));
+    let change = changes.get();
+    let changes = changes.clone();
+    gtk::timeout_add(delay, move || {
+        if changes.get() == change { action() }
+        Continue(false)
+    });
+}
+
+/// How long live translation waits for typing to pause before translating text `length` characters long.
+/// Every request sends the whole text again, so the longer it is, the more worthwhile it is to wait until the
+/// typist is more likely to have finished.
+pub fn live_delay(length: usize) -> u32 {
+    LIVE_DELAY.saturating_add((length / 2) as u32).min(MAX_LIVE_DELAY)
+}
+
+#[test]
+fn test_generation() {
+    let generation = Generation::default();
+    let first = generation.next();
+    let second = generation.clone().next();
+    assert!(!generation.is_current(first) && generation.is_current(second));
+}
+
+#[test]
+fn test_dictionary_markup() {
+    use rust_google_translate::DictionaryTranslation;
+
+    let entry = DictionaryEntry {
+        part_of_speech: "interjection".to_string(),
+        base_form:      None,
+        translations:   vec![
+            DictionaryTranslation { translation: "hello".to_string(), back_translations: vec!["saluton".to_string(), "saluto".to_string()] },
+            DictionaryTranslation { translation: "hi & bye".to_string(), back_translations: vec![] },
+        ],
+    };
+    assert_eq!(dictionary_markup("saluton", &[entry]).as_str(),
+        "<b>saluton</b>\n<i>interjection</i>  hello <span foreground=\"gray\">(saluton, saluto)</span>; hi &amp; bye");
+    assert_eq!(dictionary_markup("xyz", &[]).as_str(), "<b>xyz</b>\nNo dictionary entry");
+}
+
+#[test]
+fn test_escape_markup() {
+    assert_eq!(escape_markup("<b>Tom & Jerry</b>").as_str(), "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;");
+}
@@ -0,0 +1,410 @@
+//! The main window, which translates the text typed into it and keeps a history of translations.
+
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Instant, SystemTime};
+
+use gdk::enums::key;
+use gtk::traits::*;
+use gtk::{
+    Builder,
+    Button,
+    ButtonSignals,
+    ComboBoxSignals,
+    ComboBoxText,
+    Continue,
+    Inhibit,
+    Label,
+    ListStore,
+    Popover,
+    Statusbar,
+    TextBuffer,
+    TextTagTable,
+    TextView,
+    TextWindowType,
+    TreeIter,
+    TreeView,
+    WidgetSignals,
+    Window
+};
+
+use rust_google_translate::{
+    language_name,
+    resolve_language,
+    Error,
+    HistoryEntry,
+    Origin,
+    Translator,
+    DEFAULT_CACHE_TTL,
+    DEFAULT_SNIPPET_LENGTH
+};
+
+use cli::settings::{
+    environment_endpoints,
+    environment_proxy,
+    environment_redact,
+    open_audit_log,
+    response_cache,
+    AUDIT_LOG_VARIABLE
+};
+use gui::menu::connect_output_menu;
+use gui::{
+    add_to_history,
+    backend_status,
+    debounce,
+    dictionary_markup,
+    escape_markup,
+    hover_mode,
+    show_blocked_dialog,
+    word_at,
+    Generation,
+    Hover,
+    BACKEND,
+    HOVER_DELAY,
+    RETRANSLATE_DELAY,
+    SNIPPET_VARIABLE
+};
+
+/// Launch the GTK GUI
+pub fn launch_gui() {
+    // Initialize GTK
+    if let Err(message) = gtk::init() {
+        panic!("{:?}", message);
+    }
+
+    // Open the UI that we created in Glade
+    let glade_src = include_str!("translate.glade");
+    let builder = Builder::new_from_string(glade_src);
+
+    // Grab the elements from the UI
+    let window: Window = builder.get_object("main_window").unwrap();
+    let translate_button: Button = builder.get_object("translate_button").unwrap();
+    let translation_input: TextView = builder.get_object("translation_input").unwrap();
+    let language_box: ComboBoxText = builder.get_object("language").unwrap();
+    let status_bar: Statusbar = builder.get_object("status_bar").unwrap();
+    let status_context = status_bar.get_context_id("translation");
+    status_bar.push(status_context, BACKEND);
+    let history_store: ListStore = builder.get_object("history_store").unwrap();
+    let history_view: TreeView = builder.get_object("history_view").unwrap();
+
+    // Add a TextBuffer to every TextView
+    let input_buffer = TextBuffer::new(Some(&TextTagTable::new()));
+    translation_input.set_buffer(Some(&input_buffer));
+
+    let audit_path = std::env::var(AUDIT_LOG_VARIABLE).ok();
+    let endpoints = environment_endpoints();
+    let translator = Translator::builder().audit_log(open_audit_log(audit_path.as_deref(), false)).proxy(environment_proxy(&endpoints))
+        .endpoints(endpoints).cache(response_cache(false, DEFAULT_CACHE_TTL)).redact(environment_redact()).build();
+
+    // Wrap translation_button so that it may be borrowed multiple times
+    let wrapped_translation_button = Rc::new(RefCell::new(translate_button));
+
+    // The text that was last translated and its translation, so that changing the language translates the
+    // original text again rather than the translation which replaced it.
+    let last_translation: Rc<RefCell<Option<(String, String)>>> = Rc::new(RefCell::new(None));
+    let generation = Generation::default();
+
+    // Every translation made this session, newest last, listed newest first in the history view.
+    let history: Rc<RefCell<Vec<HistoryEntry>>> = Rc::new(RefCell::new(Vec::new()));
+    let snippet_length = std::env::var(SNIPPET_VARIABLE).ok().and_then(|length| length.parse::<usize>().ok())
+        .filter(|&length| length > 0).unwrap_or(DEFAULT_SNIPPET_LENGTH);
+
+    {   // Choosing an entry in the history puts the text that was translated back in the window.
+        let history = history.clone();
+        let translation_input = translation_input.clone();
+        history_view.connect_row_activated(move |_, path, _| {
+            let history = history.borrow();
+            let index = path.get_indices().first().map(|&row| row as usize);
+            if let Some(entry) = index.and_then(|row| history.len().checked_sub(row + 1)).map(|index| &history[index]) {
+                translation_input.get_buffer().unwrap().set_text(entry.input.as_str());
+            }
+        });
+    }
+
+    connect_output_menu(&builder, &translation_input, translator.clone(), history.clone(), status_bar.clone(), status_context);
+    let (selection_language_box, selection_translator) = (language_box.clone(), translator.clone());
+    let (dictionary_language_box, dictionary_translator) = (language_box.clone(), translator.clone());
+    let dictionary_last_translation = last_translation.clone();
+
+    {   // Keep the age of each entry up to date.
+        let history = history.clone();
+        let history_store = history_store.clone();
+        gtk::timeout_add(30_000, move || {
+            let now = SystemTime::now();
+            let mut row = TreeIter::new();
+            for (index, entry) in history.borrow().iter().rev().enumerate() {
+                if history_store.iter_nth_child(&mut row, None, index as i32) {
+                    history_store.set_string(&row, 2, entry.age(now).as_str());
+                }
+            }
+            Continue(true)
+        });
+    }
+
+    {   // Translate again for the new language once the user has settled on one, if there is text to translate.
+        let translate_button = wrapped_translation_button.clone();
+        let translation_input = translation_input.clone();
+        let changes = Rc::new(Cell::new(0));
+        language_box.connect_changed(move |_| {
+            let translate_button = translate_button.clone();
+            let translation_input = translation_input.clone();
+            debounce(&changes, RETRANSLATE_DELAY, move || {
+                let buffer = translation_input.get_buffer().unwrap();
+                let text = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap_or_default();
+                if !text.trim().is_empty() { translate_button.borrow().clicked() }
+            });
+        });
+    }
+
+    {   // Take the input buffer, translate it, and output it to the outbut buffer.
+        let translate_button = wrapped_translation_button.clone();
+        let translation_input = translation_input.clone();
+        let window = window.clone();
+        translate_button.borrow().connect_clicked(move |_| {
+            // Get the input buffer's text, going back to the original if it still holds the last translation.
+            let buffer = translation_input.get_buffer().unwrap();
+            let mut string = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap();
+            if let Some((ref original, ref translation)) = *last_translation.borrow() {
+                if string == *translation { string = original.clone() }
+            }
+
+            // Get the langauge combo box's text, and suggest the nearest languages if it is not one that Google knows.
+            let language = match resolve_language(language_box.get_active_text().unwrap().as_str()) {
+                Ok(language) => language,
+                Err(suggestions) => {
+                    let names = suggestions.iter().filter_map(|&code| language_name(code)).collect::<Vec<&str>>();
+                    let status = match names.len() {
+                        0 => format!("{}  |  Language not supported", BACKEND),
+                        _ => format!("{}  |  Language not supported; did you mean {}?", BACKEND, names.join(" or "))
+                    };
+                    status_bar.pop(status_context);
+                    status_bar.push(status_context, status.as_str());
+                    return
+                }
+            };
+
+            // Translate the text in the background so that the window stays responsive, timing how long it takes.
+            let started = Instant::now();
+            let request = generation.next();
+            let pending = translator.translate_async(&string, None, language.as_str());
+            status_bar.pop(status_context);
+            status_bar.push(status_context, &format!("{}  |  Translating…", BACKEND));
+
+            // Check on the translation periodically until it has finished.
+            let translation_input = translation_input.clone();
+            let status_bar = status_bar.clone();
+            let last_translation = last_translation.clone();
+            let window = window.clone();
+            let generation = generation.clone();
+            let history = history.clone();
+            let history_store = history_store.clone();
+            let translator = translator.clone();
+            gtk::timeout_add(50, move || {
+                // Once the translate button has been pressed again, only the newest translation may be shown.
+                if !generation.is_current(request) {
+                    pending.cancel();
+                    return Continue(false)
+                }
+                let result = match pending.poll() {
+                    Some(result) => result,
+                    None         => return Continue(true)
+                };
+                let elapsed = started.elapsed();
+
+                // Immediately translate the text, and report what happened in the status bar.
+                let status = match result {
+                    Ok(result) => {
+                        translation_input.get_buffer().unwrap().set_text(result.text.as_str());
+                        *last_translation.borrow_mut() = Some((string.clone(), result.text.clone()));
+
+                        let entry = HistoryEntry {
+                            source:      result.source_language.clone(),
+                            target:      language.clone(),
+                            input:       string.clone(),
+                            translation: result.text.clone(),
+                            time:        SystemTime::now(),
+                        };
+                        add_to_history(&history_store, &entry, snippet_length);
+                        history.borrow_mut().push(entry);
+
+                        // Offer the alternative translations when hovering over the translation.
+                        let alternatives = result.alternatives.iter()
+                            .map(|alternative| format!("{} → {}", alternative.source, alternative.candidates.join(" | ")))
+                            .collect::<Vec<String>>();
+                        if alternatives.is_empty() {
+                            translation_input.set_tooltip_text(None);
+                        } else {
+                            translation_input.set_tooltip_text(Some(alternatives.join("\n").as_str()));
+                        }
+
+                        let detected = match (result.source_language, result.confidence) {
+                            (Some(language), Some(confidence)) => format!("{} ({:.0}%)", language.to_uppercase(), confidence * 100.0),
+                            (Some(language), None)             => language.to_uppercase(),
+                            (None, _)                          => "unknown".to_string()
+                        };
+                        // Whether Google was asked at all, as a cached or remembered translation does not count against its limits.
+                        let origin = match (result.origin, result.similarity) {
+                            (Origin::Google, _)                => String::new(),
+                            (Origin::Cache, _)                 => "  |  From the cache".to_string(),
+                            (Origin::Memory, Some(similarity)) => format!("  |  From the translation memory ({:.0}% match)", similarity * 100.0),
+                            (Origin::Memory, None)             => "  |  From the translation memory".to_string()
+                        };
+                        format!("{}  |  Detected: {}  |  {} ms{}", backend_status(&translator), detected, elapsed.as_millis(), origin)
+                    },
+                    Err(Error::RateLimited(wait)) => {
+                        let again = wait.map_or_else(|| "try again later".to_string(), |wait| format!("try again in {} s", wait.as_secs().max(1)));
+                        format!("{}  |  Rate limited by Google; {}  |  {} ms", backend_status(&translator), again, elapsed.as_millis())
+                    },
+                    Err(Error::Blocked) => {
+                        show_blocked_dialog(&window);
+                        format!("{}  |  Blocked by Google  |  {} ms", backend_status(&translator), elapsed.as_millis())
+                    },
+                    Err(why) => format!("{}  |  Translation failed: {}  |  {} ms", backend_status(&translator), why, elapsed.as_millis())
+                };
+                status_bar.pop(status_context);
+                status_bar.push(status_context, status.as_str());
+                Continue(false)
+            });
+        });
+    }
+
+    // Exit the program if it receives the delete event.
+    window.connect_delete_event(|_,_| {
+        gtk::main_quit();
+        Inhibit(false)
+    });
+
+    // Translate only the selected part of the input into a popover beside it, leaving the rest as it is.
+    let selection_popover: Popover = builder.get_object("selection_popover").unwrap();
+    let translate_selection = {
+        let selection_translation: Label = builder.get_object("selection_translation").unwrap();
+        let selection_popover = selection_popover.clone();
+        let translation_input = translation_input.clone();
+        let (language_box, translator) = (selection_language_box, selection_translator);
+        let generation = Generation::default();
+        move || {
+            let buffer = translation_input.get_buffer().unwrap();
+            let (start, end) = match buffer.get_selection_bounds() {
+                Some(bounds) => bounds,
+                None         => return
+            };
+            let text = buffer.get_text(&start, &end, false).unwrap_or_default();
+            if text.trim().is_empty() { return }
+
+            // Point at the start of the selection.
+            let location = translation_input.get_iter_location(&start);
+            let (x, y) = translation_input.buffer_to_window_coords(TextWindowType::Widget, location.x, location.y);
+            selection_popover.set_pointing_to(&gdk::Rectangle { x, y, width: location.width.max(1), height: location.height });
+            selection_popover.show_all();
+
+            let language = match resolve_language(language_box.get_active_text().unwrap_or_default().as_str()) {
+                Ok(language) => language,
+                Err(_)       => return selection_translation.set_text("Language not supported")
+            };
+            selection_translation.set_text("Translating…");
+            let request = generation.next();
+            let pending = translator.translate_async(text.as_str(), None, language.as_str());
+            let selection_translation = selection_translation.clone();
+            let generation = generation.clone();
+            gtk::timeout_add(50, move || {
+                if !generation.is_current(request) {
+                    pending.cancel();
+                    return Continue(false)
+                }
+                match pending.poll() {
+                    None             => return Continue(true),
+                    Some(Ok(result)) => selection_translation.set_text(result.text.as_str()),
+                    Some(Err(why))   => selection_translation.set_text(format!("Translation failed: {}", why).as_str())
+                }
+                Continue(false)
+            });
+        }
+    };
+
+    {   // Look up the word under the pointer in the dictionary once the pointer rests on it, in a popover above it.
+        let dictionary_popover: Popover = builder.get_object("dictionary_popover").unwrap();
+        let dictionary_entry: Label = builder.get_object("dictionary_entry").unwrap();
+        let hover = hover_mode();
+        let (language_box, translator) = (dictionary_language_box, dictionary_translator);
+        let last_translation = dictionary_last_translation;
+        // The word that is shown or being looked up, so that moving the pointer within it does nothing.
+        let hovered: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let changes = Rc::new(Cell::new(0));
+        let generation = Generation::default();
+        translation_input.connect_motion_notify_event(move |view, event| {
+            let held = hover == Hover::Always || (hover == Hover::Control && event.state.contains(gdk::CONTROL_MASK));
+            let buffer = view.get_buffer().unwrap();
+            let text = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap_or_default();
+            // Once the text has been translated in place, its words are no longer in the language being translated from.
+            let translated = last_translation.borrow().as_ref().is_some_and(|&(_, ref translation)| *translation == text);
+            let word = if held && !translated { word_at(view, event.x as i32, event.y as i32) } else { None };
+            let (word, area) = match word {
+                Some(word) => word,
+                None => {
+                    *hovered.borrow_mut() = None;
+                    generation.next();
+                    dictionary_popover.hide();
+                    return Inhibit(false)
+                }
+            };
+            if hovered.borrow().as_ref() == Some(&word) { return Inhibit(false) }
+            *hovered.borrow_mut() = Some(word.clone());
+            dictionary_popover.hide();
+            let request = generation.next();
+
+            let (dictionary_popover, dictionary_entry) = (dictionary_popover.clone(), dictionary_entry.clone());
+            let (language_box, translator, generation) = (language_box.clone(), translator.clone(), generation.clone());
+            debounce(&changes, HOVER_DELAY, move || {
+                if !generation.is_current(request) { return }
+                let language = match resolve_language(language_box.get_active_text().unwrap_or_default().as_str()) {
+                    Ok(language) => language,
+                    Err(_)       => return
+                };
+                dictionary_popover.set_pointing_to(&area);
+                dictionary_entry.set_markup(format!("<b>{}</b>\nLooking up…", escape_markup(word.as_str())).as_str());
+                dictionary_popover.show_all();
+
+                let (sender, receiver) = mpsc::channel();
+                let (translator, looked_up) = (translator.clone(), word.clone());
+                thread::spawn(move || { let _ = sender.send(translator.look_up(looked_up.as_str(), None, language.as_str())); });
+                let (dictionary_popover, dictionary_entry, generation) = (dictionary_popover.clone(), dictionary_entry.clone(), generation.clone());
+                let word = word.clone();
+                gtk::timeout_add(50, move || {
+                    if !generation.is_current(request) { return Continue(false) }
+                    let markup = match receiver.try_recv() {
+                        Ok(Ok(entries)) => dictionary_markup(word.as_str(), &entries),
+                        Ok(Err(why)) => format!("<b>{}</b>\nLookup failed: {}", escape_markup(word.as_str()), escape_markup(why.to_string().as_str())),
+                        Err(mpsc::TryRecvError::Empty) => return Continue(true),
+                        Err(mpsc::TryRecvError::Disconnected) => return Continue(false)
+                    };
+                    dictionary_entry.set_markup(markup.as_str());
+                    dictionary_popover.show_all();
+                    Continue(false)
+                });
+            });
+            Inhibit(false)
+        });
+    }
+
+    { // Program what the program should do when certain keys are pressed
+        let translate_button = wrapped_translation_button.clone();
+        window.connect_key_press_event(move |_,key| {
+            let control = key.get_state().contains(gdk::CONTROL_MASK);
+            match key.get_keyval() {
+                // Escape closes the translation of a selection before it closes the program.
+                key::Escape if selection_popover.get_visible() => selection_popover.hide(),
+                key::Escape => gtk::main_quit(),
+                key::Return  => translate_button.borrow().clicked(),
+                key::t | key::T if control => translate_selection(),
+                _ => ()
+            }
+            Inhibit(false)
+        });
+    }
+
+    // Show the window and start the program
+    window.show_all();
+    gtk::main();
+}
@@ -0,0 +1,80 @@
+//! The translations made during a session, and how each is summarised when they are listed.
+
+use std::time::SystemTime;
+
+/// How many characters of a translation are shown in a history listing, unless configured otherwise.
+pub const DEFAULT_SNIPPET_LENGTH: usize = 60;
+
+/// A translation which has been made, kept so that it can be looked back on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    /// The language that the input was written in, if it was given or detected.
+    pub source: Option<String>,
+    /// The language that the input was translated into.
+    pub target: String,
+    pub input: String,
+    pub translation: String,
+    /// When the translation was made.
+    pub time: SystemTime,
+}
+
+impl HistoryEntry {
+    /// The pair of languages, such as `EO → EN`, with `?` standing in for a source language which is not known.
+    pub fn badge(&self) -> String {
+        format!("{} → {}", self.source.as_deref().unwrap_or("?").to_uppercase(), self.target.to_uppercase())
+    }
+
+    /// The start of the translation on a single line, cut off with an ellipsis after `length` characters.
+    pub fn preview(&self, length: usize) -> String {
+        snippet(self.translation.as_str(), length)
+    }
+
+    /// How long before `now` the translation was made, such as `just now` or `5 minutes ago`.
+    pub fn age(&self, now: SystemTime) -> String {
+        let seconds = now.duration_since(self.time).map_or(0, |elapsed| elapsed.as_secs());
+        let (count, unit) = match seconds {
+            0..=59       => return "just now".to_string(),
+            60..=3599    => (seconds / 60, "minute"),
+            3600..=86399 => (seconds / 3600, "hour"),
+            _            => (seconds / 86400, "day")
+        };
+        format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+    }
+}
+
+/// Collapse the whitespace in `text`, including line breaks, and cut it off with an ellipsis if it is longer
+/// than `length` characters, preferably at the end of a word.
+pub fn snippet(text: &str, length: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() <= length {
+        return text;
+    }
+    let cut = text.char_indices().nth(length.saturating_sub(1)).map_or(text.len(), |(index, _)| index);
+    // Back up to the last space, unless that would leave too little to recognise the text by.
+    let cut = match text[..cut].rfind(' ') {
+        Some(space) if space >= cut / 2 => space,
+        _ => cut
+    };
+    format!("{}…", text[..cut].trim_end())
+}
+
+#[test]
+fn test_history_entry() {
+    use std::time::Duration;
+
+    let time = SystemTime::now();
+    let entry = HistoryEntry {
+        source:      Some("eo".to_string()),
+        target:      "EN".to_string(),
+        input:       "Mi estas ne vin.\nVi estas ne min.".to_string(),
+        translation: "I am not you.\nYou are not me.".to_string(),
+        time,
+    };
+    assert_eq!(entry.badge().as_str(), "EO → EN");
+    assert_eq!(entry.preview(60).as_str(), "I am not you. You are not me.");
+    assert_eq!(entry.preview(16).as_str(), "I am not you.…");
+    assert_eq!(entry.age(time + Duration::from_secs(30)).as_str(), "just now");
+    assert_eq!(entry.age(time + Duration::from_secs(60)).as_str(), "1 minute ago");
+    assert_eq!(entry.age(time + Duration::from_secs(3 * 86400)).as_str(), "3 days ago");
+    assert_eq!(snippet("Unbrokenlongword", 5).as_str(), "Unbr…");
+}
@@ -0,0 +1,293 @@
+//! Translation of HTML, in which only the text is translated and the markup is kept exactly as it was.
+//!
+//! Text is translated a run at a time, where a run is the text between two block-level tags, such as a
+//! paragraph or a list item, so that a sentence is translated whole even when part of it is a link or in
+//! bold. The inline tags within a run are masked in the same way as names, with `protect` and `restore`, so
+//! that Google may move them along with the words they surround. Scripts, styles, preformatted text, code
+//! and elements marked with `translate="no"` or the class `notranslate` are left untranslated.
+
+use entity::{find_bracketed_numbers, restore};
+
+/// Elements which may be part of a sentence, and so do not end a run of text.
+const INLINE: &'static [&'static str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "cite", "data", "dfn", "em", "font", "i", "img", "mark", "q", "s",
+    "small", "span", "strong", "sub", "sup", "time", "u", "wbr",
+];
+
+/// Inline elements whose contents are code rather than prose, which are kept whole within a run.
+const CODE: &'static [&'static str] = &["code", "kbd", "samp", "var"];
+
+/// Elements whose contents are never translated.
+const UNTRANSLATED: &'static [&'static str] = &["script", "style", "textarea", "pre", "svg", "math", "template"];
+
+/// Elements whose contents are not markup, in which a `<` does not begin a tag.
+const RAW: &'static [&'static str] = &["script", "style", "textarea"];
+
+/// Elements which have no contents and no closing tag.
+const VOID: &'static [&'static str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Translate the text of the HTML in `input` with `translate`, which is given each run of text, with its
+/// entities decoded and its whitespace collapsed, along with the offset in `input` at which it begins, and
+/// returns its translation. The inline tags within a run are given to `translate` as placeholders such as
+/// `[0]`, which it should keep. Any which it loses are put back at the end of the run, so that the tags stay
+/// balanced.
+pub fn translate_html<F: FnMut(&str, usize) -> String>(input: &str, mut translate: F) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut run = Run::default();
+    let mut rest = input;
+    while !rest.is_empty() {
+        if run.original.is_empty() { run.offset = input.len() - rest.len(); }
+        let length = match tag_length(rest) {
+            Some(length) => length,
+            None => {
+                // Text runs up to the next '<' which begins a tag.
+                let end = rest.char_indices().skip(1).find(|&(index, c)| c == '<' && tag_length(&rest[index..]).is_some())
+                    .map_or(rest.len(), |(index, _)| index);
+                run.text(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+        };
+        let tag = &rest[..length];
+        let (name, closing) = tag_name(tag);
+        let name = name.as_str();
+        let opening = !closing && !name.is_empty() && !tag.ends_with("/>") && !VOID.contains(&name);
+        // Elements which are not translated, and code within a sentence, are taken whole.
+        let whole = opening && (UNTRANSLATED.contains(&name) || CODE.contains(&name) || untranslated(tag));
+        let length = if whole { element_length(rest, name) } else { length };
+        let element = &rest[..length];
+        if INLINE.contains(&name) || CODE.contains(&name) {
+            run.tag(element);
+        } else {
+            run.flush(&mut output, &mut translate);
+            output.push_str(element);
+        }
+        rest = &rest[length..];
+    }
+    run.flush(&mut output, &mut translate);
+    output
+}
+
+/// The text between two block-level tags, along with the inline tags within it.
+#[derive(Default)]
+struct Run {
+    /// The run as it was written, which is kept if there is nothing in it to translate.
+    original: String,
+    /// The text with its entities decoded and its tags replaced by placeholders.
+    text: String,
+    tags: Vec<String>,
+    /// Where the run begins in the input.
+    offset: usize,
+}
+
+impl Run {
+    fn text(&mut self, text: &str) {
+        self.original.push_str(text);
+        // A bracketed number in the text, such as a citation, would be taken for the placeholder of a tag, so it
+        // is masked as one of its own.
+        let text = unescape_html(text);
+        let mut last = 0;
+        for (start, end) in find_bracketed_numbers(text.as_str()) {
+            self.text.push_str(&text[last..start]);
+            self.text.push_str(format!("[{}]", self.tags.len()).as_str());
+            self.tags.push(text[start..end].to_string());
+            last = end;
+        }
+        self.text.push_str(&text[last..]);
+    }
+
+    fn tag(&mut self, tag: &str) {
+        self.original.push_str(tag);
+        self.text.push_str(format!("[{}]", self.tags.len()).as_str());
+        self.tags.push(tag.to_string());
+    }
+
+    /// Translate the run and write it to `output`, keeping the whitespace around it.
+    fn flush<F: FnMut(&str, usize) -> String>(&mut self, output: &mut String, translate: &mut F) {
+        let run = ::std::mem::take(self);
+        if !run.text.chars().any(char::is_alphabetic) {
+            output.push_str(run.original.as_str());
+            return;
+        }
+        // Only the whitespace which HTML collapses is collapsed, so that no-break spaces are kept.
+        let collapsible = |c: char| c.is_ascii_whitespace();
+        let start = run.text.len() - run.text.trim_start_matches(collapsible).len();
+        let end = run.text.trim_end_matches(collapsible).len();
+        output.push_str(&run.text[..start]);
+        let translation = translate(run.text[start..end].split_ascii_whitespace().collect::<Vec<&str>>().join(" ").as_str(), run.offset + start);
+        let (translation, missing) = restore(escape_html(translation.as_str()).as_str(), &run.tags);
+        output.push_str(translation.as_str());
+        for tag in missing {
+            output.push_str(tag.as_str());
+        }
+        output.push_str(&run.text[end..]);
+    }
+}
+
+/// The length of the tag, comment or declaration at the start of `input`, if it begins with one.
+fn tag_length(input: &str) -> Option<usize> {
+    if input.starts_with("<!--") {
+        return Some(input.find("-->").map_or(input.len(), |end| end + 3));
+    }
+    if input.starts_with("<!") || input.starts_with("<?") {
+        return Some(input.find('>').map_or(input.len(), |end| end + 1));
+    }
+    let name = input.strip_prefix("</").or_else(|| input.strip_prefix('<'))?;
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    // A '>' within a quoted attribute value does not end the tag.
+    let mut quote = None;
+    for (index, character) in input.char_indices() {
+        match (quote, character) {
+            (None, '"') | (None, '\'') => quote = Some(character),
+            (Some(open), _) if open == character => quote = None,
+            (None, '>') => return Some(index + 1),
+            _ => ()
+        }
+    }
+    Some(input.len())
+}
+
+/// The name of `tag` in lower case, and whether it is a closing tag. Comments and declarations have no name.
+fn tag_name(tag: &str) -> (String, bool) {
+    let closing = tag.starts_with("</");
+    let name = tag.trim_start_matches("</").trim_start_matches('<')
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')).next().unwrap_or("");
+    let name = if tag.starts_with("<!") || tag.starts_with("<?") { "" } else { name };
+    (name.to_ascii_lowercase(), closing)
+}
+
+/// Whether `tag` marks its element as not to be translated, with `translate="no"` or the class `notranslate`.
+fn untranslated(tag: &str) -> bool {
+    let tag = tag.to_ascii_lowercase();
+    let translate = ["translate=\"no\"", "translate='no'", "translate=no"].iter()
+        .any(|attribute| tag.match_indices(attribute).any(|(index, _)| tag[..index].ends_with(char::is_whitespace)));
+    let class = tag.match_indices("class=").filter(|&(index, _)| tag[..index].ends_with(char::is_whitespace)).any(|(index, _)| {
+        let value = &tag[index + 6..];
+        let value = match value.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => value[1..].split(quote).next().unwrap_or(""),
+            _ => value.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or("")
+        };
+        value.split_whitespace().any(|class| class == "notranslate")
+    });
+    translate || class
+}
+
+/// The length of the element named `name` at the start of `input`, from its opening tag to its closing tag,
+/// taking elements of the same name nested within it into account. An element which is never closed runs to
+/// the end of the input.
+fn element_length(input: &str, name: &str) -> usize {
+    let start = tag_length(input).unwrap_or(input.len());
+    if RAW.contains(&name) {
+        let lower = input.to_ascii_lowercase();
+        return lower[start..].find(format!("</{}", name).as_str())
+            .and_then(|end| tag_length(&input[start + end..]).map(|length| start + end + length))
+            .unwrap_or(input.len());
+    }
+    let mut depth = 1;
+    let mut index = start;
+    while let Some(offset) = input[index..].find('<') {
+        index += offset;
+        let length = match tag_length(&input[index..]) {
+            Some(length) => length,
+            None         => { index += 1; continue }
+        };
+        let tag = &input[index..index + length];
+        let (tag_name, closing) = tag_name(tag);
+        index += length;
+        if tag_name == name {
+            if closing { depth -= 1 } else if !tag.ends_with("/>") { depth += 1 }
+            if depth == 0 { return index }
+        }
+    }
+    input.len()
+}
+
+/// Decode the character references in `text`. Named references other than the few that are common in
+/// prose, such as `&eacute;`, are kept as they are.
+fn unescape_html(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(';').filter(|&end| end <= 10);
+        let character = end.and_then(|end| match &rest[1..end] {
+            "amp"  => Some('&'),
+            "lt"   => Some('<'),
+            "gt"   => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            entity if entity.starts_with("#x") || entity.starts_with("#X") => u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32),
+            entity if entity.starts_with('#') => entity[1..].parse::<u32>().ok().and_then(::std::char::from_u32),
+            _ => None
+        });
+        match (character, end) {
+            (Some(character), Some(end)) => { unescaped.push(character); rest = &rest[end + 1..]; },
+            (_, Some(end)) if rest[1..end].chars().all(char::is_alphanumeric) => { unescaped.push_str(&rest[..end + 1]); rest = &rest[end + 1..]; },
+            _ => { unescaped.push('&'); rest = &rest[1..]; }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Encode the characters of `text` which would otherwise be read as markup, leaving named references which
+/// `unescape_html` kept as they are.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, character) in text.char_indices() {
+        match character {
+            '&' => {
+                let reference = text[index + 1..].find(';').filter(|&end| end > 0 && end < 10)
+                    .is_some_and(|end| text[index + 1..index + 1 + end].chars().all(char::is_alphanumeric));
+                escaped.push_str(if reference { "&" } else { "&amp;" });
+            },
+            '<'      => escaped.push_str("&lt;"),
+            '>'      => escaped.push_str("&gt;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            _        => escaped.push(character)
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_translate_html() {
+    let input = "<!DOCTYPE html>\n<html lang=\"eo\"><head><title>Saluton</title><style>p > a { color: red }</style></head>\n\
+        <body>\n  <p class=\"intro\">Mi estas <b>ne</b> vin &amp; <a href=\"/vi?a=1&amp;b=2\" title=\"Vi > mi\">vi</a> estas ne min.</p>\n\
+        <!-- Saluton -->\n  <pre>Saluton</pre><p>Uzu <code>ls &lt;dosierujo&gt;</code> nun.<br>Fino</p>\n\
+        <p translate=\"no\">Saluton <i>mondo</i></p><p>Kaf&eacute;&nbsp;kaj <span class=\"x notranslate\">Esperanto</span></p>\n</body></html>";
+    let mut sent = Vec::new();
+    let mut offsets = Vec::new();
+    let output = translate_html(input, |text, offset| {
+        offsets.push(offset);
+        sent.push(text.to_string());
+        match text {
+            "Saluton"                                  => "Hello".to_string(),
+            "Mi estas [0]ne[1] vin & [2]vi[3] estas ne min." => "I am [0]not[1] you & [2]you[3] are not me.".to_string(),
+            "Uzu [0] nun.[1]Fino"                      => "Use [0] now.[1]End".to_string(),
+            // A translation which loses a placeholder still has its tag put back.
+            "Kaf&eacute;\u{a0}kaj [0]"                  => "Café and".to_string(),
+            _                                          => panic!("unexpected text {:?}", text)
+        }
+    });
+    assert_eq!(sent.len(), 4);
+    assert!(input[offsets[1]..].starts_with("Mi estas"));
+    assert_eq!(output, "<!DOCTYPE html>\n<html lang=\"eo\"><head><title>Hello</title><style>p > a { color: red }</style></head>\n\
+        <body>\n  <p class=\"intro\">I am <b>not</b> you &amp; <a href=\"/vi?a=1&amp;b=2\" title=\"Vi > mi\">you</a> are not me.</p>\n\
+        <!-- Saluton -->\n  <pre>Saluton</pre><p>Use <code>ls &lt;dosierujo&gt;</code> now.<br>End</p>\n\
+        <p translate=\"no\">Saluton <i>mondo</i></p><p>Café and<span class=\"x notranslate\">Esperanto</span></p>\n</body></html>");
+    assert_eq!(translate_html("1 < 2 <3", |_, _| panic!("nothing to translate")), "1 < 2 <3");
+
+    // A citation is kept apart from the placeholders of the tags.
+    let cited = "<p>See note [1] and <b>this</b> word.</p>";
+    assert_eq!(translate_html(cited, |text, _| {
+        assert_eq!(text, "See note [0] and [1]this[2] word.");
+        text.to_string()
+    }), cited);
+}
@@ -0,0 +1,327 @@
+//! A small JSON reader and writer, sufficient for the responses returned by Google Translate.
+//!
+//! Google's responses are not always strictly valid JSON: empty array elements are elided, as in `[1,,3]`.
+//! The parser therefore reads an elided element as `null`.
+
+use std::fmt;
+
+/// A parsed JSON value. Object members are kept in the order that they were written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a complete JSON document, failing if anything other than whitespace follows the value.
+    pub fn parse(input: &str) -> Result<Json, String> {
+        let mut parser = Parser { input: input.as_bytes(), position: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.position != parser.input.len() {
+            return Err(format!("unexpected trailing characters at byte {}", parser.position));
+        }
+        Ok(value)
+    }
+
+    /// Obtain the element at `index` if this is an array which is long enough.
+    pub fn get(&self, index: usize) -> Option<&Json> {
+        match *self {
+            Json::Array(ref elements) => elements.get(index),
+            _ => None
+        }
+    }
+
+    /// Obtain the member named `key` if this is an object which has one.
+    pub fn member(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|member| member.0 == key).map(|member| &member.1),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref elements) => Some(elements),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref string) => Some(string),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Number(number) => Some(number),
+            _ => None
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(string: &'a str) -> Json { Json::String(string.to_string()) }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Json { Json::String(string) }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json { value.map_or(Json::Null, Into::into) }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(elements: Vec<T>) -> Json { Json::Array(elements.into_iter().map(Into::into).collect()) }
+}
+
+impl From<f64> for Json {
+    fn from(number: f64) -> Json { Json::Number(number) }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json { Json::Bool(value) }
+}
+
+/// Writes the value as compact JSON.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) if number.is_finite() => write!(f, "{}", number),
+            Json::Number(_) => f.write_str("null"),
+            Json::String(ref string) => write_string(f, string),
+            Json::Array(ref elements) => {
+                f.write_str("[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index != 0 { f.write_str(",")?; }
+                    write!(f, "{}", element)?;
+                }
+                f.write_str("]")
+            },
+            Json::Object(ref members) => {
+                f.write_str("{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index != 0 { f.write_str(",")?; }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, string: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for character in string.chars() {
+        match character {
+            '"'  => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?
+        }
+    }
+    f.write_str("\"")
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> { self.input.get(self.position).cloned() }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, String> {
+        match self.peek() {
+            Some(byte) => Err(format!("expected {} but found '{}' at byte {}", expected, byte as char, self.position)),
+            None       => Err(format!("expected {} but the input ended", expected))
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        if self.input[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            self.error(literal)
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'[') => self.array(),
+            Some(b'{') => self.object(),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            _ => self.error("a value")
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut elements = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(elements));
+        }
+        loop {
+            // An element which has been left out entirely, as in `[1,,3]`, is read as null.
+            self.whitespace();
+            match self.peek() {
+                Some(b',') | Some(b']') => elements.push(Json::Null),
+                _ => elements.push(self.value()?)
+            }
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(elements));
+                },
+                _ => return self.error("',' or ']'")
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.whitespace();
+            if self.peek() != Some(b'"') { return self.error("a member name"); }
+            let key = self.string()?;
+            self.whitespace();
+            if self.peek() != Some(b':') { return self.error("':'"); }
+            self.position += 1;
+            members.push((key, self.value()?));
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                },
+                _ => return self.error("',' or '}'")
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self.input.get(self.position..self.position + 4)
+            .and_then(|digits| ::std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+        match digits {
+            Some(value) => {
+                self.position += 4;
+                Ok(value)
+            },
+            None => self.error("four hexadecimal digits")
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    break
+                },
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"')  => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/')  => '/',
+                        Some(b'b')  => '\u{8}',
+                        Some(b'f')  => '\u{c}',
+                        Some(b'n')  => '\n',
+                        Some(b'r')  => '\r',
+                        Some(b't')  => '\t',
+                        Some(b'u')  => {
+                            self.position += 1;
+                            let mut code = self.hex()?;
+                            // Characters outside of the basic multilingual plane are written as surrogate pairs.
+                            if (0xD800..0xDC00).contains(&code) && self.input[self.position..].starts_with(b"\\u") {
+                                self.position += 2;
+                                let low = self.hex()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            let character = ::std::char::from_u32(code).unwrap_or('\u{FFFD}');
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+                            continue
+                        },
+                        _ => return self.error("an escape sequence")
+                    };
+                    self.position += 1;
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                },
+                Some(byte) => {
+                    self.position += 1;
+                    bytes.push(byte);
+                },
+                None => return self.error("'\"'")
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "string is not valid UTF-8".to_string())
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E') | Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        let number = ::std::str::from_utf8(&self.input[start..self.position]).ok().and_then(|number| number.parse::<f64>().ok());
+        match number {
+            Some(number) => Ok(Json::Number(number)),
+            None => {
+                self.position = start;
+                self.error("a number")
+            }
+        }
+    }
+}
+
+#[test]
+fn test_json() {
+    let value = Json::parse(r#"{"a": [1, -2.5e1, true, null, "x\"é😀"], "b": {}}"#).unwrap();
+    assert_eq!(value.member("a").and_then(|a| a.get(1)).and_then(Json::as_f64), Some(-25.0));
+    assert_eq!(value.member("a").and_then(|a| a.get(4)).and_then(Json::as_str), Some("x\"é😀"));
+    assert_eq!(value.to_string(), r#"{"a":[1,-25,true,null,"x\"é😀"],"b":{}}"#);
+    assert_eq!(Json::parse("[1,,3]"), Ok(Json::Array(vec![Json::Number(1.0), Json::Null, Json::Number(3.0)])));
+    assert!(Json::parse("<html>").is_err());
+    assert!(Json::parse("[1, 2").is_err());
+    assert!(Json::parse("[1] x").is_err());
+}
@@ -0,0 +1,317 @@
+//! Language names, codes, and the script variants that Google does not provide directly.
+
+/// The languages that Google translates between, as pairs of codes and names, in alphabetical order of name.
+/// Serbian is listed a further two times, for its explicit script variants.
+pub const LANGUAGES: &'static [(&'static str, &'static str)] = &[
+    ("AF",       "Afrikaans"),
+    ("SQ",       "Albanian"),
+    ("AM",       "Amharic"),
+    ("AR",       "Arabic"),
+    ("HY",       "Armenian"),
+    ("AS",       "Assamese"),
+    ("AY",       "Aymara"),
+    ("AZ",       "Azerbaijani"),
+    ("BM",       "Bambara"),
+    ("EU",       "Basque"),
+    ("BE",       "Belarusian"),
+    ("BN",       "Bengali"),
+    ("BHO",      "Bhojpuri"),
+    ("BS",       "Bosnian"),
+    ("BG",       "Bulgarian"),
+    ("CA",       "Catalan"),
+    ("CEB",      "Cebuano"),
+    ("NY",       "Chichewa"),
+    ("ZH-CN",    "Chinese (Simplified)"),
+    ("ZH-TW",    "Chinese (Traditional)"),
+    ("CO",       "Corsican"),
+    ("HR",       "Croatian"),
+    ("CS",       "Czech"),
+    ("DA",       "Danish"),
+    ("DV",       "Dhivehi"),
+    ("DOI",      "Dogri"),
+    ("NL",       "Dutch"),
+    ("EN",       "English"),
+    ("EO",       "Esperanto"),
+    ("ET",       "Estonian"),
+    ("EE",       "Ewe"),
+    ("TL",       "Filipino"),
+    ("FI",       "Finnish"),
+    ("FR",       "French"),
+    ("FY",       "Frisian"),
+    ("GL",       "Galician"),
+    ("KA",       "Georgian"),
+    ("DE",       "German"),
+    ("EL",       "Greek"),
+    ("GN",       "Guarani"),
+    ("GU",       "Gujarati"),
+    ("HT",       "Haitian Creole"),
+    ("HA",       "Hausa"),
+    ("HAW",      "Hawaiian"),
+    ("IW",       "Hebrew"),
+    ("HI",       "Hindi"),
+    ("HMN",      "Hmong"),
+    ("HU",       "Hungarian"),
+    ("IS",       "Icelandic"),
+    ("IG",       "Igbo"),
+    ("ILO",      "Ilocano"),
+    ("ID",       "Indonesian"),
+    ("GA",       "Irish"),
+    ("IT",       "Italian"),
+    ("JA",       "Japanese"),
+    ("JW",       "Javanese"),
+    ("KN",       "Kannada"),
+    ("KK",       "Kazakh"),
+    ("KM",       "Khmer"),
+    ("RW",       "Kinyarwanda"),
+    ("GOM",      "Konkani"),
+    ("KO",       "Korean"),
+    ("KRI",      "Krio"),
+    ("KU",       "Kurdish (Kurmanji)"),
+    ("CKB",      "Kurdish (Sorani)"),
+    ("KY",       "Kyrgyz"),
+    ("LO",       "Lao"),
+    ("LA",       "Latin"),
+    ("LV",       "Latvian"),
+    ("LN",       "Lingala"),
+    ("LT",       "Lithuanian"),
+    ("LG",       "Luganda"),
+    ("LB",       "Luxembourgish"),
+    ("MK",       "Macedonian"),
+    ("MAI",      "Maithili"),
+    ("MG",       "Malagasy"),
+    ("MS",       "Malay"),
+    ("ML",       "Malayalam"),
+    ("MT",       "Maltese"),
+    ("MI",       "Maori"),
+    ("MR",       "Marathi"),
+    ("MNI-MTEI", "Meiteilon (Manipuri)"),
+    ("LUS",      "Mizo"),
+    ("MN",       "Mongolian"),
+    ("MY",       "Myanmar (Burmese)"),
+    ("NE",       "Nepali"),
+    ("NO",       "Norwegian"),
+    ("OR",       "Odia (Oriya)"),
+    ("OM",       "Oromo"),
+    ("PS",       "Pashto"),
+    ("FA",       "Persian"),
+    ("PL",       "Polish"),
+    ("PT",       "Portuguese"),
+    ("PA",       "Punjabi"),
+    ("QU",       "Quechua"),
+    ("RO",       "Romanian"),
+    ("RU",       "Russian"),
+    ("SM",       "Samoan"),
+    ("SA",       "Sanskrit"),
+    ("GD",       "Scots Gaelic"),
+    ("NSO",      "Sepedi"),
+    ("SR",       "Serbian"),
+    ("SR-CYRL",  "Serbian (Cyrillic)"),
+    ("SR-LATN",  "Serbian (Latin)"),
+    ("ST",       "Sesotho"),
+    ("SN",       "Shona"),
+    ("SD",       "Sindhi"),
+    ("SI",       "Sinhala"),
+    ("SK",       "Slovak"),
+    ("SL",       "Slovenian"),
+    ("SO",       "Somali"),
+    ("ES",       "Spanish"),
+    ("SU",       "Sundanese"),
+    ("SW",       "Swahili"),
+    ("SV",       "Swedish"),
+    ("TG",       "Tajik"),
+    ("TA",       "Tamil"),
+    ("TT",       "Tatar"),
+    ("TE",       "Telugu"),
+    ("TH",       "Thai"),
+    ("TI",       "Tigrinya"),
+    ("TS",       "Tsonga"),
+    ("TR",       "Turkish"),
+    ("TK",       "Turkmen"),
+    ("AK",       "Twi"),
+    ("UK",       "Ukrainian"),
+    ("UR",       "Urdu"),
+    ("UG",       "Uyghur"),
+    ("UZ",       "Uzbek"),
+    ("VI",       "Vietnamese"),
+    ("CY",       "Welsh"),
+    ("XH",       "Xhosa"),
+    ("YI",       "Yiddish"),
+    ("YO",       "Yoruba"),
+    ("ZU",       "Zulu"),
+];
+
+/// Three-letter ISO 639-2 codes, and other codes which Google does not use, for the languages that Google
+/// knows by another code.
+const ALIASES: &'static [(&'static str, &'static str)] = &[
+    ("ARA", "AR"), ("BUL", "BG"), ("CAT", "CA"), ("CES", "CS"), ("CHI", "ZH-CN"), ("CZE", "CS"),
+    ("DAN", "DA"), ("DEU", "DE"), ("DUT", "NL"), ("ELL", "EL"), ("ENG", "EN"), ("EPO", "EO"),
+    ("EST", "ET"), ("FIL", "TL"), ("FIN", "FI"), ("FRA", "FR"), ("FRE", "FR"), ("GER", "DE"),
+    ("GRE", "EL"), ("HE", "IW"), ("HEB", "IW"), ("HIN", "HI"), ("HRV", "HR"), ("HUN", "HU"),
+    ("IND", "ID"), ("ISL", "IS"), ("ITA", "IT"), ("JPN", "JA"), ("JV", "JW"), ("KOR", "KO"),
+    ("LAT", "LA"), ("LAV", "LV"), ("LIT", "LT"), ("NB", "NO"), ("NLD", "NL"), ("NN", "NO"),
+    ("NOR", "NO"), ("PER", "FA"), ("FAS", "FA"), ("POL", "PL"), ("POR", "PT"), ("RON", "RO"),
+    ("RUM", "RO"), ("RUS", "RU"), ("SLK", "SK"), ("SLO", "SK"), ("SLV", "SL"), ("SPA", "ES"),
+    ("SRP", "SR"), ("SWE", "SV"), ("THA", "TH"), ("TUR", "TR"), ("UKR", "UK"), ("URD", "UR"),
+    ("VIE", "VI"), ("WEL", "CY"), ("CYM", "CY"), ("ZHO", "ZH-CN"),
+];
+
+/// Obtain the language code for one of the language names in `LANGUAGES`, such as those displayed in the GUI.
+pub fn match_language(input: &str) -> Option<&'static str> {
+    LANGUAGES.iter().find(|&&(_, name)| name == input).map(|&(code, _)| code)
+}
+
+/// Obtain the name of the language with the given code, which may be in either case.
+pub fn language_name(code: &str) -> Option<&'static str> {
+    let code = code.to_uppercase();
+    LANGUAGES.iter().find(|&&(known, _)| known == code).map(|&(_, name)| name)
+}
+
+/// Whether Google can translate into or out of the language with the given code. Script variants
+/// understood by `split_script`, such as `ZH-HANT`, are supported if the language that they are a
+/// variant of is.
+pub fn is_supported(code: &str) -> bool {
+    language_name(code).is_some() || language_name(split_script(code).0.as_str()).is_some()
+}
+
+/// Normalise a language given by its code, its three-letter ISO code or its name, in any case and with
+/// small misspellings, to the code that Google expects, such as `EN` for `eng`, `English` or `Engish`.
+/// When it cannot be settled on a single language, the codes of the nearest languages are returned instead.
+pub fn resolve_language(input: &str) -> Result<String, Vec<&'static str>> {
+    let input = input.trim();
+    let code = input.to_uppercase();
+    if is_supported(code.as_str()) { return Ok(code); }
+    if let Some(&(_, code)) = ALIASES.iter().find(|&&(alias, _)| alias == code) { return Ok(code.to_string()); }
+
+    let input = input.to_lowercase();
+    if let Some(&(code, _)) = LANGUAGES.iter().find(|&&(_, name)| name.to_lowercase() == input) { return Ok(code.to_string()); }
+
+    // Each language may also be called by either part of a name such as 'Myanmar (Burmese)'.
+    let names = LANGUAGES.iter().flat_map(|&(code, name)| {
+        let (main, aside) = match name.find(" (") {
+            Some(index) => (&name[..index], Some(name[index + 2..].trim_end_matches(')'))),
+            None        => (name, None)
+        };
+        Some(name).into_iter().chain(Some(main)).chain(aside).map(move |name| (code, name.to_lowercase()))
+    }).collect::<Vec<(&'static str, String)>>();
+    let mut matches = names.iter().filter(|(_, name)| *name == input).map(|&(code, _)| code).collect::<Vec<&str>>();
+    let mut close = true;
+    if matches.is_empty() {
+        // Allow for about one mistake in every four letters, and suggest anything within twice that.
+        let distances = names.iter().map(|&(code, ref name)| (code, distance(input.as_str(), name.as_str()))).collect::<Vec<_>>();
+        let nearest = distances.iter().map(|&(_, distance)| distance).min().unwrap_or(0);
+        let tolerance = (input.chars().count() / 4).max(1);
+        matches = distances.iter().filter(|&&(_, distance)| distance == nearest && distance <= tolerance * 2)
+            .map(|&(code, _)| code).collect();
+        close = nearest <= tolerance;
+    }
+    matches.dedup();
+    match matches.as_slice() {
+        [code] if close => Ok(code.to_string()),
+        _      => Err(matches)
+    }
+}
+
+/// The number of characters which must be inserted, deleted or substituted to turn `a` into `b`.
+pub fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &y) in b.iter().enumerate() {
+            current.push((previous[j] + (x != y) as usize).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The writing system that a translation should be delivered in, when Google does not offer it directly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Script {
+    Latin
+}
+
+/// Separate an explicit script variant from a language code, returning the code that Google
+/// understands along with the script conversion that must be applied to its output.
+pub fn split_script(language: &str) -> (String, Option<Script>) {
+    match language.to_uppercase().as_str() {
+        "SR-CYRL" | "SR-CYRILLIC" => ("SR".to_string(), None),
+        "SR-LATN" | "SR-LATIN"    => ("SR".to_string(), Some(Script::Latin)),
+        "ZH" | "ZH-HANS"          => ("ZH-CN".to_string(), None),
+        "ZH-HANT"                 => ("ZH-TW".to_string(), None),
+        code => (code.to_string(), None)
+    }
+}
+
+/// Transliterate Serbian Cyrillic into Serbian Latin (Gaj's alphabet). A capital Љ, Њ or Џ becomes a digraph
+/// such as `Lj`, or `LJ` in a word written in capitals: when the letter after it is a capital too, or when it
+/// ends a word whose letter before it is a capital.
+pub fn serbian_to_latin(input: &str, output: &mut String) {
+    let mut characters = input.chars().peekable();
+    let mut previous: Option<char> = None;
+    while let Some(character) = characters.next() {
+        let capitals = match characters.peek() {
+            Some(next) if next.is_alphabetic() => next.is_uppercase(),
+            _ => previous.is_some_and(char::is_uppercase)
+        };
+        previous = Some(character);
+        let latin = match character {
+            'А' => "A",  'а' => "a",  'Б' => "B",  'б' => "b",  'В' => "V",  'в' => "v",
+            'Г' => "G",  'г' => "g",  'Д' => "D",  'д' => "d",  'Ђ' => "Đ",  'ђ' => "đ",
+            'Е' => "E",  'е' => "e",  'Ж' => "Ž",  'ж' => "ž",  'З' => "Z",  'з' => "z",
+            'И' => "I",  'и' => "i",  'Ј' => "J",  'ј' => "j",  'К' => "K",  'к' => "k",
+            'Л' => "L",  'л' => "l",  'Љ' => "Lj", 'љ' => "lj", 'М' => "M",  'м' => "m",
+            'Н' => "N",  'н' => "n",  'Њ' => "Nj", 'њ' => "nj", 'О' => "O",  'о' => "o",
+            'П' => "P",  'п' => "p",  'Р' => "R",  'р' => "r",  'С' => "S",  'с' => "s",
+            'Т' => "T",  'т' => "t",  'Ћ' => "Ć",  'ћ' => "ć",  'У' => "U",  'у' => "u",
+            'Ф' => "F",  'ф' => "f",  'Х' => "H",  'х' => "h",  'Ц' => "C",  'ц' => "c",
+            'Ч' => "Č",  'ч' => "č",  'Џ' => "Dž", 'џ' => "dž", 'Ш' => "Š",  'ш' => "š",
+            _ => { output.push(character); continue }
+        };
+        if latin.chars().count() > 1 && character.is_uppercase() && capitals {
+            output.push_str(latin.to_uppercase().as_str());
+        } else {
+            output.push_str(latin);
+        }
+    }
+}
+
+#[test]
+fn test_script_variants() {
+    assert_eq!(split_script("zh-TW"), ("ZH-TW".to_string(), None));
+    assert_eq!(split_script("sr-Latn"), ("SR".to_string(), Some(Script::Latin)));
+    let mut output = String::new();
+    serbian_to_latin("Љубав и џеп, Ђорђе!", &mut output);
+    assert_eq!(output.as_str(), "Ljubav i džep, Đorđe!");
+    output.clear();
+    serbian_to_latin("ЉУБАВ, ЏЕП И Њ, КОЊ.", &mut output);
+    assert_eq!(output.as_str(), "LJUBAV, DŽEP I Nj, KONJ.");
+    output.clear();
+    serbian_to_latin("љA њБ Џа", &mut output);
+    assert_eq!(output.as_str(), "ljA njB Dža");
+}
+
+#[test]
+fn test_languages() {
+    assert_eq!(match_language("German"), Some("DE"));
+    assert_eq!(match_language("Serbian (Latin)"), Some("SR-LATN"));
+    assert_eq!(match_language("Klingon"), None);
+    assert_eq!(language_name("zh-tw"), Some("Chinese (Traditional)"));
+    assert!(is_supported("eo") && is_supported("ZH-HANS") && is_supported("sr-Latn"));
+    assert!(!is_supported("XX"));
+}
+
+#[test]
+fn test_resolve_language() {
+    for input in &["en", "EN", "eng", "English", "english", "Engish", " Englsh "] {
+        assert_eq!(resolve_language(input), Ok("EN".to_string()));
+    }
+    assert_eq!(resolve_language("zh-hant"), Ok("ZH-HANT".to_string()));
+    assert_eq!(resolve_language("ger"), Ok("DE".to_string()));
+    assert_eq!(resolve_language("Burmese"), Ok("MY".to_string()));
+    assert_eq!(resolve_language("Serbian"), Ok("SR".to_string()));
+    assert_eq!(resolve_language("chinese"), Err(vec!["ZH-CN", "ZH-TW"]));
+    assert_eq!(resolve_language("Germen"), Ok("DE".to_string()));
+    assert_eq!(resolve_language("Fernch"), Err(vec!["FR"]));
+    assert_eq!(resolve_language("Klingon"), Err(vec![]));
+}
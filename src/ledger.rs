@@ -0,0 +1,123 @@
+//! A ledger of the chunks of a long job which have been translated, so that a job which was interrupted, such
+//! as by Ctrl+C or a crash, can be resumed without sending them again.
+//!
+//! The ledger of a job is a file named after a hash of the job, which begins with the job itself on one line,
+//! as the files of the response cache do, followed by a line for each chunk translated: the hash of the chunk
+//! and its translation as a JSON string. Each line is written as soon as its chunk is translated, and a line
+//! left half written by a crash is skipped when the ledger is read again.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use cache::fnv1a;
+use json::Json;
+
+/// The chunks of a job which have been translated, with their translations.
+pub struct JobLedger {
+    path: PathBuf,
+    completed: Mutex<HashMap<u64, String>>,
+    file: Mutex<File>,
+}
+
+impl JobLedger {
+    /// Open the ledger of `job` in `directory`, a description of the job which tells it apart from any other,
+    /// such as the file being translated and the language it is translated into. If `resume`, the chunks
+    /// which it already records are taken to be translated; otherwise it is begun afresh.
+    pub fn open(directory: &Path, job: &str, resume: bool) -> io::Result<JobLedger> {
+        fs::create_dir_all(directory)?;
+        let path = directory.join(format!("{:016x}", fnv1a(job.as_bytes())));
+        let header = Json::from(job).to_string();
+        let mut contents = String::new();
+        if resume {
+            match File::open(&path) {
+                Ok(mut file) => { file.read_to_string(&mut contents)?; },
+                Err(ref why) if why.kind() == io::ErrorKind::NotFound => (),
+                Err(why) => return Err(why)
+            }
+        }
+        let mut lines = contents.lines();
+        let mut completed = HashMap::new();
+        // A ledger of another job which shares the hash is begun afresh, as if there were none.
+        if lines.next() == Some(header.as_str()) {
+            for line in lines {
+                let entry = line.split_once('\t').and_then(|(hash, translation)| {
+                    let hash = u64::from_str_radix(hash, 16).ok()?;
+                    Json::parse(translation).ok()?.as_str().map(|translation| (hash, translation.to_string()))
+                });
+                if let Some((hash, translation)) = entry {
+                    completed.insert(hash, translation);
+                }
+            }
+        }
+        // The entries are written again, without any half written line, so that new ones follow on cleanly.
+        let mut file = File::create(&path)?;
+        let mut written = format!("{}\n", header);
+        for (hash, translation) in &completed {
+            written.push_str(format!("{:016x}\t{}\n", hash, Json::from(translation.as_str())).as_str());
+        }
+        file.write_all(written.as_bytes())?;
+        Ok(JobLedger { path, completed: Mutex::new(completed), file: Mutex::new(file) })
+    }
+
+    /// The translation of `chunk`, if the job has translated it already.
+    pub fn completed(&self, chunk: &str) -> Option<String> {
+        self.completed.lock().unwrap().get(&fnv1a(chunk.as_bytes())).cloned()
+    }
+
+    /// Record that `chunk` has been translated as `translation`.
+    pub fn record(&self, chunk: &str, translation: &str) -> io::Result<()> {
+        let hash = fnv1a(chunk.as_bytes());
+        let mut file = self.file.lock().unwrap();
+        file.write_all(format!("{:016x}\t{}\n", hash, Json::from(translation)).as_bytes())?;
+        file.flush()?;
+        self.completed.lock().unwrap().insert(hash, translation.to_string());
+        Ok(())
+    }
+
+    /// How many chunks the job has translated.
+    pub fn len(&self) -> usize {
+        self.completed.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove the ledger once the job is done, so that it is not resumed again.
+    pub fn finish(self) -> io::Result<()> {
+        fs::remove_file(&self.path)
+    }
+}
+
+#[test]
+fn test_job_ledger() {
+    use std::env;
+    use std::fs::OpenOptions;
+
+    let directory = env::temp_dir().join(format!("rust-google-translate-ledger-test-{}", ::std::process::id()));
+    let ledger = JobLedger::open(&directory, "manual.txt\nEO\nEN", true).unwrap();
+    assert!(ledger.is_empty());
+    ledger.record("Saluton", "Hello").unwrap();
+    ledger.record("Ĝis\trevido", "Goodbye,\n\"friend\"").unwrap();
+    drop(ledger);
+    // A line left half written by a crash is skipped.
+    let path = fs::read_dir(&directory).unwrap().next().unwrap().unwrap().path();
+    OpenOptions::new().append(true).open(&path).unwrap().write_all(b"00000000000000ab\t\"Half").unwrap();
+
+    let resumed = JobLedger::open(&directory, "manual.txt\nEO\nEN", true).unwrap();
+    assert_eq!(resumed.len(), 2);
+    assert_eq!(resumed.completed("Saluton").as_deref(), Some("Hello"));
+    assert_eq!(resumed.completed("Ĝis\trevido").as_deref(), Some("Goodbye,\n\"friend\""));
+    assert_eq!(resumed.completed("Dankon"), None);
+    resumed.record("Dankon", "Thank you").unwrap();
+    drop(resumed);
+    assert_eq!(JobLedger::open(&directory, "manual.txt\nEO\nEN", true).unwrap().len(), 3);
+    assert!(JobLedger::open(&directory, "manual.txt\nEO\nDE", true).unwrap().is_empty());
+    let fresh = JobLedger::open(&directory, "manual.txt\nEO\nEN", false).unwrap();
+    assert!(fresh.is_empty());
+    fresh.finish().unwrap();
+    let _ = fs::remove_dir_all(directory);
+}
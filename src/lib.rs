@@ -0,0 +1,89 @@
+//! Translate text into another language with Google Translate.
+//!
+//! ```no_run
+//! use rust_google_translate::Translator;
+//!
+//! let translator = Translator::new();
+//! let result = translator.translate("Mi estas ne vin. Vi estas ne min.", None, "EN").unwrap();
+//! assert_eq!(result.text, "I am not you. You are not me.");
+//!
+//! // Translations may also be carried out in the background, to avoid blocking a user interface.
+//! let pending = translator.translate_async("Vi estas ne min.", Some("EO"), "EN");
+//! while pending.poll().is_none() { /* do other work */ }
+//! ```
+
+extern crate hyper;
+#[cfg(feature = "tls-native")]
+extern crate hyper_native_tls;
+#[cfg(feature = "tls-rustls")]
+extern crate hyper_rustls;
+#[cfg(feature = "tls-native")]
+extern crate native_tls;
+
+mod audit;
+mod cache;
+mod circuit;
+mod currency;
+mod emoji;
+mod entity;
+mod error;
+mod glossary;
+mod history;
+mod html;
+mod json;
+mod language;
+mod ledger;
+mod localize;
+mod markdown;
+mod memory;
+mod parse;
+mod phrasebook;
+mod placeholder;
+mod plural;
+mod po;
+mod proxy;
+mod query;
+mod redact;
+mod retry;
+mod segment;
+mod subtitle;
+mod summarize;
+mod throttle;
+mod tls;
+mod translator;
+mod typography;
+mod usage;
+
+pub use audit::AuditLog;
+pub use cache::{ResponseCache, DEFAULT_CACHE_TTL};
+pub use circuit::{CircuitBreaker, CircuitState, DEFAULT_CIRCUIT_COOLDOWN, DEFAULT_CIRCUIT_THRESHOLD};
+pub use currency::{annotate_currency, Rates};
+pub use emoji::{is_emoji, strip_emoji};
+pub use entity::{find_entities, protect, restore};
+pub use error::Error;
+pub use glossary::{Glossary, GlossaryTerm};
+pub use history::{snippet, HistoryEntry, DEFAULT_SNIPPET_LENGTH};
+pub use html::translate_html;
+pub use json::Json;
+pub use language::{is_supported, language_name, match_language, resolve_language, serbian_to_latin, split_script, Script, LANGUAGES};
+pub use ledger::JobLedger;
+pub use localize::{convert_units, localize, DateOrder, Locale};
+pub use markdown::translate_markdown;
+pub use memory::{parse_tmx, MemoryMatch, TranslationMemory, TranslationUnit, DEFAULT_MATCH_THRESHOLD};
+pub use parse::{is_blocked, parse_detection, parse_dictionary, parse_response, Alternative, Detection, DictionaryEntry, DictionaryTranslation, Response, Sentence};
+pub use phrasebook::{Phrase, Phrasebook};
+pub use placeholder::find_placeholders;
+pub use plural::PluralForms;
+pub use po::translate_po;
+pub use proxy::Proxy;
+pub use query::percent_encode;
+pub use redact::{find_sensitive, Sensitive};
+pub use retry::RetryPolicy;
+pub use segment::{split_document, split_sentences, split_text, Segment, MAX_SEGMENT_LENGTH};
+pub use subtitle::Subtitles;
+pub use summarize::summarize;
+pub use throttle::{Throttle, POLITE_INTERVAL, POLITE_JITTER};
+pub use tls::{TlsBackend, TlsConfig};
+pub use translator::{LanguageRun, Origin, PendingTranslation, RetryObserver, TranslationResult, Translator, TranslatorBuilder, DEFAULT_ENDPOINT, DEFAULT_TIMEOUT, DEFAULT_USER_AGENT, HINT_CONFIDENCE, MAX_CONCURRENT_CHUNKS};
+pub use typography::{capitalize, normalize, punctuate, typographic_quotes, Normalization};
+pub use usage::Usage;
@@ -0,0 +1,234 @@
+//! Conversion of numbers, dates and units to the conventions of the language that text is translated into.
+//!
+//! Google translates the words around a number but generally leaves the number itself as it was written,
+//! so that "1,234.5" stays as it is in a German translation rather than becoming "1.234,5".
+
+/// The order in which the parts of a date are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// How a language writes numbers, dates and measurements.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Locale {
+    /// The character between the whole and fractional parts of a number.
+    pub decimal: char,
+    /// The character between groups of three digits in a large number.
+    pub grouping: char,
+    pub date_order: DateOrder,
+    /// The character between the parts of a date.
+    pub date_separator: char,
+    /// Whether measurements are given in metric rather than imperial units.
+    pub metric: bool,
+}
+
+impl Locale {
+    /// The conventions of a language given by its code, such as `DE` or `EN-GB`. `EN` is taken to be
+    /// American English, and unknown languages are given the most common conventions.
+    pub fn for_language(language: &str) -> Locale {
+        use self::DateOrder::*;
+        let language = language.to_uppercase();
+        let (decimal, grouping, date_order, date_separator, metric) = match language.as_str() {
+            "EN-GB" | "EN-AU" | "EN-IE" | "EN-NZ" => ('.', ',', DayMonthYear, '/', true),
+            "EN-CA"                               => ('.', ',', YearMonthDay, '-', true),
+            _ if language.starts_with("EN")       => ('.', ',', MonthDayYear, '/', false),
+            "DE" | "EO" | "RU" | "SR" | "SR-CYRL" | "SR-LATN" | "TR" | "PL" | "CS" | "UK" | "FI" | "NO" | "DA"
+                                                  => (',', '.', DayMonthYear, '.', true),
+            "FR" | "ES" | "IT" | "PT" | "EL" | "VI" => (',', '.', DayMonthYear, '/', true),
+            "NL"                                  => (',', '.', DayMonthYear, '-', true),
+            "SV" | "LT" | "HU"                    => (',', ' ', YearMonthDay, '-', true),
+            _ if language.starts_with("ZH") || language == "JA" || language == "KO"
+                                                  => ('.', ',', YearMonthDay, '/', true),
+            _                                     => ('.', ',', DayMonthYear, '/', true)
+        };
+        Locale { decimal, grouping, date_order, date_separator, metric }
+    }
+}
+
+/// Rewrite the numbers and dates in `text`, which are written according to `from`, according to `to`.
+/// Anything which cannot be read unambiguously according to `from`, such as a version number like
+/// `1.2.3`, is left as it is.
+pub fn localize(text: &str, from: &Locale, to: &Locale) -> String {
+    let mut localized = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|character: char| character.is_ascii_digit()) {
+        // A number which continues a word, such as the 3 in 'MP3', is left alone.
+        let word = rest[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        localized.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest.find(|character: char| !(character.is_ascii_digit() || ".,/-' ".contains(character))).unwrap_or(rest.len());
+        let candidate = &rest[..end];
+        let (length, replacement) = if word {
+            let length = digits(candidate);
+            (length, candidate[..length].to_string())
+        } else {
+            date(candidate, from, to).unwrap_or_else(|| number(candidate, from, to))
+        };
+        localized.push_str(replacement.as_str());
+        rest = &rest[length..];
+    }
+    localized.push_str(rest);
+    localized
+}
+
+/// Convert the measurements in `text` which are written in English or with symbols, such as `5 miles` or
+/// `70 °F`, into metric units if `metric` is true or into imperial units otherwise. The converted amounts
+/// are written according to `locale`.
+pub fn convert_units(text: &str, metric: bool, locale: &Locale) -> String {
+    // Each unit is listed with its names, most specific first so that 'feet' is not read as 'ft', the unit
+    // that it converts into and the factor to multiply by, and whether it is itself metric.
+    const UNITS: &'static [(&'static [&'static str], &'static str, f64, bool)] = &[
+        (&["miles", "mile", "mi"], "km", 1.609344, false),
+        (&["feet", "foot", "ft"], "m", 0.3048, false),
+        (&["inches", "inch"], "cm", 2.54, false),
+        (&["pounds", "pound", "lbs", "lb"], "kg", 0.45359237, false),
+        (&["ounces", "ounce", "oz"], "g", 28.349523, false),
+        (&["gallons", "gallon", "gal"], "l", 3.785411, false),
+        (&["kilometres", "kilometers", "kilometre", "kilometer", "km"], "mi", 1.0 / 1.609344, true),
+        (&["kilograms", "kilogram", "kg"], "lb", 1.0 / 0.45359237, true),
+        (&["centimetres", "centimeters", "centimetre", "centimeter", "cm"], "in", 1.0 / 2.54, true),
+        (&["litres", "liters", "litre", "liter"], "gal", 1.0 / 3.785411, true),
+    ];
+
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|character: char| character.is_ascii_digit()) {
+        converted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|character: char| !(character.is_ascii_digit() || character == '.')).unwrap_or(rest.len());
+        let (amount, after) = rest.split_at(end);
+        let value = amount.parse::<f64>().ok();
+        let spaced = after.trim_start_matches(' ');
+
+        // Temperatures are not simply scaled, so are handled on their own.
+        let temperature = ["°F", "° F", "°C", "° C"].iter().find(|symbol| spaced.starts_with(*symbol))
+            .filter(|symbol| symbol.ends_with('C') != metric);
+        let unit = UNITS.iter().filter(|unit| unit.3 != metric).flat_map(|unit| unit.0.iter().map(move |name| (*name, unit)))
+            .find(|&(name, _)| spaced.starts_with(name)
+                && !spaced[name.len()..].chars().next().is_some_and(char::is_alphanumeric));
+
+        match (value, temperature, unit) {
+            (Some(value), Some(symbol), _) if !metric => {
+                converted.push_str(format!("{} °F", format_amount(value * 9.0 / 5.0 + 32.0, locale)).as_str());
+                rest = &spaced[symbol.len()..];
+            },
+            (Some(value), Some(symbol), _) => {
+                converted.push_str(format!("{} °C", format_amount((value - 32.0) * 5.0 / 9.0, locale)).as_str());
+                rest = &spaced[symbol.len()..];
+            },
+            (Some(value), None, Some((name, unit))) => {
+                converted.push_str(format!("{} {}", format_amount(value * unit.2, locale), unit.1).as_str());
+                rest = &spaced[name.len()..];
+            },
+            _ => {
+                converted.push_str(amount);
+                rest = after;
+            }
+        }
+    }
+    converted.push_str(rest);
+    converted
+}
+
+/// Write an amount to one decimal place, leaving out the fraction if it is zero.
+fn format_amount(value: f64, locale: &Locale) -> String {
+    let rounded = (value * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{:.1}", rounded).replace('.', locale.decimal.to_string().as_str())
+    }
+}
+
+/// The number of bytes taken up by the digits at the start of `input`.
+fn digits(input: &str) -> usize {
+    input.find(|character: char| !character.is_ascii_digit()).unwrap_or(input.len())
+}
+
+/// Read a date at the start of `input`, of the form `01/02/2024`, `01.02.2024` or `01-02-2024`, returning
+/// its length and how it is written according to `to`. ISO dates of the form `2024-02-01` are left as they are.
+fn date(input: &str, from: &Locale, to: &Locale) -> Option<(usize, String)> {
+    let first = digits(input);
+    let separator = input[first..].chars().next().filter(|&separator| "/.-".contains(separator))?;
+    let second = first + 1 + digits(&input[first + 1..]);
+    if !input[second..].starts_with(separator) { return None; }
+    let third = second + 1 + digits(&input[second + 1..]);
+    if third - second != 5 || !(1..=2).contains(&first) || !(1..=2).contains(&(second - first - 1)) { return None; }
+    // A date must not run on into something else, such as a fourth component.
+    let after = &input[third..];
+    if after.starts_with(|c: char| "/.-".contains(c)) && after[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let a = input[..first].parse::<u32>().ok()?;
+    let b = input[first + 1..second].parse::<u32>().ok()?;
+    let year = &input[second + 1..third];
+    let (day, month) = match from.date_order {
+        DateOrder::DayMonthYear => (a, b),
+        DateOrder::MonthDayYear => (b, a),
+        DateOrder::YearMonthDay => return None
+    };
+    if !(1..=31).contains(&day) || !(1..=12).contains(&month) { return None; }
+    let s = to.date_separator;
+    let written = match to.date_order {
+        DateOrder::DayMonthYear => format!("{:02}{}{:02}{}{}", day, s, month, s, year),
+        DateOrder::MonthDayYear => format!("{:02}{}{:02}{}{}", month, s, day, s, year),
+        DateOrder::YearMonthDay => format!("{}{}{:02}{}{:02}", year, s, month, s, day)
+    };
+    Some((third, written))
+}
+
+/// Read a number at the start of `input`, returning its length and how it is written according to `to`.
+/// Something which does not read as a number according to `from` is returned as it was.
+fn number(input: &str, from: &Locale, to: &Locale) -> (usize, String) {
+    let whole = digits(input);
+    let mut end = whole;
+    let mut groups = vec![&input[..whole]];
+    // Further groups of exactly three digits, each following the grouping character.
+    while input[end..].starts_with(from.grouping) && digits(&input[end + 1..]) == 3 {
+        groups.push(&input[end + 1..end + 4]);
+        end += 4;
+    }
+    let mut fraction = None;
+    if input[end..].starts_with(from.decimal) && digits(&input[end + 1..]) > 0 {
+        let length = digits(&input[end + 1..]);
+        fraction = Some(&input[end + 1..end + 1 + length]);
+        end += 1 + length;
+    }
+
+    // Anything more, such as the second '.' of a version number, makes the whole thing ambiguous.
+    let ambiguous = input[end..].starts_with([from.decimal, from.grouping])
+        && input[end + 1..].starts_with(|c: char| c.is_ascii_digit());
+    if ambiguous || (groups.len() > 1 && groups[0].len() > 3) {
+        let length = input.find(|c: char| !(c.is_ascii_digit() || c == from.decimal || c == from.grouping)).unwrap_or(input.len());
+        return (length, input[..length].to_string());
+    }
+
+    let mut written = groups.join(to.grouping.to_string().as_str());
+    if let Some(fraction) = fraction {
+        written.push(to.decimal);
+        written.push_str(fraction);
+    }
+    (end, written)
+}
+
+#[test]
+fn test_localize() {
+    let english = Locale::for_language("EN");
+    let german = Locale::for_language("DE");
+    let japanese = Locale::for_language("JA");
+    assert_eq!(localize("Es kostet 1,234.50 € am 12/31/2024.", &english, &german), "Es kostet 1.234,50 € am 31.12.2024.");
+    assert_eq!(localize("Version 1.2.3, MP3, 2024-01-05, 3.5 and 10,000 people.", &english, &german),
+        "Version 1.2.3, MP3, 2024-01-05, 3,5 and 10.000 people.");
+    assert_eq!(localize("Am 31.12.2024 um 3,5 Uhr.", &german, &english), "Am 12/31/2024 um 3.5 Uhr.");
+    assert_eq!(localize("12/31/2024", &english, &japanese), "2024/12/31");
+    assert_eq!(localize("13/31/2024", &english, &german), "13/31/2024");
+
+    assert_eq!(convert_units("It is 5 miles away, 70 °F and 10 lbs.", true, &german), "It is 8 km away, 21,1 °C and 4,5 kg.");
+    assert_eq!(convert_units("A 2 km walk at 20°C, in 3 minutes.", false, &english), "A 1.2 mi walk at 68 °F, in 3 minutes.");
+    assert_eq!(convert_units("5 mils and 3 footnotes", true, &german), "5 mils and 3 footnotes");
+}
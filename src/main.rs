@@ -1,43 +1,520 @@
-extern crate hyper;
+extern crate encoding_rs;
 extern crate gtk;
 extern crate gdk;
+extern crate rust_google_translate;
+
+mod cli;
+mod gui;
 
-use std::io::Read;
-use std::rc::Rc;
 use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
-use hyper::Client;
-use hyper::header::Connection;
-
-use gdk::enums::key;
-use gtk::traits::*;
-use gtk::{
-    Builder,
-    Button,
-    ButtonSignals,
-    ComboBoxText,
-    Inhibit,
-    TextView,
-    TextBuffer,
-    TextTagTable,
-    WidgetSignals,
-    Window
+use rust_google_translate::{
+    annotate_currency,
+    find_entities,
+    find_placeholders,
+    language_name,
+    localize,
+    normalize,
+    protect,
+    resolve_language,
+    restore,
+    strip_emoji,
+    summarize,
+    Glossary,
+    Json,
+    Locale,
+    Normalization,
+    Proxy,
+    Rates,
+    RetryPolicy,
+    Sensitive,
+    Throttle,
+    TlsBackend,
+    TlsConfig,
+    DEFAULT_CACHE_TTL,
+    DEFAULT_MATCH_THRESHOLD,
+    DEFAULT_TIMEOUT,
+    LANGUAGES,
+    MAX_SEGMENT_LENGTH
 };
 
-const TRANSLATE: &'static str = "http://translate.googleapis.com/translate_a/single?client=gtx&sl=auto&tl=";
+use cli::batch::{
+    report_failures,
+    translate_file,
+    translate_lines,
+    translate_localized,
+    translate_markup,
+    Job,
+    Markup,
+    Processing,
+    Units
+};
+use cli::encoding::OutputEncoding;
+use cli::exec::execute_translated;
+use cli::progress::Progress;
+use cli::review::Reviewer;
+use cli::format::{
+    format_translation,
+    print_details,
+    print_error_json,
+    render_man,
+    report_error,
+    show,
+    terminal_width,
+    Errors,
+    Format,
+    Overflow
+};
+use cli::settings::{
+    build_translator,
+    environment_endpoints,
+    environment_proxy,
+    environment_redact,
+    open_ledger,
+    open_memory,
+    read_cached_rates,
+    read_names,
+    read_rates,
+    split_list,
+    TranslatorOptions,
+    AUDIT_LOG_VARIABLE
+};
+use gui::compare::launch_compare;
+use gui::kiosk::launch_kiosk;
+use gui::window::launch_gui;
+
+/// How long --polite keeps Google's responses in the cache.
+const POLITE_CACHE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
 const TRY: &'static str = "Try 'rust-google-translate --help' for more information";
 const HELP: &'static str = r#"NAME
     rust-google-translate - translate a phrase into another language with Google Translate
 
 SYNOPSIS
-    rust-google-translate [-c LANG PHRASE] [-h | --help]
+    rust-google-translate [--max-length N [--overflow POLICY]] [--wrap N | --no-wrap]
+        [--timeout SECONDS] [--polite] [--retries N [--backoff MILLISECONDS] [--no-jitter]] [--endpoint URL...]
+        [--user-agent UA...] [--no-cache | --cache-ttl SECONDS]
+        [--memory [--memory-threshold PERCENT%]] [--proxy URL] [--tls-backend BACKEND] [--ca-bundle FILE] [--insecure]
+        [--chunk-length N [--parallel-chunks]] [-d | --details] [-s LANG | --hint LANG[,LANG...]]
+        [--format FORMAT] [--errors FORMAT] [--pager] [--summarize PERCENT%] [--protect-names] [--names FILE]
+        [--protect-placeholders] [--glossary FILE] [--html | --markdown | --subtitles | --po] [--strip-emoji]
+        [--redact KINDS] [--audit-log FILE | --no-log] [--localize [--units UNITS]]
+        [--currency CODE [--rates FILE]] [--punctuate] [--capitalize] [--typographic-quotes]
+        [-c LANG[,LANG...] PHRASE | -t LANG... -c PHRASE
+            | [--jobs N] [--no-progress] (--lines LANG | -t LANG --filter)]
+        [-h | --help]
+    rust-google-translate file INPUT -t LANG [-s LANG] [-o OUTPUT [--output-encoding ENCODING] | [--format FORMAT] [--pager]]
+        [--errors FORMAT] [--no-progress] [--resume] [--interactive-review] [--summarize PERCENT%] [--redact KINDS]
+        [--glossary FILE] [--html | --markdown | --subtitles | --po]
+    rust-google-translate [--format FORMAT] [--errors FORMAT] [--strip-emoji] [--redact KINDS] [--hint LANG[,LANG...]]
+        detect [PHRASE]
+    rust-google-translate [--format FORMAT] languages
+    rust-google-translate [-s LANG] -t LANG [--redact KINDS] [--glossary FILE] exec [--] COMMAND [ARGUMENT...]
+    rust-google-translate memory (import FILE | export [FILE])
+    rust-google-translate --kiosk [-s LANG] -t LANG
+    rust-google-translate --compare [--live] [-s LANG] [-t LANG -t LANG]
 
 DESCRIPTION
-    Translates text from one language to another. If no arguments are given, a GTK GUI is launched.
+    Translates text from one language to another. If no arguments are given, a GTK GUI is launched,
+    which translates the text again whenever another language is chosen. The GUI lists the
+    translations made so far under History, each cut off after RUST_GOOGLE_TRANSLATE_HISTORY_SNIPPET
+    characters (60 by default); hover over one to read all of it, or double-click it to bring its
+    text back. Select part of the text and press Ctrl+T to translate just that part, shown beside it
+    without replacing anything. Hold Ctrl and rest the pointer on a word of the text to be translated to
+    see its dictionary entry for the chosen language; set RUST_GOOGLE_TRANSLATE_HOVER to 'always' to
+    look words up without holding Ctrl, or to 'off' to never look them up. Right-clicking a translation offers to copy it, read it aloud, look it up in
+    Wiktionary, translate it back, or save it to the phrasebook in
+    $XDG_DATA_HOME/rust-google-translate/phrasebook.tsv, acting on just the selected text if any is.
+
+COMMANDS
+    file INPUT -t LANG [-o OUTPUT]
+        translates the text file INPUT into LANG, writing the result to OUTPUT or to standard output.
+        Paragraphs are translated one at a time, and the blank lines between them are preserved. INPUT
+        may be UTF-8, or UTF-16 if it begins with a byte order mark; OUTPUT is written in the same
+        encoding, with the same byte order mark, unless --output-encoding says otherwise, and with the
+        same line endings, Windows (CRLF) or Unix (LF).
+        Paragraphs which cannot be translated are left untranslated and listed on standard error,
+        in which case the exit status is 2.
+
+    detect [PHRASE]
+        prints the code of the language that PHRASE, or standard input if no PHRASE is given, is
+        written in, followed by how confident Google is in it from 0 to 1, separated by a tab. Nothing
+        is translated. With --format json, prints an object with the members "language" and
+        "confidence" instead.
+
+    languages
+        lists the code and name of every language that Google supports. These are the codes accepted
+        wherever a LANG is expected, along with the script variants ZH-HANS, ZH-HANT, SR-CYRL and
+        SR-LATN. With --format json, prints an array of objects with the members "code" and "name".
+
+        A LANG may also be given by its name or its three-letter ISO 639-2 code, in any case, so that
+        en, EN, eng, English and english are all the same. Small misspellings are corrected, and when
+        a LANG cannot be settled on a single language the nearest languages are suggested instead.
+
+    exec [--] COMMAND [ARGUMENT...]
+        runs COMMAND with its ARGUMENTs, and translates what it writes to standard output and standard
+        error into the language given with -t a line at a time, for reading the messages of a tool which
+        speaks another language. Each line is written to the same stream as the command wrote it to, with
+        those written to standard error marked with '[stderr]'. Lines which cannot be translated are
+        written as they were. The exit status is that of COMMAND. Everything after exec is taken to be
+        the command, so every option must be given before it.
+
+    memory import FILE
+        adds the translations in the TMX file FILE, as written by most translation tools, to the
+        translation memory used by --memory, and prints how many were added.
+
+    memory export [FILE]
+        writes the translation memory used by --memory as a TMX file to FILE, or to standard output.
+
+    --kiosk [-s LANG] -t LANG
+        launches a fullscreen GUI for information desks and reception counters, which translates from
+        LANG (or any language, if -s is not given) into the language given with -t. Text is shown in
+        large type, side by side with its translation, and the languages cannot be changed. Enter
+        translates and Escape clears both sides. The window cannot be closed from within it; stop the
+        program to leave kiosk mode.
+
+    --compare [--live] [-s LANG] [-t LANG -t LANG]
+        launches a GUI which translates the same text into two languages at once, shown side by side,
+        for writing to readers of both. Each side has its own choice of language, which starts as the
+        languages given with -t, or English and Spanish. Enter translates and Escape quits. Choosing
+        another language translates the text again.
+
+        With --live, the text is translated as it is typed, once typing pauses. The pause waited for
+        grows with the length of the text, as every request sends all of it again, and a translation
+        which is still under way when the text changes is abandoned.
 
 OPTIONS
     -c LANG PHRASE
-        translates PHRASE into LANG
+        translates PHRASE into LANG. Script variants may be requested explicitly, such as
+        ZH-CN / ZH-TW for Simplified / Traditional Chinese, and SR-CYRL / SR-LATN for
+        Cyrillic / Latin Serbian.
+
+        LANG may be a comma-separated list, such as EN,DE,FR, in which case PHRASE is translated
+        into every language at once and each translation is printed under its language code. With
+        --format json, the translations are printed as one object keyed by language code. If some
+        of the translations fail, the others are still printed and the exit status is 2.
+
+    -t, --target LANG -c PHRASE
+        the same as -c LANG PHRASE. -t may be repeated, or given a comma-separated list, to
+        translate into several languages.
+
+    --lines LANG
+        reads standard input and translates every line into LANG on its own. The output has exactly
+        one line for each line of input, in the same order, so that it may be pasted alongside it.
+
+        Lines which cannot be translated are left blank. Once every line has been processed, they are
+        listed on standard error along with a command to retry them, and the exit status is 2.
+
+    -t, --target LANG --filter
+        the same as --lines LANG, for use as a filter in pipelines and editors.
+
+    --jobs N
+        the number of lines to translate in parallel in --lines or --filter mode. Defaults to 4.
+
+    --no-progress
+        does not draw the progress bar which file and --lines otherwise show on standard error when it is
+        a terminal. The bar of file shows how far through the file the translation has got and about how
+        long is left, and that of --lines counts the lines translated; both say when a request failed and
+        is about to be tried again, such as when Google is limiting requests. --filter never draws one.
+        Must be given before file or --lines.
+
+    --max-length N
+        limits the translation to N characters. Must be given before -c.
+
+    --overflow POLICY
+        decides what happens to translations longer than --max-length: 'truncate' cuts the
+        translation short with a trailing ellipsis (the default), 'error' fails instead of
+        printing anything, and 'chunk' prints the translation in pieces of at most N characters.
+
+    --protect-names
+        keeps names in PHRASE from being translated by -c. Runs of capitalised words which do not begin
+        a sentence, such as 'Ada Lovelace', and words with a capital inside them, such as 'iPhone', are
+        taken to be names. They are swapped for placeholders before translation and put back afterwards,
+        and any which did not survive translation are listed on standard error. Must be given before -c.
+
+    --names FILE
+        the same as --protect-names, but also protects every name listed in FILE, one per line.
+
+    --protect-placeholders
+        keeps the placeholders of format strings from being translated by -c, --lines, --filter or file, so
+        that user interface strings still format once translated. The placeholders of printf (%s, %1$d,
+        %-5.2f, %(name)s), .NET, Python and Rust ({0}, {name}, {0:.2f}), templates such as Mustache and
+        Jinja ({{name}}) and template literals (${var}) are recognised. Must be given before -c, --lines,
+        --filter or file.
+
+    --glossary FILE
+        keeps the terms listed in FILE, one per line, from being translated by -c, --lines, --filter, file,
+        exec, --kiosk or --compare, so that the same words are used throughout a batch of translations. A term may be followed
+        by a tab, a language code, another tab and the translation to use for it in that language, such as
+        'checkout<TAB>DE<TAB>Kasse'; a term may be listed once for each language it has a translation into,
+        and is kept as it is in any other. Blank lines and lines beginning with # are skipped. Must be
+        given before -c, --lines, --filter, file or exec.
+
+    --html
+        treats the text given to -c or file as HTML, translating only its text and keeping its tags,
+        attributes and character references as they were. The text of a paragraph, heading or other block
+        is translated as a whole, with the links and other inline elements within it moved along with the
+        words they surround. Scripts, styles, preformatted text, code and elements marked translate="no" or
+        with the class notranslate are left untranslated. --protect-names has no effect on HTML. Must be
+        given before -c or file.
+
+    --markdown
+        treats the text given to -c or file as Markdown, translating only its prose and keeping headings,
+        list markers, quotations, tables, emphasis, links and images as they were. The lines of a
+        paragraph are translated together and joined into one. Code blocks, inline code, URLs, link
+        destinations, front matter and HTML blocks are never sent to Google. --protect-names has no
+        effect on Markdown. Must be given before -c or file.
+
+    --subtitles
+        treats the text given to -c or file as subtitles in the SRT or WebVTT format, translating only the
+        text of each cue and keeping the cue numbers, timings, cue settings and notes as they were. Cues
+        are sent many at a time, in batches of up to --chunk-length characters, and each translation is
+        broken into as many lines as its cue had. Formatting tags such as <i> are kept, and the lines of a
+        dialogue, each beginning with a dash, are kept apart. ASS and SSA subtitles, which begin with
+        [Script Info], are translated in the same way: only the text of each Dialogue event is translated,
+        keeping its timing, style, margins and effect, the styles and the comments as they were. Override
+        blocks such as {\an8} are kept, and the karaoke timing of a line (\k, \kf, \ko) is shared out
+        among the words of its translation, taking as long in all as before. A file without any cues is an
+        error. Must be given before -c or file.
+
+    --po
+        treats the text given to -c or file as a gettext PO file, filling in each entry whose msgstr is
+        empty with the translation of its msgid. An entry with plural forms is given one msgstr for each
+        plural form of the target language, or of the Plural-Forms header of the file if it has one, each
+        translated with the number it counts set to one which takes that form. Comments, references, flags, contexts, the header, obsolete entries and entries which are
+        already translated are kept as they were. Each entry filled in is flagged fuzzy, so that it is
+        looked over before it is used, and an entry which cannot be translated is left empty. The
+        placeholders of format strings are always kept. Must be given before -c or file.
+
+    --strip-emoji
+        removes emoji, emoticons such as :-) and kaomoji such as (^_^) from the text given to -c or
+        detect before sending it, as they can lead Google to mistake the language of a short message.
+        Otherwise they are passed through translation exactly as they were written. Must be given
+        before -c or detect.
+
+    --redact KINDS
+        masks sensitive text in the input before it is sent to Google, and puts it back into the
+        translation afterwards, so that it never leaves this machine. KINDS is a comma-separated list of
+        'email' for email addresses, 'phone' for phone numbers of nine or more digits written with a
+        leading + or separators, and 'card' for payment card numbers, or 'all'. Applies to everything
+        sent to Google, including from --kiosk and --compare, and must be given before -c, --lines,
+        --filter, file, detect and exec. KINDS may also be set with the environment variable
+        RUST_GOOGLE_TRANSLATE_REDACT, which applies to the GUI as well.
+
+    --audit-log FILE
+        appends a line of JSON to FILE for every request before it is sent to Google, recording when it
+        was sent, where to, and exactly what text it carried, so that what left this machine can be
+        reviewed later. A request which cannot be recorded is not sent. The log may also be turned on
+        with the environment variable RUST_GOOGLE_TRANSLATE_AUDIT_LOG, which applies to the GUI as well.
+
+    --no-log
+        records nothing in the audit log, even if one is given by --audit-log or the environment.
+
+    --localize
+        rewrites numbers and dates in translations made by -c in the way that the target language
+        writes them, so that 1,234.5 and 12/31/2024 become 1.234,5 and 31.12.2024 in German. Numbers
+        are read in the way that the source language writes them. Measurements are also converted
+        into the units that the target language uses: metric for all but American English. Must be
+        given before -c.
+
+    --units UNITS
+        which units --localize converts measurements into: 'metric', 'imperial', or 'keep' to leave
+        them as they are. Defaults to the units of the target language.
+
+    --punctuate
+        ends each translation made by -c, --lines, --filter or file with a full stop (。 in Chinese and
+        Japanese) if it does not already end with punctuation. In a file, each paragraph is a translation.
+
+    --capitalize
+        begins each translation with a capital letter.
+
+    --typographic-quotes
+        replaces straight and English double quotation marks in each translation with those of the target
+        language, such as „…“ in German, « … » in French and 「…」 in Japanese, with the secondary marks for
+        a quotation within a quotation.
+
+    --currency CODE
+        follows each amount of money in translations made by -c with its value in the currency CODE,
+        such as EUR, converted using the exchange rates last given with --rates. Amounts are recognised
+        by a currency symbol such as $ or €, or by a currency code in the table of rates, on either
+        side of them. Must be given before -c.
+
+    --rates FILE
+        the exchange rates for --currency, one currency on each line as a code and its value against a
+        common base currency, such as 'EUR 0.92'. The table is kept in
+        $XDG_CACHE_HOME/rust-google-translate/rates (or ~/.cache/rust-google-translate/rates) and
+        used until --rates is given again.
+
+    --summarize PERCENT%
+        before translating with -c or file, trims the input down to about PERCENT% of its length by
+        keeping only its most representative sentences, for when only the gist of a long text is
+        needed. Line numbers reported by file then refer to the trimmed text. Must be given before -c.
+
+    -s, --source LANG
+        translates from LANG, rather than letting Google detect the language of the input. This
+        helps with short phrases, which are easily mistaken for another language.
+
+    --hint LANG[,LANG...]
+        the languages which the input is likely to be written in, most likely first, such as 'es,pt'. When
+        the language of the input is detected, by translation or by detect, a language other than these
+        which Google is less than 90% sure of is taken to be the first of them instead, so that short
+        phrases are not mistaken for a related language. Has no effect with -s.
+
+    --format FORMAT
+        how -c prints the translation: 'text' (the default), 'json' or 'man'. The JSON output is a single
+        object with the members "text", "source_language", "confidence", "sentences" (each with its
+        "translation" and "source") and "alternatives" (each with its "source" and "candidates").
+        Members which Google did not report are null. --max-length and --wrap do not apply to JSON
+        output. 'man' lays the translation out like a manual page, for reading long documents: short
+        lines which stand alone are taken to be headings and set in bold, and the paragraphs beneath
+        them are indented and wrapped to --wrap columns (or 80). --max-length does not apply to it, and
+        file may use it when no OUTPUT is given. Must be given before -c, file, detect or languages.
+
+    --resume
+        carries on with a file whose translation was interrupted, such as by Ctrl+C or a crash, or which
+        had segments that could not be translated, translating only the segments which the last run did not.
+        Every run of file keeps a ledger of the segments it has translated under
+        $XDG_CACHE_HOME/rust-google-translate/jobs (or ~/.cache), which is removed once the whole file has
+        been translated and written; the same file translated into the same language in the same way is
+        the same job. Without --resume, the file is translated from the start. Must be given before file.
+
+    --interactive-review
+        shows each segment of the file beside its translation before it is written, and asks whether to
+        accept the translation (a, or Enter), edit it (e) in $VISUAL or $EDITOR, or on a line of its own
+        if neither is set, skip it (s) and keep the original, or accept it and every translation after it
+        (r). The answers are read from standard input, which must be a terminal. Ending the input with
+        Ctrl+D stops the review without writing anything; the file can then be reviewed again without
+        sending it to Google again with --resume. Cannot be used with --html, --markdown, --subtitles or
+        --po. Must be given before file.
+
+    --errors FORMAT
+        how translations which fail are reported on standard error: 'text' (the default) or 'json'. With
+        'json', each failure is written as a JSON object on a line of its own, with the members "kind"
+        (such as "rate_limited", "status" or "parse"), "message", "item" (the index of the language of -c,
+        counting from 0, or of the line of --lines, --filter or file which failed, or null) and
+        "retryable" (whether trying again later is likely to succeed), while translations are still
+        written to standard output. Must be given before -c, --lines, --filter, file or detect.
+
+    --pager
+        pipes the translation printed by -c, or by file when no OUTPUT is given, into $PAGER (or less)
+        when printing to a terminal, rather than flooding it with a long document. Unless LESS is set,
+        less is run with -FRX, so that it keeps the bold of --format man and quits at once if the
+        translation fits on one screen. Must be given before -c or file.
+
+    --output-encoding ENCODING
+        writes the OUTPUT of file in ENCODING rather than UTF-8, for programs which expect subtitles
+        or resource files in a legacy encoding. ENCODING is any encoding a web browser knows, such as
+        UTF-16LE, UTF-16BE, Shift_JIS, EUC-KR, GBK, Big5, windows-1251, windows-1252 or KOI8-R, in any
+        case. UTF-8 and UTF-16 may be followed by +BOM to begin OUTPUT with a byte order mark, and
+        UTF-16 alone is UTF-16LE+BOM. Characters which ENCODING cannot write are written as '?' and
+        listed on standard error. Requires -o.
+
+    --proxy URL
+        sends requests through the proxy at URL, such as http://proxy.example.com:3128 or
+        socks5://localhost:1080. An HTTP proxy is asked to tunnel the encrypted connection to Google.
+        Defaults to the first of HTTPS_PROXY, ALL_PROXY and HTTP_PROXY to be set, unless the host of
+        the first endpoint is listed in NO_PROXY.
+
+    --no-cache
+        sends every request to Google, rather than reusing a translation of the same text into the
+        same language made recently. Translations are kept in $XDG_CACHE_HOME/rust-google-translate
+        (or ~/.cache/rust-google-translate) for reuse.
+
+    --cache-ttl SECONDS
+        reuses a cached translation for up to SECONDS after it was made. Defaults to 604800 (a week);
+        0 turns the cache off.
+
+    --memory
+        answers from the translation memory in $XDG_DATA_HOME/rust-google-translate/memory.jsonl (or
+        ~/.local/share/rust-google-translate/memory.jsonl) when it holds a translation of nearly the same
+        text into the same language, rather than asking Google, and adds every translation that Google
+        makes to it. Long input is looked up a chunk at a time, as split by --chunk-length. Must be given
+        before -c, --lines, --filter or file.
+
+    --memory-threshold PERCENT%
+        how similar text must be to text in the translation memory for its translation to be used.
+        Defaults to 95%, which allows for little more than a difference in punctuation or a typing
+        mistake; 100% only uses translations of exactly the same text. Text which differs in a number or
+        a placeholder is never close enough, as its translation would carry the wrong one. How close a
+        match was is shown by -d and in the status bar of the GUI, and file lists the segments which were
+        translated from a match that was not exact, so that they can be looked over.
+
+    --user-agent UA
+        sends UA as the User-Agent of every request, rather than that of a common browser. Given more
+        than once, each request uses the next in turn.
+
+    --endpoint URL[,URL...]
+        sends requests to the server at URL rather than https://translate.googleapis.com, such as a
+        regional mirror or a proxy which forwards to Google. When several are given, by repeating
+        --endpoint or separating them with commas, a server which times out, fails or limits requests
+        is passed over for the next. Defaults to the endpoints listed in RUST_GOOGLE_TRANSLATE_ENDPOINT.
+
+        A server which fails five requests in a row is rested for a minute, and the others are used in
+        its place. Once every server is resting, translations fail at once, without sending a request,
+        until the first of them has rested. The status bar of the window notes any servers resting.
+
+    --tls-backend BACKEND
+        which implementation of TLS secures the connection to Google: 'native' for the platform's own
+        library (the default), or 'rustls'. Each is only available if this program was built with it.
+
+    --ca-bundle FILE
+        also trusts the PEM certificates in FILE, such as the root certificate of a corporate proxy
+        which inspects HTTPS traffic.
+
+    --insecure
+        does not check the certificate presented by the server. Anyone able to intercept the connection
+        can then read the text being translated, so this is only for diagnosing certificate problems.
+        Not supported by the rustls backend.
+
+    --chunk-length N
+        splits input longer than N characters into several requests, preferably at the ends of
+        sentences, and joins the translations back together with the same spacing. Defaults to 4000,
+        as Google rejects or truncates much longer requests. Must be given before -c, --lines or file.
+
+    --parallel-chunks
+        sends the requests for input split by --chunk-length four at a time, rather than one at a time.
+
+    -d, --details
+        also prints the detected source language, how confident Google is in it, how similar PHRASE was
+        to the text in the translation memory whose translation was used, and alternative translations
+        for each part of PHRASE to standard error.
+
+    --timeout SECONDS
+        gives up on a request which takes longer than SECONDS to send or to respond. Defaults to 10;
+        0 waits indefinitely.
+
+    --retries N
+        tries a request up to N more times when it times out, loses its connection or Google responds
+        with a server error, waiting longer between each try. Defaults to 2; 0 never retries. When
+        Google limits the rate of requests, the wait it asks for is respected, up to 30 seconds;
+        beyond that the request fails with an explanation rather than waiting.
+
+    --backoff MILLISECONDS
+        waits MILLISECONDS before the first retry, doubling the wait for each later one up to 8
+        seconds. Defaults to 500.
+
+    --no-jitter
+        waits exactly as long as --backoff says between retries, rather than a random amount of up
+        to half less.
+
+    --polite
+        paces requests for long batch runs, such as file, --lines or --subtitles, so that they do not
+        trip Google's abuse detection on the free endpoint. Requests are sent one at a time, two to
+        three seconds apart at random, retries back off from five seconds up to two minutes over five
+        attempts, a rate limit of up to ten minutes is waited out, and responses are cached for 30
+        days. Translations found in the cache are not held back. Options given after --polite, such
+        as --jobs or --retries, override it.
+
+    --wrap N
+        re-flows the translation so that no line is wider than N columns. When printing to a
+        terminal, output is wrapped to $COLUMNS (or 80) by default. Must be given before -c.
+
+    --no-wrap
+        prints each paragraph of the translation on a single line.
 
     -h, --help
         displays this information
@@ -45,173 +522,855 @@ OPTIONS
 EXAMPLE
     rust-google-translate -c EN Mi estas ne vin. Vi estas ne min.
         > I am not you. You are not me.
-"#;
 
-fn main() {
-    let mut arguments = std::env::args().skip(1);
-    if let Some(flag) = arguments.next() {
-        match flag.as_str() {
-            "-c" => {
-                if let Some(lang) = arguments.next() {
-                    let input = arguments.fold(String::with_capacity(lang.len()), |acc, x| acc + x.as_str() + " ");
-                    let mut translation = String::new();
-                    translate(input.as_str(), lang.as_str(), &mut translation);
-                    println!("{}", translation);
-                }
-            },
-            "-h" | "--help" => println!("{}", HELP),
-            _ => println!("rust-google-translate: invalid option -- '{}'\n{}", flag, TRY)
-        }
-    } else {
-        launch_gui();
-    }
-}
+    rust-google-translate -s EO -c EN Saluton
+        > Hello
 
-fn match_language(input: &str) -> String {
-    match input {
-        "Chinese"   => "ZH-CN".to_string(),
-        "English"   => "EN".to_string(),
-        "Esperanto" => "EO".to_string(),
-        "French"    => "FR".to_string(),
-        "German"    => "DE".to_string(),
-        "Italian"   => "IT".to_string(),
-        "Japanese"  => "JA".to_string(),
-        "Korean"    => "KO".to_string(),
-        "Russian"   => "RU".to_string(),
-        "Spanish"   => "ES".to_string(),
-        _ => {
-            println!("Language Not Supported");
-            std::process::exit(1);
-        }
-    }
-}
+    rust-google-translate detect Mi estas ne vin.
+        > EO      0.98828125
 
-/// Launch the GTK GUI
-fn launch_gui() {
-    // Initialize GTK
-    if let Err(message) = gtk::init() {
-        panic!("{:?}", message);
-    }
+    rust-google-translate --protect-names -c EO Yesterday Ada Lovelace met Charles Babbage in London.
+        > Hieraŭ Ada Lovelace renkontis Charles Babbage en London.
+
+    rust-google-translate --localize -s EN -c DE It is 5 miles away and costs $1,234.50.
+        > Es ist 8 km entfernt und kostet 1.234,50 $.
+
+    rust-google-translate --currency EUR --rates rates.txt -s EN -c DE A coffee costs $4.50.
+        > Ein Kaffee kostet 4,50 $ (≈ 4,14 EUR).
 
-    // Open the UI that we created in Glade
-    let glade_src = include_str!("translate.glade");
-    let builder = Builder::new_from_string(glade_src);
+    rust-google-translate -t EN -t DE -c Saluton
+        > EN:
+        > Hello
+        >
+        > DE:
+        > Hallo
 
-    // Grab the elements from the UI
-    let window: Window = builder.get_object("main_window").unwrap();
-    let translate_button: Button = builder.get_object("translate_button").unwrap();
-    let translation_input: TextView = builder.get_object("translation_input").unwrap();
-    let language_box: ComboBoxText = builder.get_object("language").unwrap();
+    rust-google-translate --format json -c EN Saluton | jq -r .source_language
+        > eo
 
-    // Add a TextBuffer to every TextView
-    let input_buffer = TextBuffer::new(Some(&TextTagTable::new()));
-    translation_input.set_buffer(Some(&input_buffer));
+    paste original.txt <(rust-google-translate --lines EN < original.txt)
 
-    // Wrap translation_button so that it may be borrowed multiple times
-    let wrapped_translation_button = Rc::new(RefCell::new(translate_button));
+    grep -i error build.log | rust-google-translate -t EN --filter
 
-    {   // Take the input buffer, translate it, and output it to the outbut buffer.
-        let translate_button = wrapped_translation_button.clone();
-        translate_button.borrow().connect_clicked(move |_| {
-            // Get the input buffer's text
-            let buffer = translation_input.get_buffer().unwrap();
-            let string = buffer.get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false).unwrap();
+    rust-google-translate file README.eo.txt -t EN -o README.txt
 
-            // Get the langauge combo box's text.
-            let language = match_language(language_box.get_active_text().unwrap().as_str());
+    rust-google-translate file artikolo.txt -t EN --summarize 20%
 
-            // Translate the text.
-            let mut translation = String::new();
-            translate(&string, language.as_str(), &mut translation);
+    rust-google-translate memory import glossary.tmx && rust-google-translate --memory file manual.txt -t DE
+"#;
 
-            // Immediately translate the text
-            translation_input.get_buffer().unwrap().set_text(translation.as_str());
-        });
+fn main() {
+    let mut arguments = std::env::args().skip(1).peekable();
+    if arguments.peek().is_none() {
+        launch_gui();
+        return
     }
 
-    // Exit the program if it receives the delete event.
-    window.connect_delete_event(|_,_| {
-        gtk::main_quit();
-        Inhibit(false)
-    });
-
-    { // Program what the program should do when certain keys are pressed
-        let translate_button = wrapped_translation_button.clone();
-        window.connect_key_press_event(move |_,key| {
-            match key.get_keyval() {
-                key::Escape => gtk::main_quit(),
-                key::Return  => translate_button.borrow().clicked(),
-                _ => ()
+    let mut max_length: Option<usize> = None;
+    let mut overflow = Overflow::Truncate;
+    let mut wrap = terminal_width();
+    let mut jobs = 4;
+    let endpoints = environment_endpoints();
+    let mut options = TranslatorOptions {
+        timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT)),
+        chunk_length: MAX_SEGMENT_LENGTH,
+        concurrent_chunks: false,
+        audit_path: std::env::var(AUDIT_LOG_VARIABLE).ok(),
+        no_log: false,
+        tls: TlsConfig::default(),
+        proxy: environment_proxy(&endpoints),
+        retry: RetryPolicy::default(),
+        endpoints,
+        user_agents: Vec::new(),
+        no_cache: false,
+        cache_ttl: DEFAULT_CACHE_TTL,
+        throttle: None,
+        memory: false,
+        memory_threshold: DEFAULT_MATCH_THRESHOLD,
+        redact: environment_redact(),
+        glossary: Glossary::new(),
+        hints: Vec::new(),
+        on_retry: None,
+    };
+    let mut details = false;
+    let mut format = Format::Text;
+    let mut errors = Errors::Text;
+    let mut progressing = true;
+    let mut resuming = false;
+    let mut reviewing = false;
+    let mut paging = false;
+    let mut summary: Option<f64> = None;
+    let mut names: Option<Vec<String>> = None;
+    let mut placeholders = false;
+    let mut markup: Option<Markup> = None;
+    let mut stripping = false;
+    let mut given_endpoints = false;
+    let mut given_proxy = false;
+    let mut kiosk = false;
+    let mut compare = false;
+    let mut live = false;
+    let mut localizing = false;
+    let mut normalization = Normalization::default();
+    let mut units = Units::Target;
+    let mut currency: Option<String> = None;
+    let mut rates: Option<Rates> = None;
+    let mut source: Option<String> = None;
+    let mut targets: Vec<String> = Vec::new();
+    let mut file: Option<String> = None;
+    let mut output: Option<String> = None;
+    let mut encoding: Option<OutputEncoding> = None;
+    while let Some(flag) = arguments.next() {
+        match flag.as_str() {
+            "-c" => {
+                // The languages are given directly after '-c', unless they were given beforehand with '-t'.
+                let languages = if targets.is_empty() {
+                    arguments.next().map(|lang| split_list(lang.as_str()))
+                } else {
+                    Some(std::mem::take(&mut targets))
+                };
+                if let Some(languages) = languages {
+                    let languages = match resolve_languages(&languages) {
+                        Some(languages) => languages,
+                        None            => return
+                    };
+                    let rates = match (currency.as_ref(), rates.take()) {
+                        (None, _)              => None,
+                        (Some(_), Some(rates)) => Some(rates),
+                        (Some(_), None)        => match read_cached_rates() {
+                            Ok(rates) => Some(rates),
+                            Err(why)  => {
+                                println!("rust-google-translate: no exchange rates for '--currency' ({}); give them with --rates FILE\n{}", why, TRY);
+                                return
+                            }
+                        }
+                    };
+                    let input = arguments.by_ref().fold(String::new(), |acc, x| acc + x.as_str() + " ");
+                    let input = match summary {
+                        Some(ratio) => summarize(input.as_str(), ratio),
+                        None        => input
+                    };
+                    let input = if stripping { strip_emoji(input.as_str()) } else { input };
+                    // HTML and Markdown are masked a block of text at a time as they are translated instead.
+                    let (input, masks) = if markup.is_none() {
+                        let mut entities = names.as_ref().map_or_else(Vec::new, |names| find_entities(input.as_str(), names));
+                        let found = if placeholders { find_placeholders(input.as_str()) } else { Vec::new() };
+                        for term in found {
+                            if !entities.contains(&term) { entities.push(term); }
+                        }
+                        entities.sort_by_key(|entity| std::cmp::Reverse(entity.len()));
+                        protect(input.as_str(), &entities)
+                    } else {
+                        (input, Vec::new())
+                    };
+                    let translator = build_translator(&options);
+                    let languages = languages.iter().map(String::as_str).collect::<Vec<&str>>();
+                    let results = if let Some(markup) = markup {
+                        let processing = Processing { placeholders, normalization, markup: Some(markup) };
+                        languages.iter().map(|language| {
+                            let (result, failures) = translate_markup(&translator, markup, input.as_str(), source.as_deref(), language,
+                                &processing, &Job::untracked());
+                            failures.into_iter().next().map_or(Ok(result), |(_, why)| Err(why))
+                        }).collect()
+                    } else if localizing {
+                        translate_localized(&translator, input.as_str(), source.as_deref(), &languages, units)
+                    } else {
+                        translator.translate_all(input.as_str(), source.as_deref(), &languages)
+                    };
+
+                    // With several languages, each result is labelled with its language and a failure is not fatal.
+                    let several = languages.len() > 1;
+                    let label = |language: &str| if several { format!("{}: ", language.to_uppercase()) } else { String::new() };
+                    let mut failures = 0;
+                    let mut members = Vec::new();
+                    let mut printed = String::new();
+                    for (index, (language, result)) in languages.iter().zip(results).enumerate() {
+                        let mut result = match result {
+                            Ok(result) => result,
+                            Err(why)   => {
+                                report_error(errors, &why, Some(index), label(language).as_str());
+                                members.push((language.to_uppercase(), Json::Null));
+                                failures += 1;
+                                continue
+                            }
+                        };
+                        if !masks.is_empty() {
+                            let (text, missing) = restore(result.text.as_str(), &masks);
+                            for name in missing {
+                                eprintln!("rust-google-translate: {}warning: '{}' did not survive translation", label(language), name);
+                            }
+                            result.text = text;
+                            for sentence in &mut result.sentences {
+                                sentence.translation = restore(sentence.translation.as_str(), &masks).0;
+                                sentence.source = restore(sentence.source.as_str(), &masks).0;
+                            }
+                        }
+                        if localizing {
+                            let from = source.as_deref().or(result.source_language.as_deref()).unwrap_or("EN");
+                            result.text = localize(result.text.as_str(), &Locale::for_language(from), &Locale::for_language(language));
+                        }
+                        if let (Some(currency), Some(rates)) = (currency.as_ref(), rates.as_ref()) {
+                            // Once localized, the amounts in the translation are written the way the target language writes them.
+                            let from = if localizing { Some(*language) } else { source.as_deref().or(result.source_language.as_deref()) };
+                            let from = Locale::for_language(from.unwrap_or("EN"));
+                            result.text = annotate_currency(result.text.as_str(), rates, currency, &from, &Locale::for_language(language));
+                        }
+                        if normalization.is_enabled() && markup.is_none() {
+                            result.text = normalize(result.text.as_str(), language, &normalization);
+                        }
+                        if details {
+                            if several { eprintln!("{}:", language.to_uppercase()); }
+                            print_details(&result);
+                        }
+                        if format == Format::Json {
+                            members.push((language.to_uppercase(), Json::from(result)));
+                            continue
+                        }
+                        if index != 0 && several { printed.push('\n'); }
+                        let translation = if format == Format::Man {
+                            // Each language is a section of the page, headed by its name.
+                            let text = if several { format!("{}\n\n{}", language_name(language).unwrap_or(*language).to_uppercase(), result.text) } else { result.text };
+                            Ok(render_man(text.as_str(), wrap.unwrap_or(80), std::io::stdout().is_terminal()))
+                        } else {
+                            if several { printed.push_str(format!("{}:\n", language.to_uppercase()).as_str()); }
+                            format_translation(result.text, max_length, overflow, wrap)
+                        };
+                        match translation {
+                            Ok(translation) => printed.push_str(translation.as_str()),
+                            Err(why) => {
+                                println!("rust-google-translate: {}{}", label(language), why);
+                                failures += 1;
+                            }
+                        }
+                    }
+                    if let Err(why) = show(printed.as_str(), paging) {
+                        eprintln!("rust-google-translate: unable to print the translation: {}", why);
+                        std::process::exit(1);
+                    }
+                    if format == Format::Json {
+                        if several {
+                            println!("{}", Json::Object(members));
+                        } else if failures == 0 {
+                            println!("{}", members[0].1);
+                        }
+                    }
+                    if failures == languages.len() {
+                        std::process::exit(1);
+                    } else if failures != 0 {
+                        std::process::exit(2);
+                    }
+                }
+            },
+            "detect" => {
+                let mut input = arguments.by_ref().collect::<Vec<String>>().join(" ");
+                if input.is_empty() {
+                    if let Err(why) = std::io::stdin().read_to_string(&mut input) {
+                        eprintln!("rust-google-translate: unable to read standard input: {}", why);
+                        std::process::exit(1);
+                    }
+                }
+                if stripping { input = strip_emoji(input.as_str()); }
+                let translator = build_translator(&options);
+                match translator.detect(input.as_str()) {
+                    Ok(detection) => match format {
+                        Format::Json => println!("{}", Json::from(detection)),
+                        Format::Text | Format::Man => match detection.confidence {
+                            Some(confidence) => println!("{}\t{}", detection.language.to_uppercase(), confidence),
+                            None             => println!("{}", detection.language.to_uppercase())
+                        }
+                    },
+                    Err(why) => {
+                        report_error(errors, &why, None, "");
+                        std::process::exit(1);
+                    }
+                }
+                return
+            },
+            "exec" => {
+                let mut command = arguments.by_ref().collect::<Vec<String>>();
+                if command.first().is_some_and(|first| first == "--") { command.remove(0); }
+                let lang = match single_language("exec", &mut targets) {
+                    Some(lang) => lang,
+                    None => {
+                        println!("rust-google-translate: 'exec' requires a target language given with -t\n{}", TRY);
+                        return
+                    }
+                };
+                if command.is_empty() {
+                    println!("rust-google-translate: 'exec' requires a command\n{}", TRY);
+                    return
+                }
+                let translator = build_translator(&options);
+                let processing = Processing { placeholders, normalization, markup: None };
+                match execute_translated(&translator, &command, source.as_deref(), lang.as_str(), &processing) {
+                    Ok(status) => std::process::exit(status),
+                    Err(why) => {
+                        eprintln!("rust-google-translate: unable to run {}: {}", command[0], why);
+                        std::process::exit(127);
+                    }
+                }
+            },
+            "memory" => {
+                let translation_memory = match open_memory(true) {
+                    Some(translation_memory) => translation_memory,
+                    None                     => return
+                };
+                match (arguments.next().as_deref(), arguments.next()) {
+                    (Some("import"), Some(path)) => {
+                        let imported = fs::read_to_string(&path).map_err(|why| why.to_string())
+                            .and_then(|tmx| translation_memory.import_tmx(tmx.as_str()));
+                        match imported {
+                            Ok(count) => println!("{} translations imported", count),
+                            Err(why) => {
+                                eprintln!("rust-google-translate: unable to import {}: {}", path, why);
+                                std::process::exit(1);
+                            }
+                        }
+                    },
+                    (Some("export"), Some(path)) => if let Err(why) = fs::write(&path, translation_memory.to_tmx()) {
+                        eprintln!("rust-google-translate: unable to write {}: {}", path, why);
+                        std::process::exit(1);
+                    },
+                    (Some("export"), None) => print!("{}", translation_memory.to_tmx()),
+                    _ => println!("rust-google-translate: 'memory' requires 'import FILE' or 'export [FILE]'\n{}", TRY)
+                }
+                return
+            },
+            "languages" => {
+                if format == Format::Json {
+                    let languages = LANGUAGES.iter().map(|&(code, name)| {
+                        Json::Object(vec![("code".to_string(), code.into()), ("name".to_string(), name.into())])
+                    }).collect::<Vec<Json>>();
+                    println!("{}", Json::from(languages));
+                } else {
+                    for &(code, name) in LANGUAGES {
+                        println!("{:<10}{}", code, name);
+                    }
+                }
+                return
+            },
+            "--lines" | "--filter" => {
+                // The language is given directly after '--lines', or beforehand with '-t' for '--filter'.
+                let lang = if flag == "--lines" { arguments.next() } else { single_language(&flag, &mut targets) };
+                match lang.map(|lang| resolve(lang.as_str())) {
+                    Some(None) => (),
+                    Some(Some(lang)) => {
+                        // An editor filtering text through --filter owns the terminal, so only --lines draws a bar.
+                        let progress = match progressing && flag == "--lines" {
+                            true  => Progress::new("standard input:", None, "lines"),
+                            false => Progress::hidden()
+                        };
+                        options.on_retry = progress.observer();
+                        let translator = build_translator(&options);
+                        let processing = Processing { placeholders, normalization, markup: None };
+                        let failures = translate_lines(&translator, source.as_deref(), lang.as_str(), jobs, &processing, &progress);
+                        if !failures.is_empty() {
+                            report_failures(&failures, errors);
+                            std::process::exit(2);
+                        }
+                    },
+                    None => println!("rust-google-translate: '{}' requires a language\n{}", flag, TRY)
+                }
+                return
+            },
+            "file" => match arguments.next() {
+                Some(path) => file = Some(path),
+                None => {
+                    println!("rust-google-translate: 'file' requires an input file\n{}", TRY);
+                    return
+                }
+            },
+            "-o" | "--output" => match arguments.next() {
+                Some(path) => output = Some(path),
+                None => {
+                    println!("rust-google-translate: '{}' requires an output file\n{}", flag, TRY);
+                    return
+                }
+            },
+            "--output-encoding" => match arguments.next().as_deref().map(OutputEncoding::parse) {
+                Some(Some(chosen)) => encoding = Some(chosen),
+                Some(None) => {
+                    println!("rust-google-translate: '--output-encoding' must be an encoding such as UTF-16LE+BOM, Shift_JIS or windows-1251\n{}", TRY);
+                    return
+                },
+                None => {
+                    println!("rust-google-translate: '--output-encoding' requires an encoding\n{}", TRY);
+                    return
+                }
+            },
+            "-t" | "--target" => match arguments.next() {
+                Some(lang) => match resolve_languages(&split_list(lang.as_str())) {
+                    Some(languages) => targets.extend(languages),
+                    None            => return
+                },
+                None => {
+                    println!("rust-google-translate: '{}' requires a language\n{}", flag, TRY);
+                    return
+                }
+            },
+            "--jobs" => {
+                match arguments.next().and_then(|value| value.parse::<usize>().ok()) {
+                    Some(value) if value > 0 => jobs = value,
+                    _ => {
+                        println!("rust-google-translate: '--jobs' requires a positive number\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "-d" | "--details" => details = true,
+            "--format" => {
+                match arguments.next().as_deref() {
+                    Some("text") => format = Format::Text,
+                    Some("json") => format = Format::Json,
+                    Some("man")  => format = Format::Man,
+                    _ => {
+                        println!("rust-google-translate: '--format' must be one of text, json or man\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--no-progress" => progressing = false,
+            "--resume" => resuming = true,
+            "--interactive-review" => reviewing = true,
+            "--errors" => {
+                match arguments.next().as_deref() {
+                    Some("text") => errors = Errors::Text,
+                    Some("json") => errors = Errors::Json,
+                    _ => {
+                        println!("rust-google-translate: '--errors' must be one of text or json\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--protect-names" => if names.is_none() { names = Some(Vec::new()) },
+            "--names" => match arguments.next() {
+                Some(path) => match read_names(path.as_str()) {
+                    Ok(list) => names.get_or_insert_with(Vec::new).extend(list),
+                    Err(why) => {
+                        println!("rust-google-translate: {}: {}", path, why);
+                        return
+                    }
+                },
+                None => {
+                    println!("rust-google-translate: '--names' requires a file\n{}", TRY);
+                    return
+                }
+            },
+            "--protect-placeholders" => placeholders = true,
+            "--html" => markup = Some(Markup::Html),
+            "--markdown" => markup = Some(Markup::Markdown),
+            "--subtitles" => markup = Some(Markup::Subtitles),
+            "--po" => markup = Some(Markup::Po),
+            "--glossary" => match arguments.next() {
+                Some(path) => match Glossary::open(path.as_str()) {
+                    Ok(glossary) => options.glossary = glossary,
+                    Err(why) => {
+                        println!("rust-google-translate: {}: {}", path, why);
+                        return
+                    }
+                },
+                None => {
+                    println!("rust-google-translate: '--glossary' requires a file\n{}", TRY);
+                    return
+                }
+            },
+            "--strip-emoji" => stripping = true,
+            "--audit-log" => match arguments.next() {
+                Some(path) => options.audit_path = Some(path),
+                None => {
+                    println!("rust-google-translate: '--audit-log' requires a file\n{}", TRY);
+                    return
+                }
+            },
+            "--no-log" => options.no_log = true,
+            "--kiosk" => kiosk = true,
+            "--compare" => compare = true,
+            "--live" => live = true,
+            "--tls-backend" => {
+                match arguments.next().as_deref() {
+                    Some("native") => options.tls.backend = TlsBackend::Native,
+                    Some("rustls") => options.tls.backend = TlsBackend::Rustls,
+                    _ => {
+                        println!("rust-google-translate: '--tls-backend' must be one of native or rustls\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--ca-bundle" => match arguments.next() {
+                Some(path) => options.tls.ca_bundle = Some(PathBuf::from(path)),
+                None => {
+                    println!("rust-google-translate: '--ca-bundle' requires a file\n{}", TRY);
+                    return
+                }
+            },
+            "--insecure" => options.tls.verify = false,
+            "--no-cache" => options.no_cache = true,
+            "--cache-ttl" => {
+                match arguments.next().and_then(|value| value.parse::<u64>().ok()) {
+                    Some(value) => options.cache_ttl = Duration::from_secs(value),
+                    None => {
+                        println!("rust-google-translate: '--cache-ttl' requires a number of seconds\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--memory" => options.memory = true,
+            "--memory-threshold" => {
+                match arguments.next().as_ref().and_then(|value| value.strip_suffix('%')).and_then(|value| value.parse::<f64>().ok()) {
+                    Some(value) if value > 0.0 && value <= 100.0 => options.memory_threshold = value / 100.0,
+                    _ => {
+                        println!("rust-google-translate: '--memory-threshold' requires a percentage such as 95%\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--user-agent" => match arguments.next() {
+                Some(user_agent) => options.user_agents.push(user_agent),
+                None => {
+                    println!("rust-google-translate: '--user-agent' requires a value\n{}", TRY);
+                    return
+                }
+            },
+            "--endpoint" => match arguments.next() {
+                Some(urls) => {
+                    // Endpoints given as options replace those from the environment, rather than adding to them.
+                    if !given_endpoints {
+                        options.endpoints.clear();
+                        given_endpoints = true;
+                    }
+                    options.endpoints.extend(split_list(urls.as_str()));
+                    if !given_proxy { options.proxy = environment_proxy(&options.endpoints) }
+                },
+                None => {
+                    println!("rust-google-translate: '--endpoint' requires a URL\n{}", TRY);
+                    return
+                }
+            },
+            "--proxy" => match arguments.next().map(|url| Proxy::parse(url.as_str())) {
+                Some(Ok(url)) => {
+                    options.proxy = Some(url);
+                    given_proxy = true;
+                },
+                Some(Err(why)) => {
+                    println!("rust-google-translate: {}\n{}", why, TRY);
+                    return
+                },
+                None => {
+                    println!("rust-google-translate: '--proxy' requires a URL\n{}", TRY);
+                    return
+                }
+            },
+            "--redact" => {
+                match arguments.next().and_then(|kinds| Sensitive::from_list(kinds.as_str())) {
+                    Some(kinds) => options.redact = kinds,
+                    _ => {
+                        println!("rust-google-translate: '--redact' requires a list of email, phone and card, or all\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--localize" => localizing = true,
+            "--punctuate" => normalization.punctuation = true,
+            "--capitalize" => normalization.capitalize = true,
+            "--typographic-quotes" => normalization.quotes = true,
+            "--units" => {
+                match arguments.next().as_deref() {
+                    Some("metric")   => units = Units::Metric,
+                    Some("imperial") => units = Units::Imperial,
+                    Some("keep")     => units = Units::Keep,
+                    _ => {
+                        println!("rust-google-translate: '--units' must be one of metric, imperial or keep\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--currency" => match arguments.next() {
+                Some(code) if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) => currency = Some(code.to_uppercase()),
+                _ => {
+                    println!("rust-google-translate: '--currency' requires a currency code such as EUR\n{}", TRY);
+                    return
+                }
+            },
+            "--rates" => match arguments.next() {
+                Some(path) => match read_rates(path.as_str()) {
+                    Ok(table) => rates = Some(table),
+                    Err(why) => {
+                        println!("rust-google-translate: {}: {}", path, why);
+                        return
+                    }
+                },
+                None => {
+                    println!("rust-google-translate: '--rates' requires a file\n{}", TRY);
+                    return
+                }
+            },
+            "--summarize" => {
+                match arguments.next().as_ref().and_then(|value| value.strip_suffix('%')).and_then(|value| value.parse::<f64>().ok()) {
+                    Some(value) if value > 0.0 && value <= 100.0 => summary = Some(value / 100.0),
+                    _ => {
+                        println!("rust-google-translate: '--summarize' requires a percentage such as 20%\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "-s" | "--source" => match arguments.next() {
+                Some(lang) => match resolve(lang.as_str()) {
+                    Some(lang) => source = Some(lang),
+                    None       => return
+                },
+                None => {
+                    println!("rust-google-translate: '{}' requires a language\n{}", flag, TRY);
+                    return
+                }
+            },
+            "--hint" => match arguments.next() {
+                Some(langs) => match resolve_languages(&split_list(langs.as_str())) {
+                    Some(languages) => options.hints = languages,
+                    None            => return
+                },
+                None => {
+                    println!("rust-google-translate: '--hint' requires a list of languages\n{}", TRY);
+                    return
+                }
+            },
+            "--timeout" => {
+                match arguments.next().and_then(|value| value.parse::<u64>().ok()) {
+                    Some(0)     => options.timeout = None,
+                    Some(value) => options.timeout = Some(Duration::from_secs(value)),
+                    None => {
+                        println!("rust-google-translate: '--timeout' requires a number of seconds\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--retries" => {
+                match arguments.next().and_then(|value| value.parse::<u32>().ok()) {
+                    Some(value) => options.retry.attempts = value.saturating_add(1),
+                    None => {
+                        println!("rust-google-translate: '--retries' requires a number\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--backoff" => {
+                match arguments.next().and_then(|value| value.parse::<u64>().ok()) {
+                    Some(value) => options.retry.backoff = Duration::from_millis(value),
+                    None => {
+                        println!("rust-google-translate: '--backoff' requires a number of milliseconds\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--no-jitter" => options.retry.jitter = false,
+            "--polite" => {
+                options.throttle = Some(Arc::new(Throttle::polite()));
+                options.retry = RetryPolicy::polite();
+                options.no_cache = false;
+                options.cache_ttl = options.cache_ttl.max(POLITE_CACHE_TTL);
+                options.concurrent_chunks = false;
+                jobs = 1;
+            },
+            "-h" | "--help" => {
+                println!("{}", HELP);
+                return
+            },
+            "--max-length" => {
+                match arguments.next().and_then(|value| value.parse::<usize>().ok()) {
+                    Some(value) if value > 0 => max_length = Some(value),
+                    _ => {
+                        println!("rust-google-translate: '--max-length' requires a positive number\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--wrap" => {
+                match arguments.next().and_then(|value| value.parse::<usize>().ok()) {
+                    Some(value) if value > 0 => wrap = Some(value),
+                    _ => {
+                        println!("rust-google-translate: '--wrap' requires a positive number\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--no-wrap" => wrap = None,
+            "--pager" => paging = true,
+            "--chunk-length" => {
+                match arguments.next().and_then(|value| value.parse::<usize>().ok()) {
+                    Some(value) if value > 0 => options.chunk_length = value,
+                    _ => {
+                        println!("rust-google-translate: '--chunk-length' requires a positive number\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            "--parallel-chunks" => options.concurrent_chunks = true,
+            "--overflow" => {
+                match arguments.next().as_deref() {
+                    Some("truncate") => overflow = Overflow::Truncate,
+                    Some("error")    => overflow = Overflow::Error,
+                    Some("chunk")    => overflow = Overflow::Chunk,
+                    _ => {
+                        println!("rust-google-translate: '--overflow' must be one of truncate, error or chunk\n{}", TRY);
+                        return
+                    }
+                }
+            },
+            _ => {
+                println!("rust-google-translate: invalid option -- '{}'\n{}", flag, TRY);
+                return
             }
-            Inhibit(false)
-        });
+        }
     }
 
-    // Show the window and start the program
-    window.show_all();
-    gtk::main();
-}
+    if kiosk {
+        let lang = match single_language("--kiosk", &mut targets) {
+            Some(lang) => lang,
+            None => {
+                println!("rust-google-translate: '--kiosk' requires a target language given with -t\n{}", TRY);
+                return
+            }
+        };
+        let translator = build_translator(&options);
+        launch_kiosk(translator, source, lang);
+        return
+    }
 
-/// Send text to Google Translate and translate it.
-fn translate(input: &str, language: &str, output: &mut String) {
-    let mut search = String::new();
-    search.push_str(TRANSLATE);
-    search.push_str(language);
-    search.push_str("&dt=t&q=");
-    search.push_str(input);
-    if let Ok(mut response) = Client::new().get(&search).header(Connection::close()).send() {
-        search.clear();
-        if let Err(error) = response.read_to_string(&mut search) {
-            panic!("Unable to read response: {}", error);
+    if compare {
+        if targets.len() > 2 {
+            println!("rust-google-translate: '--compare' compares two languages at a time\n{}", TRY);
+            return
+        }
+        let mut defaults = vec!["EN".to_string(), "ES".to_string()].into_iter();
+        let languages = [targets.get(0).cloned(), targets.get(1).cloned()]
+            .map(|language| language.or_else(|| defaults.next()).unwrap_or_default());
+        let translator = build_translator(&options);
+        launch_compare(translator, source, languages, live);
+        return
+    }
+
+    if let Some(file) = file {
+        let lang = match single_language("file", &mut targets) {
+            Some(lang) => lang,
+            None => {
+                println!("rust-google-translate: 'file' requires a target language given with -t\n{}", TRY);
+                return
+            }
+        };
+        if encoding.is_some() && output.is_none() {
+            println!("rust-google-translate: '--output-encoding' requires an output file given with -o\n{}", TRY);
+            return
+        }
+        if reviewing && markup.is_some() {
+            println!("rust-google-translate: '--interactive-review' reviews plain text files, not --html, --markdown, --subtitles or --po\n{}", TRY);
+            return
+        }
+        if reviewing && !std::io::stdin().is_terminal() {
+            println!("rust-google-translate: '--interactive-review' reads its answers from a terminal, which standard input is not\n{}", TRY);
+            return
+        }
+        let progress = if progressing { Progress::new(file.as_str(), None, "bytes") } else { Progress::hidden() };
+        options.on_retry = progress.observer();
+        let translator = build_translator(&options);
+        let processing = Processing { placeholders, normalization, markup };
+        // The same file translated the same way is the same job, wherever it is run from.
+        let path = fs::canonicalize(&file).map_or_else(|_| file.clone(), |path| path.display().to_string());
+        let name = format!("file\n{}\n{}\n{}\n{:?}\n{:?}", path, source.as_deref().unwrap_or("auto"), lang, processing, summary);
+        let review = if reviewing { Some(RefCell::new(Reviewer::terminal())) } else { None };
+        let mut job = Job { progress, ledger: open_ledger(name.as_str(), resuming), review };
+        let translated = translate_file(&translator, source.as_deref(), lang.as_str(), file.as_str(), summary, &processing, &job)
+            .and_then(|translated| {
+                match output.as_deref() {
+                    // A file written in UTF-16, or with a byte order mark, is translated into a file written the same way.
+                    Some(path) => match encoding.or(translated.encoding) {
+                        Some(encoding) => {
+                            let (bytes, unmappable) = encoding.encode(translated.text.as_str());
+                            File::create(path)?.write_all(&bytes)?;
+                            if !unmappable.is_empty() {
+                                let mut characters = Vec::new();
+                                for character in unmappable.iter().map(char::to_string) {
+                                    if !characters.contains(&character) { characters.push(character); }
+                                }
+                                eprintln!("rust-google-translate: warning: {} character(s) cannot be written in {} and were written as '?': {}",
+                                    unmappable.len(), encoding.name(), characters.join(" "));
+                            }
+                        },
+                        None => File::create(path)?.write_all(translated.text.as_bytes())?
+                    },
+                    None if format == Format::Man => {
+                        show(render_man(translated.text.as_str(), wrap.unwrap_or(80), std::io::stdout().is_terminal()).as_str(), paging)?
+                    },
+                    None => show(translated.text.as_str(), paging)?
+                }
+                if !translated.near_matches.is_empty() {
+                    eprintln!("rust-google-translate: {} segment(s) were translated from near matches in the translation memory; look them over:",
+                        translated.near_matches.len());
+                    for &(line, similarity) in &translated.near_matches {
+                        eprintln!("    segment at line {}: {:.0}% match", line, similarity * 100.0);
+                    }
+                }
+                Ok(translated.failures)
+            });
+        // Until every segment has been translated and written, the ledger is kept so that the job can be resumed.
+        let resumable = job.ledger.is_some();
+        match translated {
+            Ok(ref failures) if failures.is_empty() => if let Some(ledger) = job.ledger.take() {
+                let _ = ledger.finish();
+            },
+            Ok(failures) if errors == Errors::Json => {
+                for (line, why) in failures {
+                    report_error(errors, &why, Some(line - 1), "");
+                }
+                std::process::exit(2);
+            },
+            Ok(failures) => {
+                eprintln!("rust-google-translate: {} segment(s) could not be translated and were left as they were:", failures.len());
+                for (line, why) in failures {
+                    eprintln!("    segment at line {}: {}", line, why);
+                }
+                if resumable { eprintln!("To translate only those, run the same command again with --resume"); }
+                std::process::exit(2);
+            },
+            Err(why) if errors == Errors::Json => {
+                print_error_json("io", format!("{}: {}", file, why).as_str(), None, false);
+                std::process::exit(1);
+            },
+            Err(why) => {
+                eprintln!("rust-google-translate: {}: {}", file, why);
+                std::process::exit(1);
+            }
         }
     }
-    parse_message(search.as_str(), output);
 }
 
-/// Take the raw response from Google and parse the translation only.
-fn parse_message(input: &str, translation: &mut String) {
-    let mut escape      = false;
-    let mut ignore      = false;
-    let mut found_match = false;
-    let mut matched: u8 = 0;
-
-    // Loop until ',,,0]]' is found
-    for character in input.chars().skip(4) {
-        if found_match {
-            matched = match matched {
-                0 => 1,
-                1 => { found_match = false; 0 },
-                _     => unreachable!()
+/// Normalise `language` to a code that Google supports, printing an error along with the nearest
+/// languages if it cannot be.
+fn resolve(language: &str) -> Option<String> {
+    match resolve_language(language) {
+        Ok(code) => Some(code),
+        Err(suggestions) => {
+            let suggestions = suggestions.iter()
+                .map(|&code| format!("'{}' ({})", code, language_name(code).unwrap_or(code)))
+                .collect::<Vec<String>>();
+            match suggestions.len() {
+                0 => println!("rust-google-translate: unsupported language '{}'", language),
+                _ => println!("rust-google-translate: unsupported language '{}'; did you mean {}?", language, suggestions.join(" or "))
             }
-        } else if ignore {
-            matched = match (matched, character) {
-                (0, ',') => 1,
-                (1, ',') => 2,
-                (2, ',') => 3,
-                (3, '0') => 4,
-                (4, ']') => 5,
-                (5, ']') => break, // ',,,0]]' has been found
-                (5, _)   => {ignore = false; found_match = true; 0 }
-                _ => 0
-            };
-        } else if character == '\\' && !escape {
-            escape = true;
-        } else if escape {
-            translation.push(character);
-            escape = false;
-        } else if character == '"' {
-            ignore = true;
-        } else {
-            translation.push(character);
+            println!("Run 'rust-google-translate languages' for a list of supported languages");
+            None
         }
     }
 }
 
+/// Normalise each of `languages`, returning `None` if any of them cannot be.
+fn resolve_languages(languages: &[String]) -> Option<Vec<String>> {
+    languages.iter().map(|language| resolve(language.as_str())).collect()
+}
 
-#[test]
-fn test_parse_message() {
-    const TEST: &'static str = "[[[\"I am not you. \",\"Mi estas ne vin.\",,,0],[\"You are not me.\",\"Vi estas ne min.\",,,0]],,\"eo\",,,,0.070792444,,[[\"eo\"],,[0.070792444],[\"eo\"]]]";
-    let mut output = String::new();
-    parse_message(TEST, &mut output);
-    assert_eq!(output.as_str(), "I am not you. You are not me.")
+/// Take the one language given with -t for a mode which translates into a single language, exiting if
+/// several were given.
+fn single_language(mode: &str, targets: &mut Vec<String>) -> Option<String> {
+    if targets.len() > 1 {
+        println!("rust-google-translate: '{}' translates into one language at a time\n{}", mode, TRY);
+        std::process::exit(1);
+    }
+    targets.pop()
 }
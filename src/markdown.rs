@@ -0,0 +1,417 @@
+//! Translation of Markdown, in which only the prose is translated and the syntax is kept as it was.
+//!
+//! Each paragraph, heading, list item, quotation and table cell is translated as a whole, with the lines of a
+//! paragraph joined together. The markers which begin a block, such as `#`, `>` and `- `, are kept aside, and
+//! the syntax within the text, such as emphasis, link destinations, inline code and URLs, is masked with
+//! `protect`-style placeholders so that Google may move it along with the words it belongs to. Code blocks,
+//! front matter, HTML blocks and link reference definitions are not translated at all.
+
+use entity::{find_bracketed_numbers, restore};
+
+/// Translate the prose of the Markdown in `input` with `translate`, which is given each block of text, with
+/// its syntax replaced by placeholders such as `[0]`, along with the offset in `input` of the line it begins
+/// on, and returns its translation. Any placeholders it loses are put back at the end of the block.
+pub fn translate_markdown<F: FnMut(&str, usize) -> String>(input: &str, mut translate: F) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut paragraph: Option<Paragraph> = None;
+    let mut fence: Option<(char, usize)> = None;
+    let mut front_matter = input.starts_with("---\n") || input.starts_with("---\r\n");
+    let mut previous_blank = true;
+    let mut in_list = false;
+    let mut offset = 0;
+    for (number, line) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[content.len()..];
+
+        if front_matter {
+            output.push_str(line);
+            front_matter = number == 0 || !(content == "---" || content == "...");
+            continue;
+        }
+        if let Some((marker, length)) = fence {
+            output.push_str(line);
+            let text = split_container(content).1.trim();
+            if text.len() >= length && text.chars().all(|c| c == marker) { fence = None; }
+            continue;
+        }
+
+        let (prefix, text, item) = split_container(content);
+        let indented = content.starts_with('\t') || content.starts_with("    ");
+        let continues = paragraph.as_ref().is_some_and(|paragraph| !paragraph.hard_break && paragraph.quoted == prefix.contains('>'));
+        let literal = text.trim().is_empty() || is_thematic_break(content) || text.starts_with('|')
+            || fence_marker(text).is_some() || is_html_block(text) || is_reference_definition(text)
+            || heading_level(text) > 0 || (indented && previous_blank && !in_list) || item;
+        if !literal && continues {
+            // A line of text within a paragraph continues it, even if it is not indented to match.
+            let current = paragraph.as_mut().unwrap();
+            current.text.push(' ');
+            current.text.push_str(text.trim_end_matches([' ', '\\']));
+            current.ending = content[content.trim_end_matches(' ').len()..].to_string() + ending;
+            current.hard_break = is_hard_break(content);
+            previous_blank = false;
+            continue;
+        }
+        if let Some(paragraph) = paragraph.take() {
+            paragraph.flush(&mut output, &mut translate);
+        }
+
+        if text.trim().is_empty() {
+            output.push_str(line);
+            previous_blank = true;
+            continue;
+        }
+        if previous_blank && !content.starts_with([' ', '\t']) && !item {
+            in_list = false;
+        }
+        previous_blank = false;
+        if (indented && !in_list) || is_thematic_break(content) || is_html_block(text) || is_reference_definition(text) {
+            output.push_str(line);
+        } else if let Some(marker) = fence_marker(text) {
+            fence = Some(marker);
+            output.push_str(line);
+        } else if text.starts_with('|') {
+            output.push_str(prefix);
+            output.push_str(translate_row(text, start, &mut translate).as_str());
+            output.push_str(ending);
+        } else if heading_level(text) > 0 {
+            // The closing sequence of #s, if any, is kept along with the opening one.
+            let level = heading_level(text);
+            let body = text[level..].trim_start();
+            let closed = body.trim_end().trim_end_matches('#');
+            let closed = if closed.ends_with(' ') || closed.is_empty() { closed.trim_end() } else { body.trim_end() };
+            output.push_str(prefix);
+            output.push_str(&text[..text.len() - body.len()]);
+            output.push_str(translate_inline(closed, start, &mut translate).as_str());
+            output.push_str(&body[closed.len()..]);
+            output.push_str(ending);
+        } else {
+            in_list = in_list || item;
+            paragraph = Some(Paragraph {
+                prefix: prefix.to_string(),
+                text: text.trim_end_matches([' ', '\\']).to_string(),
+                offset: start,
+                ending: content[content.trim_end_matches(' ').len()..].to_string() + ending,
+                quoted: prefix.contains('>'),
+                hard_break: is_hard_break(content),
+            });
+        }
+    }
+    if let Some(paragraph) = paragraph.take() {
+        paragraph.flush(&mut output, &mut translate);
+    }
+    output
+}
+
+/// A paragraph, or the text of a list item, which may run over several lines.
+struct Paragraph {
+    /// The indentation and block markers before its first line.
+    prefix: String,
+    /// Its lines, joined with spaces.
+    text: String,
+    /// Where its first line begins in the input.
+    offset: usize,
+    /// What follows its last line, including any spaces which mark a hard line break.
+    ending: String,
+    /// Whether it is within a quotation.
+    quoted: bool,
+    /// Whether its last line ends with a hard line break, after which the next line begins a new paragraph.
+    hard_break: bool,
+}
+
+impl Paragraph {
+    fn flush<F: FnMut(&str, usize) -> String>(self, output: &mut String, translate: &mut F) {
+        // A backslash which marks a hard line break was taken off with the trailing spaces, and goes back on.
+        let backslash = self.hard_break && !self.ending.starts_with(' ');
+        output.push_str(self.prefix.as_str());
+        output.push_str(translate_inline(self.text.as_str(), self.offset, translate).as_str());
+        if backslash { output.push('\\'); }
+        output.push_str(self.ending.as_str());
+    }
+}
+
+/// Translate `text` with `translate`, masking its inline syntax: code spans, autolinks, inline HTML, URLs,
+/// backslash escapes, emphasis markers, and the brackets and destinations of links and images. Text with no
+/// words outside its syntax is returned as it is.
+fn translate_inline<F: FnMut(&str, usize) -> String>(text: &str, offset: usize, translate: &mut F) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut tags: Vec<String> = Vec::new();
+    // The positions of the ']' which close the links that are open, and the length of what follows them.
+    let mut closing: Vec<(usize, usize)> = Vec::new();
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        let length = if closing.last().is_some_and(|&(end, _)| end == index) {
+            closing.pop().map(|(_, length)| length)
+        } else {
+            match rest.chars().next().unwrap_or(' ') {
+                '`'       => code_span(rest),
+                '\\'      => rest[1..].chars().next().filter(char::is_ascii_punctuation).map(|_| 2),
+                '<'       => angle_bracket(rest),
+                '*' | '_' => emphasis(text, index),
+                '~'       => if rest.starts_with("~~") { Some(2) } else { None },
+                'h'       => url(text, index),
+                '[' | '!' => link(text, index).map(|(opening, end, length)| {
+                    closing.push((end, length));
+                    opening
+                }).or_else(|| bracketed_number(rest)),
+                _         => None
+            }
+        };
+        match length {
+            Some(length) => {
+                masked.push_str(format!("[{}]", tags.len()).as_str());
+                tags.push(text[index..index + length].to_string());
+                index += length;
+            },
+            None => {
+                let character = rest.chars().next().unwrap_or(' ');
+                masked.push(character);
+                index += character.len_utf8();
+            }
+        }
+    }
+
+    if !restore(masked.as_str(), &vec![String::new(); tags.len()]).0.chars().any(char::is_alphabetic) {
+        return text.to_string();
+    }
+    let (translation, missing) = restore(translate(masked.as_str(), offset).as_str(), &tags);
+    missing.into_iter().fold(translation, |translation, tag| translation + tag.as_str())
+}
+
+/// The length of the bracketed number at the start of `text`, such as the citation `[1]`, which is not a link
+/// but would be taken for the placeholder of one.
+fn bracketed_number(text: &str) -> Option<usize> {
+    find_bracketed_numbers(text).first().filter(|&&(start, _)| start == 0).map(|&(_, end)| end)
+}
+
+/// Translate the cells of the table row `row` one at a time, keeping the pipes and the spaces which pad them.
+fn translate_row<F: FnMut(&str, usize) -> String>(row: &str, offset: usize, translate: &mut F) -> String {
+    if row.chars().all(|c| "|:- \t".contains(c)) {
+        return row.to_string();
+    }
+    let mut cells = Vec::new();
+    let (mut start, mut code, mut escaped) = (0, false, false);
+    for (index, character) in row.char_indices() {
+        match character {
+            '|' if !code && !escaped => { cells.push(&row[start..index]); start = index + 1; },
+            '`' => code = !code,
+            _ => ()
+        }
+        escaped = character == '\\' && !escaped;
+    }
+    cells.push(&row[start..]);
+    cells.into_iter().map(|cell| {
+        let trimmed = cell.trim();
+        let leading = cell.len() - cell.trim_start().len();
+        format!("{}{}{}", &cell[..leading], translate_inline(trimmed, offset, translate), &cell[leading + trimmed.len()..])
+    }).collect::<Vec<String>>().join("|")
+}
+
+/// Split `line` into the indentation and markers of the quotations and list item it is within, the text
+/// after them, and whether it begins a list item. The box of a task list item is taken to be a marker.
+fn split_container(line: &str) -> (&str, &str, bool) {
+    let mut index = 0;
+    let mut item = false;
+    loop {
+        let rest = &line[index..];
+        let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+        let after = &rest[spaces..];
+        if after.starts_with('>') {
+            index += spaces + 1;
+            if line[index..].starts_with(' ') { index += 1; }
+        } else if !item && list_marker(after) > 0 && !is_thematic_break(after) {
+            index += spaces + list_marker(after);
+            item = true;
+            if ["[ ] ", "[x] ", "[X] "].iter().any(|task| line[index..].starts_with(task)) { index += 4; }
+        } else {
+            index += spaces;
+            break;
+        }
+    }
+    (&line[..index], &line[index..], item)
+}
+
+/// The length of the list marker which `text` begins with, including the space after it, such as `- ` or
+/// `2. `, or 0 if it begins with none.
+fn list_marker(text: &str) -> usize {
+    let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let marker = match (digits, text[digits..].chars().next()) {
+        (0, Some('-')) | (0, Some('*')) | (0, Some('+')) => 1,
+        (1..=9, Some('.')) | (1..=9, Some(')'))         => digits + 1,
+        _                                               => return 0
+    };
+    match text[marker..].chars().next() {
+        Some(' ') | Some('\t') => marker + 1,
+        None                   => marker,
+        _                      => 0
+    }
+}
+
+/// The level of the ATX heading which `text` begins, or 0 if it is not one.
+fn heading_level(text: &str) -> usize {
+    let level = text.len() - text.trim_start_matches('#').len();
+    let spaced = text[level..].is_empty() || text[level..].starts_with([' ', '\t']);
+    if (1..=6).contains(&level) && spaced { level } else { 0 }
+}
+
+/// The character and length of the code fence which `text` opens, if it opens one.
+fn fence_marker(text: &str) -> Option<(char, usize)> {
+    let marker = text.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let length = text.len() - text.trim_start_matches(marker).len();
+    // The info string of a backtick fence may not contain backticks, or it would be a code span.
+    let valid = length >= 3 && !(marker == '`' && text[length..].contains('`'));
+    if valid { Some((marker, length)) } else { None }
+}
+
+/// Whether `line` is a thematic break, such as `---`, `***` or `_ _ _`.
+fn is_thematic_break(line: &str) -> bool {
+    let line = line.trim();
+    let marker = match line.chars().next() {
+        Some(marker) if "-*_".contains(marker) => marker,
+        _ => return false
+    };
+    line.chars().all(|c| c == marker || c == ' ' || c == '\t') && line.matches(marker).count() >= 3
+}
+
+/// Whether `text` begins a block of HTML, which is passed through untranslated, rather than an autolink.
+fn is_html_block(text: &str) -> bool {
+    let name = match text.strip_prefix('<') {
+        Some(rest) if rest.starts_with("!--") => return true,
+        Some(rest) => rest.trim_start_matches('/'),
+        None => return false
+    };
+    let length = name.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(name.len());
+    length > 0 && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && (name[length..].is_empty() || name[length..].starts_with([' ', '>', '/', '\t']))
+}
+
+/// Whether `text` is a link reference definition, such as `[docs]: https://example.com`.
+fn is_reference_definition(text: &str) -> bool {
+    text.starts_with('[') && text.find("]:").is_some_and(|end| end > 1 && !text[1..end].contains(['[', ']']))
+}
+
+/// Whether `line` ends with a hard line break: two spaces or a backslash.
+fn is_hard_break(line: &str) -> bool {
+    line.ends_with("  ") || line.ends_with('\\')
+}
+
+/// The length of the code span at the start of `text`, which is closed by a run of as many backticks as it
+/// opens with, if it is closed.
+fn code_span(text: &str) -> Option<usize> {
+    let opening = text.len() - text.trim_start_matches('`').len();
+    let mut index = opening;
+    while let Some(start) = text[index..].find('`').map(|start| index + start) {
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == opening { return Some(start + run) }
+        index = start + run;
+    }
+    None
+}
+
+/// The length of the autolink, such as `<https://example.com>`, or inline HTML tag at the start of `text`.
+fn angle_bracket(text: &str) -> Option<usize> {
+    let end = text.find('>')?;
+    let inside = &text[1..end];
+    let autolink = !inside.is_empty() && !inside.contains(char::is_whitespace) && inside.contains([':', '@']);
+    let tag = inside.trim_start_matches('/').starts_with(|c: char| c.is_ascii_alphabetic()) || inside.starts_with("!--");
+    if autolink || tag { Some(end + 1) } else { None }
+}
+
+/// The length of the run of emphasis markers at `index` in `text`, if it opens or closes emphasis: it must
+/// be next to a word, and a run of underscores may not be within one, as in `snake_case`.
+fn emphasis(text: &str, index: usize) -> Option<usize> {
+    let marker = text[index..].chars().next()?;
+    let length = text[index..].len() - text[index..].trim_start_matches(marker).len();
+    let before = text[..index].chars().next_back();
+    let after = text[index + length..].chars().next();
+    let word = |c: Option<char>| c.is_some_and(|c| !c.is_whitespace());
+    let within = before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric);
+    if (word(before) || word(after)) && !(marker == '_' && within) { Some(length) } else { None }
+}
+
+/// The length of the URL at `index` in `text`, if one begins there, without any punctuation after it.
+fn url(text: &str, index: usize) -> Option<usize> {
+    let rest = &text[index..];
+    let starts = (rest.starts_with("http://") || rest.starts_with("https://"))
+        && !text[..index].ends_with(|c: char| c.is_alphanumeric());
+    if !starts { return None }
+    let end = rest.find(|c: char| c.is_whitespace() || c == '<').unwrap_or(rest.len());
+    Some(rest[..end].trim_end_matches(|c: char| ".,;:!?)'\"".contains(c)).len())
+}
+
+/// If a link or image begins at `index` in `text`, the length of its opening `[` or `![`, the position of its
+/// closing `]`, and the length of the `]` along with the destination or reference which follows it.
+fn link(text: &str, index: usize) -> Option<(usize, usize, usize)> {
+    let opening = if text[index..].starts_with("![") { 2 } else if text[index..].starts_with('[') { 1 } else { return None };
+    let mut depth = 0;
+    let mut end = None;
+    let mut position = index + opening;
+    while position < text.len() {
+        let rest = &text[position..];
+        match rest.chars().next().unwrap_or(' ') {
+            '\\' => position += rest[1..].chars().next().map_or(0, char::len_utf8),
+            '`'  => position += code_span(rest).unwrap_or(1) - 1,
+            '['  => depth += 1,
+            ']' if depth == 0 => { end = Some(position); break },
+            ']'  => depth -= 1,
+            _    => ()
+        }
+        position += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    let end = end?;
+    let after = &text[end + 1..];
+    let (open, close) = match after.chars().next() {
+        Some('(') => ('(', ')'),
+        Some('[') => ('[', ']'),
+        _         => return None
+    };
+    // Destinations may contain balanced parentheses, as in Wikipedia's URLs.
+    let mut depth = 0;
+    for (offset, character) in after.char_indices() {
+        if character == open { depth += 1 }
+        if character == close { depth -= 1 }
+        if depth == 0 { return Some((opening, end, offset + 2)) }
+    }
+    None
+}
+
+#[test]
+fn test_translate_markdown() {
+    let input = "---\ntitle: Saluton\n---\n# Saluton *mondo* #\n\n\
+        Mi estas **ne** vin. Vidu [la dokumentojn](https://eo.wikipedia.org/wiki/Vi_(pronomo) \"Titolo\")\n\
+        kaj `cargo build`, au https://example.com.\n\n\
+        - unu\n- [x] du <https://example.com>\n  daŭrigo\n\n\
+        ```rust\nlet saluton = \"mondo\";\n```\n\n    indented code\n\n\
+        > Citaĵo  \n> daŭras\n\n\
+        | Nomo | Priskribo |\n|------|-----------|\n| `a`  | unua_varo |\n\n\
+        [ref]: https://example.com\n***\n![Bildo de kato][kato] 3 * 4\n";
+    let mut sent = Vec::new();
+    let output = translate_markdown(input, |text, offset| {
+        sent.push((text.to_string(), offset));
+        text.to_uppercase()
+    });
+    assert_eq!(output, "---\ntitle: Saluton\n---\n# SALUTON *MONDO* #\n\n\
+        MI ESTAS **NE** VIN. VIDU [LA DOKUMENTOJN](https://eo.wikipedia.org/wiki/Vi_(pronomo) \"Titolo\") \
+        KAJ `cargo build`, AU https://example.com.\n\n\
+        - UNU\n- [x] DU <https://example.com> DAŬRIGO\n\n\
+        ```rust\nlet saluton = \"mondo\";\n```\n\n    indented code\n\n\
+        > CITAĴO  \n> DAŬRAS\n\n\
+        | NOMO | PRISKRIBO |\n|------|-----------|\n| `a`  | UNUA_VARO |\n\n\
+        [ref]: https://example.com\n***\n![BILDO DE KATO][kato] 3 * 4\n");
+    assert_eq!(sent[0].0.as_str(), "Saluton [0]mondo[1]");
+    assert_eq!(sent[1].0.as_str(), "Mi estas [0]ne[1] vin. Vidu [2]la dokumentojn[3] kaj [4], au [5].");
+    assert!(input[sent[1].1..].starts_with("Mi estas"));
+    assert_eq!(sent.len(), 10);
+
+    // A translation which loses a placeholder still has its syntax put back.
+    assert_eq!(translate_markdown("Uzu `make`.", |_, _| "Use.".to_string()).as_str(), "Use.`make`");
+    assert_eq!(translate_markdown("[\\é](x)", |text, _| text.to_string()).as_str(), "[\\é](x)");
+
+    // A citation is masked as syntax of its own rather than taken for a placeholder.
+    assert_eq!(translate_markdown("See note [1] and *this* word.", |text, _| {
+        assert_eq!(text, "See note [0] and [1]this[2] word.");
+        text.to_string()
+    }).as_str(), "See note [1] and *this* word.");
+}
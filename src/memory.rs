@@ -0,0 +1,493 @@
+//! A translation memory, which keeps each piece of text that has been translated along with its translation,
+//! so that the same or nearly the same text can be translated again without asking Google.
+//!
+//! The memory is kept as one JSON object per line, so that new translations are added to it without rewriting
+//! it, which is only rewritten when a translation replaces another, and can be exchanged with other translation
+//! tools as TMX.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use cache::temporary_path;
+use json::Json;
+use language::distance;
+use placeholder::find_placeholders;
+
+/// How similar, from 0 to 1, text must be to text in the memory for its translation to be used, unless
+/// configured otherwise. This allows for little more than a difference in punctuation or a typing mistake.
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.95;
+
+/// A piece of text and its translation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TranslationUnit {
+    /// The language of the original, if it is known.
+    pub source_language: Option<String>,
+    pub target_language: String,
+    pub source: String,
+    pub target: String,
+}
+
+/// A unit found in the memory for a piece of text, and how similar its source is to that text, from 0 to 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryMatch {
+    pub unit: TranslationUnit,
+    pub similarity: f64,
+}
+
+/// A translation memory, which may be shared between threads.
+pub struct TranslationMemory {
+    path: Option<PathBuf>,
+    store: Mutex<Store>,
+}
+
+/// The units of a memory in the order that they were added, along with an index of the trigrams of their
+/// sources, which narrows a lookup down to the units which could be similar enough before any is compared.
+#[derive(Default)]
+struct Store {
+    units: Vec<TranslationUnit>,
+    /// The units whose source has each trigram, by their index, with how many times it has it.
+    trigrams: HashMap<[char; 3], Vec<(usize, usize)>>,
+}
+
+impl Store {
+    fn new(units: Vec<TranslationUnit>) -> Store {
+        let mut store = Store::default();
+        for unit in units {
+            store.push(unit);
+        }
+        store
+    }
+
+    fn push(&mut self, unit: TranslationUnit) {
+        let index = self.units.len();
+        for (trigram, count) in trigrams(unit.source.trim()) {
+            self.trigrams.entry(trigram).or_default().push((index, count));
+        }
+        self.units.push(unit);
+    }
+}
+
+impl TranslationMemory {
+    /// A memory which is forgotten when it is dropped.
+    pub fn new() -> TranslationMemory {
+        TranslationMemory { path: None, store: Mutex::new(Store::default()) }
+    }
+
+    /// Open the memory kept at `path`, which is created when the first unit is added to it. Lines which
+    /// cannot be read as a unit are skipped, as are units replaced by a later line.
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<TranslationMemory> {
+        let path = path.into();
+        let mut units = Vec::new();
+        match File::open(&path) {
+            Ok(file) => for line in BufReader::new(file).lines() {
+                if let Some(unit) = Json::parse(line?.as_str()).ok().as_ref().and_then(unit_from_json) {
+                    units.retain(|known| !same_source(known, &unit));
+                    units.push(unit);
+                }
+            },
+            Err(ref why) if why.kind() == io::ErrorKind::NotFound => (),
+            Err(why) => return Err(why)
+        }
+        Ok(TranslationMemory { path: Some(path), store: Mutex::new(Store::new(units)) })
+    }
+
+    /// Where the memory is kept, if it is kept anywhere.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Every unit in the memory, in the order that they were added.
+    pub fn units(&self) -> Vec<TranslationUnit> {
+        self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).units.clone()
+    }
+
+    /// Add `unit` to the memory, replacing any unit with the same source text and languages.
+    pub fn add(&self, unit: TranslationUnit) -> io::Result<()> {
+        let mut store = self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if store.units.contains(&unit) {
+            return Ok(());
+        }
+        if !store.units.iter().any(|known| same_source(known, &unit)) {
+            if let Some(ref path) = self.path {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                file.write_all(format!("{}\n", unit_to_json(&unit)).as_bytes())?;
+            }
+            store.push(unit);
+            return Ok(());
+        }
+        let mut kept = store.units.iter().filter(|known| !same_source(known, &unit)).cloned().collect::<Vec<TranslationUnit>>();
+        kept.push(unit);
+        if let Some(ref path) = self.path {
+            // The file is rewritten without the unit replaced, so that it does not grow with every replacement,
+            // through a temporary file which no other process sharing the memory is writing to.
+            let temporary = temporary_path(path);
+            let written = File::create(&temporary).and_then(|mut file| {
+                let lines = kept.iter().map(|unit| format!("{}\n", unit_to_json(unit))).collect::<String>();
+                file.write_all(lines.as_bytes())?;
+                file.sync_all()
+            }).and_then(|_| fs::rename(&temporary, path));
+            if written.is_err() {
+                let _ = fs::remove_file(&temporary);
+            }
+            written?;
+        }
+        *store = Store::new(kept);
+        Ok(())
+    }
+
+    /// Find the unit whose source is most similar to `text`, translated from `source` (or any language, if it
+    /// is not known) into `target`, provided that it is at least `threshold` similar. A unit which is not the
+    /// same text must have the same numbers and placeholders, as its translation would carry the wrong ones.
+    pub fn lookup(&self, source: Option<&str>, target: &str, text: &str, threshold: f64) -> Option<MemoryMatch> {
+        let text = text.trim();
+        let length = text.chars().count();
+        let candidates = {
+            let store = self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // How many trigrams each unit shares with the text, counting a trigram no more often than either has it.
+            let mut shared: HashMap<usize, usize> = HashMap::new();
+            for (trigram, count) in trigrams(text) {
+                for &(index, known) in store.trigrams.get(&trigram).map_or(&[][..], Vec::as_slice) {
+                    *shared.entry(index).or_insert(0) += count.min(known);
+                }
+            }
+            // Unless the threshold is low enough for text to be similar while sharing no trigram at all, only the
+            // units which share one need to be looked at. Newer units are looked at first, as they are preferred
+            // to older ones which are just as similar, being likelier to be wanted.
+            let mut indices = if 3.0 * (1.0 - threshold) < 1.0 {
+                shared.keys().cloned().collect::<Vec<usize>>()
+            } else {
+                (0..store.units.len()).collect()
+            };
+            indices.sort_unstable_by(|a, b| b.cmp(a));
+            indices.into_iter().filter(|index| {
+                let unit = &store.units[*index];
+                let languages = same_language(unit.target_language.as_str(), target) && match (source, unit.source_language.as_ref()) {
+                    (Some(source), Some(known)) => same_language(known, source),
+                    _ => true
+                };
+                let candidate = unit.source.trim().chars().count();
+                let longest = length.max(candidate);
+                // Text whose length alone differs by more than the threshold allows cannot be similar enough, nor
+                // can text which shares too few trigrams, as each edit spoils no more than three of them.
+                let edits = ((1.0 - threshold) * longest as f64 + 1e-9).floor().max(0.0) as usize;
+                languages && longest != 0 && (length.min(candidate) as f64) >= threshold * longest as f64
+                    && shared.get(index).cloned().unwrap_or(0) + 3 * edits >= longest + 2
+            }).map(|index| store.units[index].clone()).collect::<Vec<TranslationUnit>>()
+        };
+
+        // The candidates are compared in full without holding the lock, so that other threads may look up theirs.
+        let mut best: Option<MemoryMatch> = None;
+        for unit in candidates {
+            let candidate = unit.source.trim();
+            let longest = length.max(candidate.chars().count());
+            let similarity = 1.0 - distance(text, candidate) as f64 / longest as f64;
+            if similarity < 1.0 && invariants(text) != invariants(candidate) {
+                continue;
+            }
+            if similarity >= threshold && best.as_ref().is_none_or(|best| similarity > best.similarity) {
+                best = Some(MemoryMatch { unit, similarity });
+            }
+        }
+        best
+    }
+
+    /// Add the units of a TMX document to the memory, returning how many were added.
+    pub fn import_tmx(&self, tmx: &str) -> Result<usize, String> {
+        let units = parse_tmx(tmx)?;
+        let count = units.len();
+        for unit in units {
+            self.add(unit).map_err(|why| why.to_string())?;
+        }
+        Ok(count)
+    }
+
+    /// Write the memory as a TMX 1.4 document.
+    pub fn to_tmx(&self) -> String {
+        let units = self.units();
+        let mut tmx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tmx version=\"1.4\">\n");
+        tmx.push_str("  <header creationtool=\"rust-google-translate\" creationtoolversion=\"");
+        tmx.push_str(env!("CARGO_PKG_VERSION"));
+        tmx.push_str("\" segtype=\"sentence\" o-tmf=\"rust-google-translate\" adminlang=\"en\" srclang=\"*all*\" datatype=\"plaintext\"/>\n");
+        tmx.push_str("  <body>\n");
+        for unit in units {
+            tmx.push_str("    <tu>\n");
+            // TMX has no way to say that the language of a segment is unknown, so `und` (undetermined) is used.
+            let source_language = unit.source_language.as_deref().unwrap_or("und");
+            for &(language, text) in [(source_language, unit.source.as_str()), (unit.target_language.as_str(), unit.target.as_str())].iter() {
+                tmx.push_str(format!("      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n", escape_xml(language), escape_xml(text)).as_str());
+            }
+            tmx.push_str("    </tu>\n");
+        }
+        tmx.push_str("  </body>\n</tmx>\n");
+        tmx
+    }
+}
+
+impl Default for TranslationMemory {
+    fn default() -> TranslationMemory { TranslationMemory::new() }
+}
+
+/// The numbers and placeholders in `text`, which a translation must keep as they are.
+fn invariants(text: &str) -> (Vec<&str>, Vec<String>) {
+    let numbers = text.split(|c: char| !c.is_ascii_digit()).filter(|number| !number.is_empty()).collect();
+    let mut placeholders = find_placeholders(text);
+    placeholders.sort();
+    (numbers, placeholders)
+}
+
+/// The trigrams of `text`, with how many times each appears. The text is padded at each end, so that text of
+/// `n` letters has `n + 2` trigrams, and its first and last letters are in as many as the rest.
+fn trigrams(text: &str) -> HashMap<[char; 3], usize> {
+    let padded = "\0\0".chars().chain(text.chars()).chain("\0\0".chars()).collect::<Vec<char>>();
+    let mut counts = HashMap::new();
+    for window in padded.windows(3) {
+        *counts.entry([window[0], window[1], window[2]]).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Whether a unit in the language `known` serves for text in the language `wanted`. Tools which write TMX name
+/// the region as well as the language, such as `de-DE`, so a language given without a region or script, such as
+/// `DE`, is served by any of its variants, as it is in `Glossary::translation`, but `ZH-TW` is not served by
+/// `zh-CN`.
+fn same_language(known: &str, wanted: &str) -> bool {
+    let primary = |language: &str| language.split('-').next().unwrap_or(language).to_string();
+    known.eq_ignore_ascii_case(wanted)
+        || ((!known.contains('-') || !wanted.contains('-')) && primary(known).eq_ignore_ascii_case(primary(wanted).as_str()))
+}
+
+fn same_source(a: &TranslationUnit, b: &TranslationUnit) -> bool {
+    a.source == b.source && a.target_language.eq_ignore_ascii_case(&b.target_language)
+        && a.source_language.as_deref().map(str::to_lowercase) == b.source_language.as_deref().map(str::to_lowercase)
+}
+
+fn unit_to_json(unit: &TranslationUnit) -> Json {
+    Json::Object(vec![
+        ("source_language".to_string(), unit.source_language.clone().into()),
+        ("target_language".to_string(), unit.target_language.as_str().into()),
+        ("source".to_string(),          unit.source.as_str().into()),
+        ("target".to_string(),          unit.target.as_str().into()),
+    ])
+}
+
+fn unit_from_json(json: &Json) -> Option<TranslationUnit> {
+    Some(TranslationUnit {
+        source_language: json.member("source_language").and_then(Json::as_str).map(String::from),
+        target_language: json.member("target_language").and_then(Json::as_str)?.to_string(),
+        source:          json.member("source").and_then(Json::as_str)?.to_string(),
+        target:          json.member("target").and_then(Json::as_str)?.to_string(),
+    })
+}
+
+/// Read the translation units of a TMX document. Each `<tu>` becomes one unit for every language besides its
+/// source language, which is the one named by its `srclang` attribute or the header's, or else its first.
+/// Inline markup within a segment, such as `<bpt>`, is dropped, keeping only the text.
+pub fn parse_tmx(tmx: &str) -> Result<Vec<TranslationUnit>, String> {
+    if !tmx.contains("<tmx") {
+        return Err("not a TMX document".to_string());
+    }
+    let header_source = tags(tmx, "header").next().and_then(|(header, _)| attribute(header, "srclang"));
+    let mut units = Vec::new();
+    for (tu, body) in tags(tmx, "tu") {
+        let source_language = attribute(tu, "srclang").or_else(|| header_source.clone()).filter(|language| language != "*all*");
+        let variants = tags(body, "tuv").filter_map(|(tuv, body)| {
+            let language = attribute(tuv, "xml:lang").or_else(|| attribute(tuv, "lang"))?;
+            let segment = tags(body, "seg").next()?.1;
+            Some((language, unescape_xml(strip_tags(segment).as_str())))
+        }).collect::<Vec<(String, String)>>();
+        let source = match source_language {
+            Some(ref language) => variants.iter().position(|(variant, _)| variant.eq_ignore_ascii_case(language)),
+            None               => if variants.is_empty() { None } else { Some(0) }
+        };
+        let source = match source {
+            Some(source) => source,
+            None         => continue
+        };
+        for (index, (language, text)) in variants.iter().enumerate() {
+            if index == source { continue }
+            let source_language = Some(variants[source].0.clone()).filter(|language| language != "und");
+            units.push(TranslationUnit {
+                source_language,
+                target_language: language.clone(),
+                source:          variants[source].1.clone(),
+                target:          text.clone(),
+            });
+        }
+    }
+    Ok(units)
+}
+
+/// The elements named `name` in `xml` which are not nested in one another, as the text of their opening tag
+/// and their content. An empty element such as `<header/>` has no content.
+fn tags<'a>(xml: &'a str, name: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+    let mut rest = xml;
+    ::std::iter::from_fn(move || loop {
+        let start = rest.find(format!("<{}", name).as_str())?;
+        let after = &rest[start + name.len() + 1..];
+        // A longer name which begins with this one, such as <tuv> when looking for <tu>, is not a match.
+        if !after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            rest = after;
+            continue;
+        }
+        let end = after.find('>')?;
+        let open = &after[..end];
+        if open.ends_with('/') {
+            rest = &after[end + 1..];
+            return Some((open, ""));
+        }
+        let content = &after[end + 1..];
+        let close = content.find(format!("</{}>", name).as_str()).unwrap_or(content.len());
+        rest = &content[(close + name.len() + 3).min(content.len())..];
+        return Some((open, &content[..close]));
+    })
+}
+
+/// The value of the attribute `name` in the text of an opening tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let preceded = rest[..index].ends_with(char::is_whitespace);
+        let after = rest[index + name.len()..].trim_start();
+        rest = &rest[index + name.len()..];
+        if !preceded || !after.starts_with('=') { continue }
+        let value = after[1..].trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' { return None }
+        let end = value[1..].find(quote)?;
+        return Some(unescape_xml(&value[1..end + 1]));
+    }
+    None
+}
+
+/// The text of a segment without its inline markup. The native codes within `<bpt>`, `<ept>`, `<it>`, `<ph>`
+/// and `<ut>`, such as the `<b>` of another format, are not text and are dropped along with them.
+fn strip_tags(xml: &str) -> String {
+    const CODES: [&str; 5] = ["bpt", "ept", "it", "ph", "ut"];
+    let mut text = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None      => return text
+        };
+        let tag = &rest[start + 1..end];
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        rest = &rest[end + 1..];
+        if CODES.contains(&name) && !tag.ends_with('/') {
+            let close = format!("</{}>", name);
+            rest = rest.find(close.as_str()).map_or("", |index| &rest[index + close.len()..]);
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None      => break
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp"  => Some('&'),
+            "lt"   => Some('<'),
+            "gt"   => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32),
+            _ if entity.starts_with('#')  => entity[1..].parse::<u32>().ok().and_then(::std::char::from_u32),
+            _ => None
+        };
+        match character {
+            Some(character) => { unescaped.push(character); rest = &rest[end + 1..]; },
+            None            => { unescaped.push('&'); rest = &rest[1..]; }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[test]
+fn test_translation_memory() {
+    let memory = TranslationMemory::new();
+    let unit = TranslationUnit {
+        source_language: Some("eo".to_string()),
+        target_language: "EN".to_string(),
+        source:          "Mi estas ne vin.".to_string(),
+        target:          "I am not you.".to_string(),
+    };
+    memory.add(unit.clone()).unwrap();
+    assert_eq!(memory.lookup(Some("EO"), "en", "Mi estas ne vin.", 1.0).map(|found| found.unit), Some(unit.clone()));
+    assert!(memory.lookup(None, "EN", "Mi estas ne vin!", 0.9).is_some_and(|found| found.similarity < 1.0));
+    assert_eq!(memory.lookup(None, "DE", "Mi estas ne vin.", 0.9), None);
+    assert_eq!(memory.lookup(None, "EN", "Vi estas ne min.", 0.9), None);
+
+    // Text which differs only in a number is not close enough, however similar it is.
+    let numbered = TranslationMemory::new();
+    numbered.add(TranslationUnit { target_language: "EN".to_string(), source: "Pagenda ene de 30 tagoj.".to_string(),
+        target: "Payable within 30 days.".to_string(), ..unit.clone() }).unwrap();
+    assert_eq!(numbered.lookup(None, "EN", "Pagenda ene de 60 tagoj.", 0.9), None);
+    assert!(numbered.lookup(None, "EN", "Pagenda ene de 30 tagoj!", 0.9).is_some());
+
+    // The trigram index passes over no unit which comparing every unit in full would have found.
+    let sources = ["La kato dormas.", "La kato dormis.", "La hundo dormas.", "Kato", "Katoj", "Ne.", "La kato dormas sur la tapiŝo."];
+    let indexed = TranslationMemory::new();
+    for source in sources.iter() {
+        indexed.add(TranslationUnit { source: source.to_string(), target: source.to_uppercase(), ..unit.clone() }).unwrap();
+    }
+    for text in ["La kato dormas!", "la kato dormas.", "Kato.", "Ne", "La katoj dormas sur la tapiŝo."].iter() {
+        for &threshold in [0.5, 0.7, 0.8, 0.9, 0.95, 1.0].iter() {
+            let similarity = |source: &&str| 1.0 - distance(text, source) as f64 / text.chars().count().max(source.chars().count()) as f64;
+            let expected = sources.iter().rev().filter(|source| similarity(source) >= threshold)
+                .fold(None, |best: Option<&&str>, source| match best {
+                    Some(best) if similarity(best) >= similarity(source) => Some(best),
+                    _ => Some(source)
+                });
+            assert_eq!(indexed.lookup(None, "EN", text, threshold).map(|found| found.unit.source), expected.map(|source| source.to_string()));
+        }
+    }
+
+    let tmx = memory.to_tmx();
+    assert!(tmx.contains("<tuv xml:lang=\"eo\"><seg>Mi estas ne vin.</seg></tuv>"));
+    assert_eq!(parse_tmx(tmx.as_str()), Ok(vec![unit.clone()]));
+
+    let tmx = r#"<tmx version="1.4"><header srclang="en-US" datatype="plaintext"/><body>
+        <tu tuid="1"><tuv xml:lang="en-US"><seg>Fish &amp; <bpt i="1">&lt;b&gt;</bpt>chips<ept i="1">&lt;/b&gt;</ept></seg></tuv>
+        <tuv xml:lang="fr-FR"><seg>Poisson-frites</seg></tuv><tuv lang="de-DE"><seg>Fish &#38; Chips</seg></tuv></tu>
+        </body></tmx>"#;
+    let units = parse_tmx(tmx).unwrap();
+    assert_eq!(units.len(), 2);
+    assert_eq!((units[0].source.as_str(), units[0].target.as_str()), ("Fish & chips", "Poisson-frites"));
+    assert_eq!((units[1].source_language.as_deref(), units[1].target_language.as_str()), (Some("en-US"), "de-DE"));
+    assert_eq!(units[1].target.as_str(), "Fish & Chips");
+
+    // Units imported with regions serve a language given without one.
+    let memory = TranslationMemory::new();
+    memory.import_tmx(tmx).unwrap();
+    assert_eq!(memory.lookup(Some("EN"), "DE", "Fish & chips", 1.0).map(|found| found.unit.target), Some("Fish & Chips".to_string()));
+    assert_eq!(memory.lookup(None, "fr-CA", "Fish & chips", 1.0), None);
+    assert!(same_language("zh", "ZH-TW") && !same_language("zh-CN", "ZH-TW"));
+
+    // Replacing a unit rewrites the file rather than adding to it.
+    let path = ::std::env::temp_dir().join(format!("rust-google-translate-memory-test-{}.jsonl", ::std::process::id()));
+    let memory = TranslationMemory::open(path.as_path()).unwrap();
+    memory.add(unit.clone()).unwrap();
+    memory.add(TranslationUnit { target: "I am not thee.".to_string(), ..unit.clone() }).unwrap();
+    memory.add(TranslationUnit { target_language: "DE".to_string(), ..unit.clone() }).unwrap();
+    assert_eq!(fs::read_to_string(path.as_path()).unwrap().lines().count(), 2);
+    assert_eq!(TranslationMemory::open(path.as_path()).unwrap().units(), memory.units());
+    let _ = fs::remove_file(path.as_path());
+}
@@ -0,0 +1,226 @@
+//! Parsing of the raw responses returned by the Google Translate endpoint.
+
+use json::Json;
+
+/// A single sentence of the translation, along with the source text that it was translated from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sentence {
+    pub translation: String,
+    pub source: String,
+}
+
+/// Other ways in which a segment of the source text could have been translated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alternative {
+    /// The segment of the source text that the candidates translate.
+    pub source: String,
+    /// The candidate translations, the first of which is normally the one that was used.
+    pub candidates: Vec<String>,
+}
+
+impl From<Sentence> for Json {
+    fn from(sentence: Sentence) -> Json {
+        Json::Object(vec![
+            ("translation".to_string(), sentence.translation.into()),
+            ("source".to_string(),      sentence.source.into()),
+        ])
+    }
+}
+
+impl From<Alternative> for Json {
+    fn from(alternative: Alternative) -> Json {
+        Json::Object(vec![
+            ("source".to_string(),     alternative.source.into()),
+            ("candidates".to_string(), alternative.candidates.into()),
+        ])
+    }
+}
+
+/// The parts of Google's response that are of interest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Response {
+    pub sentences: Vec<Sentence>,
+    /// The language that Google detected the input to be written in.
+    pub source_language: Option<String>,
+    /// How confident Google is in the detected language, from 0 to 1.
+    pub confidence: Option<f64>,
+    /// Alternative translations for segments of the source text, when they were requested.
+    pub alternatives: Vec<Alternative>,
+}
+
+/// The language that a piece of text was detected to be written in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Detection {
+    /// The code of the detected language, such as `eo`.
+    pub language: String,
+    /// How confident Google is in the detected language, from 0 to 1.
+    pub confidence: Option<f64>,
+}
+
+impl From<Detection> for Json {
+    fn from(detection: Detection) -> Json {
+        Json::Object(vec![
+            ("language".to_string(),   detection.language.into()),
+            ("confidence".to_string(), detection.confidence.into()),
+        ])
+    }
+}
+
+/// The dictionary entry for a word as one part of speech, such as its meanings as a noun.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DictionaryEntry {
+    /// The part of speech, such as `noun`, in the language that the word was translated into.
+    pub part_of_speech: String,
+    /// The form that the word is listed under, such as `run` for `running`, if Google reported one.
+    pub base_form: Option<String>,
+    /// The translations of the word, most common first.
+    pub translations: Vec<DictionaryTranslation>,
+}
+
+/// A translation of a word in a dictionary entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DictionaryTranslation {
+    pub translation: String,
+    /// The words which the translation is itself translated back into, which tell apart its meanings.
+    pub back_translations: Vec<String>,
+}
+
+/// Take the raw response from Google and parse it. The response is an array which contains the list of
+/// translated sentences first, the detected source language third, the alternative translations sixth,
+/// and the detection confidence seventh.
+pub fn parse_response(input: &str) -> Result<Response, String> {
+    let root = Json::parse(input)?;
+    let sentences = root.get(0).and_then(Json::as_array).ok_or_else(|| "missing list of sentences".to_string())?;
+
+    let mut response = Response::default();
+    for sentence in sentences {
+        // Other entries, such as romanization, may follow the sentences and have no translation.
+        if let Some(translation) = sentence.get(0).and_then(Json::as_str) {
+            response.sentences.push(Sentence {
+                translation: translation.to_string(),
+                source: sentence.get(1).and_then(Json::as_str).unwrap_or("").to_string()
+            });
+        }
+    }
+    response.source_language = root.get(2).and_then(Json::as_str).map(String::from);
+    response.confidence = root.get(6).and_then(Json::as_f64);
+
+    // Each alternative is of the form [source, _, [[candidate, score, ...], ...], ...].
+    for alternative in root.get(5).and_then(Json::as_array).unwrap_or(&[]) {
+        let candidates = alternative.get(2).and_then(Json::as_array).unwrap_or(&[]).iter()
+            .filter_map(|candidate| candidate.get(0).and_then(Json::as_str))
+            .map(String::from)
+            .collect::<Vec<String>>();
+        if let (Some(source), false) = (alternative.get(0).and_then(Json::as_str), candidates.is_empty()) {
+            response.alternatives.push(Alternative { source: source.to_string(), candidates });
+        }
+    }
+    Ok(response)
+}
+
+/// Parse a response to a request which asked for no translation, keeping only the detected language.
+pub fn parse_detection(input: &str) -> Result<Detection, String> {
+    let root = Json::parse(input)?;
+    let language = root.get(2).and_then(Json::as_str).ok_or_else(|| "missing detected language".to_string())?;
+    Ok(Detection { language: language.to_string(), confidence: root.get(6).and_then(Json::as_f64) })
+}
+
+/// Parse a response to a request for the dictionary entries of a word. The entries are second in the
+/// response, which holds `null` there if Google has no entry for the word.
+pub fn parse_dictionary(input: &str) -> Result<Vec<DictionaryEntry>, String> {
+    let root = Json::parse(input)?;
+    if root.as_array().is_none() {
+        return Err("response is not an array".to_string());
+    }
+    // Each entry is of the form [part of speech, [translation, ...], [[translation, [back translation, ...], _, score], ...], base form, _].
+    let mut entries = Vec::new();
+    for entry in root.get(1).and_then(Json::as_array).unwrap_or(&[]) {
+        let part_of_speech = match entry.get(0).and_then(Json::as_str) {
+            Some(part_of_speech) => part_of_speech.to_string(),
+            None                 => continue
+        };
+        let translations = entry.get(2).and_then(Json::as_array).unwrap_or(&[]).iter().filter_map(|translation| {
+            Some(DictionaryTranslation {
+                translation:       translation.get(0).and_then(Json::as_str)?.to_string(),
+                back_translations: translation.get(1).and_then(Json::as_array).unwrap_or(&[]).iter()
+                    .filter_map(Json::as_str).map(String::from).collect(),
+            })
+        }).collect::<Vec<DictionaryTranslation>>();
+        if !translations.is_empty() {
+            let base_form = entry.get(3).and_then(Json::as_str).map(String::from);
+            entries.push(DictionaryEntry { part_of_speech, base_form, translations });
+        }
+    }
+    Ok(entries)
+}
+
+/// Whether a response is the page that Google shows in place of a translation when it has judged the traffic
+/// from the network to be automated, asking for a CAPTCHA to be solved before serving it again.
+pub fn is_blocked(input: &str) -> bool {
+    const MARKERS: &'static [&'static str] = &[
+        "detected unusual traffic",
+        "id=\"captcha-form\"",
+        "google.com/sorry/",
+        "g-recaptcha",
+    ];
+    // A translation is always a JSON array, even if its text happens to contain one of the markers.
+    !input.trim_start().starts_with('[') && MARKERS.iter().any(|marker| input.contains(marker))
+}
+
+#[test]
+fn test_parse_message() {
+    const TEST: &'static str = "[[[\"I am not you. \",\"Mi estas ne vin.\",,,0],[\"You are not me.\",\"Vi estas ne min.\",,,0]],,\"eo\",,,,0.070792444,,[[\"eo\"],,[0.070792444],[\"eo\"]]]";
+    let response = parse_response(TEST).unwrap();
+    let output: String = response.sentences.iter().map(|sentence| sentence.translation.as_str()).collect();
+    assert_eq!(output.as_str(), "I am not you. You are not me.");
+    assert_eq!(response.sentences[1].source.as_str(), "Vi estas ne min.");
+    assert_eq!(response.source_language, Some("eo".to_string()));
+    assert_eq!(response.confidence, Some(0.070792444));
+
+    assert!(response.alternatives.is_empty());
+
+    const ALTERNATIVES: &'static str = r#"[[["Hello","Hola",null,null,1]],null,"es",null,null,[["Hola",null,[["Hello",1000,true,false],["Hi",1000,true,false]],[[0,4]],"Hola",0,0]],1,[],[["es"],null,[1],["es"]]]"#;
+    let response = parse_response(ALTERNATIVES).unwrap();
+    assert_eq!(response.confidence, Some(1.0));
+    assert_eq!(response.alternatives, vec![Alternative {
+        source: "Hola".to_string(),
+        candidates: vec!["Hello".to_string(), "Hi".to_string()]
+    }]);
+
+    assert!(parse_response("<html><body>Error 503</body></html>").is_err());
+    assert!(parse_response("[null]").is_err());
+}
+
+#[test]
+fn test_parse_detection() {
+    let detection = parse_detection(r#"[null,null,"eo",null,null,null,0.98828125,[],[["eo"],null,[0.98828125],["eo"]]]"#).unwrap();
+    assert_eq!(detection, Detection { language: "eo".to_string(), confidence: Some(0.98828125) });
+    assert_eq!(Json::from(detection).to_string(), r#"{"language":"eo","confidence":0.98828125}"#);
+    assert!(parse_detection("[null]").is_err());
+}
+
+#[test]
+fn test_parse_dictionary() {
+    const DICTIONARY: &'static str = r#"[null,[["interjection",["hello","hi"],[["hello",["saluton","saluto"],null,0.6],["hi",["saluton"],null,0.1]],"saluton",9],["noun",[],[],"saluton",1]],"eo"]"#;
+    assert_eq!(parse_dictionary(DICTIONARY).unwrap(), vec![DictionaryEntry {
+        part_of_speech: "interjection".to_string(),
+        base_form:      Some("saluton".to_string()),
+        translations:   vec![
+            DictionaryTranslation { translation: "hello".to_string(), back_translations: vec!["saluton".to_string(), "saluto".to_string()] },
+            DictionaryTranslation { translation: "hi".to_string(), back_translations: vec!["saluton".to_string()] },
+        ],
+    }]);
+    assert_eq!(parse_dictionary(r#"[null,null,"eo"]"#), Ok(vec![]));
+    assert!(parse_dictionary("<html></html>").is_err());
+}
+
+#[test]
+fn test_is_blocked() {
+    const SORRY: &'static str = concat!(
+        "<html><body><div>Our systems have detected unusual traffic from your computer network.</div>",
+        "<form id=\"captcha-form\" action=\"index\" method=\"post\"></form></body></html>"
+    );
+    assert!(is_blocked(SORRY));
+    assert!(!is_blocked("<html><body>Error 503</body></html>"));
+    assert!(!is_blocked(r#"[[["Our systems have detected unusual traffic","...",null,null,1]],null,"en"]"#));
+}
@@ -0,0 +1,110 @@
+//! A phrasebook of translations worth keeping, saved as tab-separated lines of the source language, the
+//! target language, the original text and its translation, so that it can be read by a spreadsheet.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// A phrase and its translation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phrase {
+    /// The language of the original, if it is known.
+    pub source: Option<String>,
+    pub target: String,
+    pub original: String,
+    pub translation: String,
+}
+
+/// A phrasebook file, which phrases are appended to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phrasebook {
+    path: PathBuf,
+}
+
+impl Phrasebook {
+    /// The phrasebook kept at `path`, which is created when the first phrase is added.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Phrasebook {
+        Phrasebook { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add `phrase` to the end of the phrasebook.
+    pub fn add(&self, phrase: &Phrase) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let fields = [phrase.source.as_deref().unwrap_or(""), phrase.target.as_str(), phrase.original.as_str(), phrase.translation.as_str()];
+        let line = fields.iter().map(|field| escape(field)).collect::<Vec<String>>().join("\t");
+        file.write_all(format!("{}\n", line).as_bytes())
+    }
+
+    /// Every phrase in the phrasebook, in the order they were added. Lines which are not phrases are skipped.
+    pub fn phrases(&self) -> io::Result<Vec<Phrase>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref why) if why.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(why) => return Err(why)
+        };
+        let mut phrases = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let fields = line.split('\t').map(unescape).collect::<Vec<String>>();
+            if let [ref source, ref target, ref original, ref translation] = fields[..] {
+                phrases.push(Phrase {
+                    source:      if source.is_empty() { None } else { Some(source.clone()) },
+                    target:      target.clone(),
+                    original:    original.clone(),
+                    translation: translation.clone(),
+                });
+            }
+        }
+        Ok(phrases)
+    }
+}
+
+/// Escape the tabs, line breaks and backslashes in a field, which would otherwise break up its line.
+fn escape(field: &str) -> String {
+    field.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut characters = field.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+        match characters.next() {
+            Some('t')       => unescaped.push('\t'),
+            Some('n')       => unescaped.push('\n'),
+            Some('r')       => unescaped.push('\r'),
+            Some(character) => unescaped.push(character),
+            None            => unescaped.push('\\')
+        }
+    }
+    unescaped
+}
+
+#[test]
+fn test_phrasebook() {
+    use std::env;
+
+    let path = env::temp_dir().join(format!("rust-google-translate-phrasebook-test-{}", ::std::process::id())).join("phrasebook.tsv");
+    let phrasebook = Phrasebook::new(path.as_path());
+    assert_eq!(phrasebook.phrases().unwrap(), vec![]);
+    let phrase = Phrase {
+        source:      Some("eo".to_string()),
+        target:      "EN".to_string(),
+        original:    "Mi estas ne vin.\tVi estas ne min.".to_string(),
+        translation: "I am not you.\nYou are not me. \\o/".to_string(),
+    };
+    phrasebook.add(&phrase).unwrap();
+    phrasebook.add(&Phrase { source: None, ..phrase.clone() }).unwrap();
+    assert_eq!(phrasebook.phrases().unwrap(), vec![phrase.clone(), Phrase { source: None, ..phrase }]);
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
@@ -0,0 +1,116 @@
+//! Recognition of the placeholders in format strings, such as `%s` and `{0}`, which Google translates,
+//! respaces or drops, leaving a user interface string which no longer formats.
+//!
+//! The placeholders found are masked in the same way as names, with `protect` before the text is sent and
+//! `restore` once the translation has arrived.
+
+/// Find the placeholders in `input`, longest first, in the form expected by `protect`. These are those of
+/// printf (`%s`, `%1$d`, `%-5.2f`, `%(name)s` and `%%`), of .NET, Python and Rust (`{0}`, `{name}`,
+/// `{0:.2f}` and `{}`), of templates such as Mustache, Handlebars and Jinja (`{{name}}` and
+/// `{{ user.name }}`), and of shell and JavaScript template literals (`${var}`).
+pub fn find_placeholders(input: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut index = 0;
+    while let Some(start) = input[index..].find(['%', '{', '$']).map(|start| index + start) {
+        let rest = &input[start..];
+        let length = if rest.starts_with("{{") {
+            template(rest)
+        } else if rest.starts_with("${") {
+            rest.find('}').filter(|&end| end > 2 && !rest[2..end].contains(['\n', '{'])).map(|end| end + 1)
+        } else if rest.starts_with('{') {
+            format_field(rest)
+        } else if rest.starts_with('%') {
+            printf(rest)
+        } else {
+            None
+        };
+        match length {
+            Some(length) => {
+                let placeholder = &input[start..start + length];
+                if !found.iter().any(|known| known == placeholder) { found.push(placeholder.to_string()); }
+                index = start + length;
+            },
+            None => index = start + 1
+        }
+    }
+    found.sort_by_key(|placeholder| ::std::cmp::Reverse(placeholder.len()));
+    found
+}
+
+/// The length of the template tag of the form `{{name}}` at the start of `input`, if there is one. Its
+/// contents may be spaced out, but not spread over several lines.
+fn template(input: &str) -> Option<usize> {
+    let end = input.find("}}")?;
+    let contents = input[2..end].trim();
+    let valid = !contents.is_empty() && !input[2..end].contains(['\n', '{', '}'])
+        && contents.chars().all(|c| c.is_alphanumeric() || " _.#/^&>!-".contains(c));
+    if valid { Some(end + 2) } else { None }
+}
+
+/// The length of the format field of the form `{0}`, `{name}` or `{0:.2f}` at the start of `input`, if there
+/// is one. Its name is a number or an identifier, which may be followed by a format specification, but not by
+/// spaces, so that braces around words are left alone.
+fn format_field(input: &str) -> Option<usize> {
+    let end = input.find('}')?;
+    let contents = &input[1..end];
+    let (name, specification) = match contents.find([':', ',']) {
+        Some(colon) => (&contents[..colon], &contents[colon + 1..]),
+        None        => (contents, "")
+    };
+    let identifier = name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        && !name.starts_with('.') && !name.ends_with('.');
+    let specification = specification.chars().all(|c| c.is_ascii_alphanumeric() || "<>^=+-#0.,%_ ".contains(c));
+    if identifier && specification && !contents.contains('{') { Some(end + 1) } else { None }
+}
+
+/// The length of the printf conversion specification at the start of `input`, such as `%s`, `%1$d`,
+/// `%-5.2f` or `%(name)s`, if there is one. A conversion which runs into a word, such as the `%o` of
+/// `50%off`, is taken to be a percentage sign instead.
+fn printf(input: &str) -> Option<usize> {
+    let bytes = input.as_bytes();
+    let mut index = 1;
+    if bytes.get(index) == Some(&b'%') {
+        return Some(2);
+    }
+    if bytes.get(index) == Some(&b'(') {
+        let close = input.find(')')?;
+        if close == 2 || !input[2..close].chars().all(|c| c.is_alphanumeric() || c == '_') { return None }
+        index = close + 1;
+    } else {
+        // An argument position, such as the 1$ of %1$s.
+        let digits = input[1..].find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len() - 1);
+        if digits > 0 && bytes.get(1 + digits) == Some(&b'$') { index = 2 + digits; }
+    }
+    while bytes.get(index).is_some_and(|byte| b"-+0#'".contains(byte)) { index += 1; }
+    while bytes.get(index).is_some_and(|byte| byte.is_ascii_digit() || *byte == b'*') { index += 1; }
+    if bytes.get(index) == Some(&b'.') {
+        index += 1;
+        while bytes.get(index).is_some_and(|byte| byte.is_ascii_digit() || *byte == b'*') { index += 1; }
+    }
+    for modifier in ["hh", "ll", "h", "l", "L", "z", "j", "t", "q"].iter() {
+        if input[index..].starts_with(modifier) { index += modifier.len(); break }
+    }
+    if !bytes.get(index).is_some_and(|byte| b"diouxXeEfFgGaAcspn@".contains(byte)) {
+        return None;
+    }
+    let runs_on = input[index + 1..].chars().next().is_some_and(char::is_alphabetic);
+    if runs_on { None } else { Some(index + 1) }
+}
+
+#[test]
+fn test_find_placeholders() {
+    assert_eq!(find_placeholders("Hello %s, you have %d new messages (%.1f%% full)"), vec!["%.1f", "%s", "%d", "%%"]);
+    assert_eq!(find_placeholders("%1$s sent %2$-5d files to %(name)s"), vec!["%(name)s", "%2$-5d", "%1$s"]);
+    assert_eq!(find_placeholders("Deleted {0} of {1:N0} files in {folder.name}, {}"), vec!["{folder.name}", "{1:N0}", "{0}", "{}"]);
+    assert_eq!(find_placeholders("Welcome back, {{name}}! You have {{ count }} items."), vec!["{{ count }}", "{{name}}"]);
+    assert_eq!(find_placeholders("Saved to ${HOME}/backups at ${time}"), vec!["${HOME}", "${time}"]);
+    assert!(find_placeholders("Save 50% off {this week only} for $5 or 50%off").is_empty());
+
+    use entity::{protect, restore};
+    let input = "Hello %s, you have {count} items";
+    let placeholders = find_placeholders(input);
+    let (protected, masks) = protect(input, &placeholders);
+    assert_eq!(protected.as_str(), "Hello [1], you have [0] items");
+    assert_eq!(masks, placeholders);
+    assert_eq!(restore("Hola [ 1 ], tienes [0] artículos", &placeholders).0.as_str(), "Hola %s, tienes {count} artículos");
+}
@@ -0,0 +1,218 @@
+//! The plural rules of gettext, which say how many plural forms a language has and which of them a number
+//! takes, so that each `msgstr[n]` of a PO entry can be filled in with the form it is for.
+//!
+//! A rule is written as in the `Plural-Forms` header of a PO file, such as
+//! `nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);`,
+//! whose expression is in the subset of C which gettext understands.
+
+/// The rules of the languages whose plurals differ from those of English, by the code of the language
+/// without its region or script.
+const RULES: &'static [(&'static [&'static str], &'static str)] = &[
+    (&["hmn", "id", "ja", "jv", "jw", "km", "ko", "lo", "ms", "my", "su", "th", "vi", "zh"], "nplurals=1; plural=0;"),
+    (&["am", "fr", "hy", "ln", "mg", "ti", "tl"], "nplurals=2; plural=(n > 1);"),
+    (&["is"], "nplurals=2; plural=(n%10!=1 || n%100==11);"),
+    (&["mk"], "nplurals=2; plural=(n%10==1 && n%100!=11) ? 0 : 1;"),
+    (&["be", "bs", "hr", "ru", "sr", "uk"],
+        "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);"),
+    (&["cs", "sk"], "nplurals=3; plural=(n==1) ? 0 : (n>=2 && n<=4) ? 1 : 2;"),
+    (&["pl"], "nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);"),
+    (&["lt"], "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);"),
+    (&["lv"], "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2);"),
+    (&["ro"], "nplurals=3; plural=(n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2);"),
+    (&["sl"], "nplurals=4; plural=(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3);"),
+    (&["cy"], "nplurals=4; plural=(n==1) ? 0 : (n==2) ? 1 : (n != 8 && n != 11) ? 2 : 3;"),
+    (&["gd"], "nplurals=4; plural=(n==1 || n==11) ? 0 : (n==2 || n==12) ? 1 : (n > 2 && n < 20) ? 2 : 3;"),
+    (&["mt"], "nplurals=4; plural=(n==1 ? 0 : n==0 || (n%100>1 && n%100<11) ? 1 : (n%100>10 && n%100<20) ? 2 : 3);"),
+    (&["ga"], "nplurals=5; plural=(n==1 ? 0 : n==2 ? 1 : n<7 ? 2 : n<11 ? 3 : 4);"),
+    (&["ar"], "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5);"),
+];
+
+/// The rule of English and of the languages not listed, which have a singular and a plural.
+const DEFAULT_RULE: &'static str = "nplurals=2; plural=(n != 1);";
+
+/// The largest number tried when looking for a number which takes each form.
+const MAX_SAMPLE: u64 = 1000;
+
+/// How many plural forms a language has, and which of them each number takes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PluralForms {
+    count: usize,
+    expression: Expression,
+}
+
+impl PluralForms {
+    /// Parse the value of a `Plural-Forms` header, returning `None` if it is not in the form
+    /// `nplurals=N; plural=EXPRESSION;`.
+    pub fn parse(header: &str) -> Option<PluralForms> {
+        let mut count = None;
+        let mut expression = None;
+        for part in header.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=')?;
+            match name.trim() {
+                "nplurals" => count = value.trim().parse::<usize>().ok().filter(|&count| count > 0),
+                "plural"   => expression = Parser::parse(value),
+                _ => ()
+            }
+        }
+        Some(PluralForms { count: count?, expression: expression? })
+    }
+
+    /// The rule of `language`, a code such as `RU` or `pt-BR`.
+    pub fn for_language(language: &str) -> PluralForms {
+        let base = language.split(['-', '_']).next().unwrap_or("").to_lowercase();
+        let rule = RULES.iter().find(|&&(languages, _)| languages.contains(&base.as_str())).map_or(DEFAULT_RULE, |&(_, rule)| rule);
+        PluralForms::parse(rule).expect("the plural rules of the languages are valid")
+    }
+
+    /// How many plural forms there are.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The index of the form which `n` takes.
+    pub fn form(&self, n: u64) -> usize {
+        self.expression.evaluate(n) as usize
+    }
+
+    /// A number which takes each form in turn, the smallest but for 0, which is only used for a form which no
+    /// other number takes, or `None` for a form which no number takes.
+    pub fn samples(&self) -> Vec<Option<u64>> {
+        (0..self.count).map(|form| {
+            (1..=MAX_SAMPLE).chain(Some(0)).find(|&n| self.form(n) == form)
+        }).collect()
+    }
+}
+
+/// An expression of a plural rule.
+#[derive(Clone, Debug, PartialEq)]
+enum Expression {
+    N,
+    Number(u64),
+    Not(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Or, And, Equal, NotEqual, Less, LessOrEqual, Greater, GreaterOrEqual, Add, Subtract, Multiply, Divide, Remainder
+}
+
+impl Expression {
+    fn evaluate(&self, n: u64) -> u64 {
+        match *self {
+            Expression::N => n,
+            Expression::Number(number) => number,
+            Expression::Not(ref operand) => (operand.evaluate(n) == 0) as u64,
+            Expression::Conditional(ref condition, ref then, ref otherwise) => {
+                if condition.evaluate(n) != 0 { then.evaluate(n) } else { otherwise.evaluate(n) }
+            },
+            Expression::Binary(operator, ref left, ref right) => {
+                let (left, right) = (left.evaluate(n), right.evaluate(n));
+                match operator {
+                    Operator::Or             => (left != 0 || right != 0) as u64,
+                    Operator::And            => (left != 0 && right != 0) as u64,
+                    Operator::Equal          => (left == right) as u64,
+                    Operator::NotEqual       => (left != right) as u64,
+                    Operator::Less           => (left < right) as u64,
+                    Operator::LessOrEqual    => (left <= right) as u64,
+                    Operator::Greater        => (left > right) as u64,
+                    Operator::GreaterOrEqual => (left >= right) as u64,
+                    Operator::Add            => left.wrapping_add(right),
+                    Operator::Subtract       => left.wrapping_sub(right),
+                    Operator::Multiply       => left.wrapping_mul(right),
+                    Operator::Divide         => left.checked_div(right).unwrap_or(0),
+                    Operator::Remainder      => left.checked_rem(right).unwrap_or(0)
+                }
+            }
+        }
+    }
+}
+
+/// A recursive descent parser of the expressions of plural rules, from the loosest binding operator, the
+/// conditional, to the tightest.
+struct Parser<'a> {
+    input: &'a str,
+}
+
+/// The binary operators at each level of precedence, loosest first.
+const LEVELS: &'static [&'static [(&'static str, Operator)]] = &[
+    &[("||", Operator::Or)],
+    &[("&&", Operator::And)],
+    &[("==", Operator::Equal), ("!=", Operator::NotEqual)],
+    &[("<=", Operator::LessOrEqual), (">=", Operator::GreaterOrEqual), ("<", Operator::Less), (">", Operator::Greater)],
+    &[("+", Operator::Add), ("-", Operator::Subtract)],
+    &[("*", Operator::Multiply), ("/", Operator::Divide), ("%", Operator::Remainder)],
+];
+
+impl<'a> Parser<'a> {
+    fn parse(input: &str) -> Option<Expression> {
+        let mut parser = Parser { input };
+        let expression = parser.conditional()?;
+        if parser.input.trim().is_empty() { Some(expression) } else { None }
+    }
+
+    /// Consume `token` if the input begins with it, after any whitespace.
+    fn eat(&mut self, token: &str) -> bool {
+        self.input = self.input.trim_start();
+        match self.input.strip_prefix(token) {
+            Some(rest) => { self.input = rest; true },
+            None => false
+        }
+    }
+
+    fn conditional(&mut self) -> Option<Expression> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") { return Some(condition) }
+        let then = self.conditional()?;
+        if !self.eat(":") { return None }
+        let otherwise = self.conditional()?;
+        Some(Expression::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)))
+    }
+
+    fn binary(&mut self, level: usize) -> Option<Expression> {
+        if level == LEVELS.len() { return self.unary() }
+        let mut left = self.binary(level + 1)?;
+        while let Some(&(_, operator)) = LEVELS[level].iter().find(|&&(token, _)| self.eat(token)) {
+            let right = self.binary(level + 1)?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Expression> {
+        if self.eat("!") {
+            return self.unary().map(|operand| Expression::Not(Box::new(operand)));
+        }
+        if self.eat("(") {
+            let expression = self.conditional()?;
+            return if self.eat(")") { Some(expression) } else { None };
+        }
+        if self.eat("n") {
+            return Some(Expression::N);
+        }
+        let digits = self.input.find(|c: char| !c.is_ascii_digit()).unwrap_or(self.input.len());
+        let number = self.input[..digits].parse::<u64>().ok()?;
+        self.input = &self.input[digits..];
+        Some(Expression::Number(number))
+    }
+}
+
+#[test]
+fn test_plural_forms() {
+    let russian = PluralForms::for_language("RU");
+    assert_eq!(russian.count(), 3);
+    assert_eq!([1, 2, 5, 11, 21, 22, 25, 111].iter().map(|&n| russian.form(n)).collect::<Vec<usize>>(), vec![0, 1, 2, 2, 0, 1, 2, 2]);
+    assert_eq!(russian.samples(), vec![Some(1), Some(2), Some(5)]);
+    assert_eq!(PluralForms::for_language("ar").samples(), vec![Some(0), Some(1), Some(2), Some(3), Some(11), Some(100)]);
+    assert_eq!(PluralForms::for_language("lv").samples(), vec![Some(1), Some(2), Some(0)]);
+    assert_eq!(PluralForms::for_language("zh-TW").samples(), vec![Some(1)]);
+    assert_eq!(PluralForms::for_language("eo").samples(), vec![Some(1), Some(2)]);
+    assert_eq!(PluralForms::for_language("fr").form(0), 0);
+    for &(_, rule) in RULES {
+        assert!(PluralForms::parse(rule).is_some(), "{}", rule);
+    }
+    assert_eq!(PluralForms::parse("nplurals=2; plural=n>1 ? 1 : 2;").unwrap().samples(), vec![None, Some(2)]);
+    assert!(PluralForms::parse("nplurals=2; plural=(n != 1;").is_none());
+    assert!(PluralForms::parse("nplurals=0; plural=0;").is_none());
+}
@@ -0,0 +1,301 @@
+//! Translation of gettext PO files, in which the entries which have not been translated yet are filled in with
+//! the translation of their message, and everything else, such as comments, references, flags, contexts and the
+//! entries which have been translated already, is kept exactly as it was.
+//!
+//! An entry filled in by Google is flagged `fuzzy`, as `msgmerge` flags its guesses, so that a translator looks
+//! over it before it is used. The header, whose `msgid` is empty, and obsolete entries, which are commented out
+//! with `#~`, are never filled in.
+//!
+//! An entry with plural forms is given one `msgstr[n]` for each plural form of the target language, following the
+//! `Plural-Forms` header if the file has one. Each form is translated on its own, with the number which its
+//! message counts replaced by a number which takes that form, so that Google inflects the words around it.
+
+use placeholder::find_placeholders;
+use plural::PluralForms;
+
+/// Fill in the empty `msgstr` of each entry of the PO file `input` with `translate`, which is given the text of
+/// its `msgid`, without the quotes and escapes of the file and without the whitespace around it, along with the
+/// offset in the input of the line on which the `msgid` begins. It returns the translation, or `None` if there
+/// is none, in which case the entry is left empty. An entry with plural forms is filled in with a form for each
+/// of the plural forms of `language`, or of the `Plural-Forms` header of the file if it has one: the
+/// translation of `msgid` for a form which 1 takes, and of `msgid_plural` for the others, each with the number
+/// which it counts replaced by one which takes the form while it is translated.
+pub fn translate_po<F: FnMut(&str, usize) -> Option<String>>(input: &str, language: &str, mut translate: F) -> String {
+    let plurals = header_plural_forms(input).unwrap_or_else(|| PluralForms::for_language(language));
+    let mut output = String::with_capacity(input.len() * 2);
+    let mut entry: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0;
+    // Whether the entry has reached its msgstr, after which a comment, msgctxt or msgid begins the next entry even
+    // without a blank line between them.
+    let mut translated = false;
+    for line in input.split_inclusive('\n') {
+        let content = line.trim_start();
+        if line.trim().is_empty() {
+            output.push_str(fill(&entry, &plurals, &mut translate).as_str());
+            output.push_str(line);
+            entry.clear();
+            translated = false;
+            offset += line.len();
+            continue;
+        }
+        if translated && (content.starts_with('#') || content.starts_with("msgctxt") || content.starts_with("msgid")) {
+            output.push_str(fill(&entry, &plurals, &mut translate).as_str());
+            entry.clear();
+            translated = false;
+        }
+        if content.starts_with("msgstr") {
+            translated = true;
+        }
+        entry.push((offset, line));
+        offset += line.len();
+    }
+    output.push_str(fill(&entry, &plurals, &mut translate).as_str());
+    output
+}
+
+/// A keyword of an entry, such as `msgid` or `msgstr[1]`, with its string, which may be continued over the lines
+/// after it.
+struct Field<'a> {
+    keyword: &'a str,
+    value: String,
+    /// The lines of the entry which the field is written on.
+    lines: (usize, usize),
+}
+
+/// The plural forms given by the `Plural-Forms` header of the PO file `input`, if it has a valid one.
+fn header_plural_forms(input: &str) -> Option<PluralForms> {
+    let line = input.lines().map(str::trim).find(|line| line.starts_with("\"Plural-Forms:"))?;
+    PluralForms::parse(unescape(line)["Plural-Forms:".len()..].trim())
+}
+
+/// The entry written on `lines`, with its `msgstr` filled in if it was empty and could be translated.
+fn fill<F: FnMut(&str, usize) -> Option<String>>(lines: &[(usize, &str)], plurals: &PluralForms, translate: &mut F) -> String {
+    let verbatim = || lines.iter().map(|&(_, line)| line).collect::<String>();
+    let fields = parse_fields(lines);
+    let field = |keyword: &str| fields.iter().find(|field| field.keyword == keyword);
+    let msgid = match field("msgid") {
+        Some(msgid) if !msgid.value.is_empty() => msgid,
+        _ => return verbatim()
+    };
+    let msgstrs = fields.iter().filter(|field| field.keyword.starts_with("msgstr")).collect::<Vec<&Field>>();
+    if msgstrs.is_empty() || msgstrs.iter().any(|msgstr| !msgstr.value.is_empty()) {
+        return verbatim();
+    }
+
+    // The messages already translated, as the same message may be asked for by several plural forms.
+    let mut translated: Vec<(String, Option<String>)> = Vec::new();
+    let mut translate_message = |message: &str| {
+        let start = message.len() - message.trim_start().len();
+        let end = message.trim_end().len();
+        if !message[start..end].chars().any(char::is_alphabetic) {
+            return Some(message.to_string());
+        }
+        if let Some(&(_, ref translation)) = translated.iter().find(|&&(ref known, _)| known == message) {
+            return translation.clone();
+        }
+        let translation = translate(&message[start..end], lines[msgid.lines.0].0).map(|translation| {
+            format!("{}{}{}", &message[..start], translation.trim(), &message[end..])
+        });
+        translated.push((message.to_string(), translation.clone()));
+        translation
+    };
+    let forms = match field("msgid_plural") {
+        Some(plural) => plurals.samples().into_iter().map(|sample| {
+            let message = if sample == Some(1) { msgid.value.as_str() } else { plural.value.as_str() };
+            match (sample, count_placeholder(message)) {
+                (Some(sample), Some(placeholder)) => {
+                    let number = sample.to_string();
+                    translate_message(message.replacen(placeholder.as_str(), number.as_str(), 1).as_str())
+                        .and_then(|translation| {
+                            find_number(translation.as_str(), number.as_str()).map(|start| {
+                                format!("{}{}{}", &translation[..start], placeholder, &translation[start + number.len()..])
+                            })
+                        })
+                        .or_else(|| translate_message(message))
+                },
+                _ => translate_message(message)
+            }
+        }).collect::<Option<Vec<String>>>(),
+        None => translate_message(msgid.value.as_str()).map(|translation| vec![translation])
+    };
+    let forms = match forms {
+        Some(forms) => forms,
+        None => return verbatim()
+    };
+
+    let ending = if lines[0].1.ends_with("\r\n") { "\r\n" } else { "\n" };
+    // The flag goes among the other flags, or if there are none, after the comments which come before flags.
+    let flags = lines.iter().position(|&(_, line)| line.starts_with("#,"));
+    let before = lines.iter().position(|&(_, line)| !line.starts_with('#') || line.starts_with("#|") || line.starts_with("#~"))
+        .unwrap_or(lines.len());
+    let mut output = String::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].1;
+        if let Some(msgstr) = msgstrs.iter().find(|msgstr| msgstr.lines.0 == index) {
+            // The forms are written where the first msgstr was, and the others are dropped, as the language may
+            // have more or fewer forms than the entry had.
+            if index == msgstrs[0].lines.0 {
+                if msgstr.keyword == "msgstr" {
+                    output.push_str(render(msgstr.keyword, forms[0].as_str(), ending).as_str());
+                } else {
+                    for (form, translation) in forms.iter().enumerate() {
+                        output.push_str(render(format!("msgstr[{}]", form).as_str(), translation.as_str(), ending).as_str());
+                    }
+                }
+            }
+            index = msgstr.lines.1;
+            continue;
+        }
+        if flags.is_none() && index == before {
+            output.push_str(format!("#, fuzzy{}", ending).as_str());
+        }
+        if flags == Some(index) && !line[2..].split(',').any(|flag| flag.trim() == "fuzzy") {
+            let content = line.trim_end_matches(['\n', '\r']);
+            output.push_str(format!("{}, fuzzy{}", content, &line[content.len()..]).as_str());
+        } else {
+            output.push_str(line);
+        }
+        index += 1;
+    }
+    output
+}
+
+/// The placeholder of `message` which stands for the number that it counts: the first which formats an integer,
+/// such as `%d` or `%1$lu`, or failing that the first field, such as `{0}` or `{count}`.
+fn count_placeholder(message: &str) -> Option<String> {
+    let mut placeholders = find_placeholders(message).into_iter()
+        .filter_map(|placeholder| message.find(placeholder.as_str()).map(|start| (start, placeholder)))
+        .collect::<Vec<(usize, String)>>();
+    placeholders.sort();
+    let integer = |placeholder: &str| placeholder.starts_with('%') && placeholder.ends_with(['d', 'i', 'u']);
+    let field = |placeholder: &str| placeholder.starts_with(['{', '$']);
+    placeholders.iter().find(|&&(_, ref placeholder)| integer(placeholder))
+        .or_else(|| placeholders.iter().find(|&&(_, ref placeholder)| field(placeholder)))
+        .map(|&(_, ref placeholder)| placeholder.clone())
+}
+
+/// The offset of the first occurrence of the digits `number` in `text` which is not part of a longer number.
+fn find_number(text: &str, number: &str) -> Option<usize> {
+    text.match_indices(number).map(|(start, _)| start).find(|&start| {
+        !text[..start].ends_with(|c: char| c.is_ascii_digit()) && !text[start + number.len()..].starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// The keywords of the entry written on `lines` and their strings, unescaped.
+fn parse_fields<'a>(lines: &[(usize, &'a str)]) -> Vec<Field<'a>> {
+    let mut fields: Vec<Field> = Vec::new();
+    for (index, &(_, line)) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.starts_with('"') {
+            if let Some(field) = fields.last_mut().filter(|field| field.lines.1 == index) {
+                field.value.push_str(unescape(line).as_str());
+                field.lines.1 = index + 1;
+            }
+        } else if line.starts_with("msg") {
+            let keyword = line.split_whitespace().next().unwrap_or("");
+            let value = unescape(line[keyword.len()..].trim());
+            fields.push(Field { keyword, value, lines: (index, index + 1) });
+        }
+    }
+    fields
+}
+
+/// The text of the quoted string `quoted`, with its escapes undone.
+fn unescape(quoted: &str) -> String {
+    let inner = quoted.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')).unwrap_or("");
+    let mut text = String::with_capacity(inner.len());
+    let mut characters = inner.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            text.push(character);
+            continue;
+        }
+        match characters.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(other) => text.push(other),
+            None => text.push('\\')
+        }
+    }
+    text
+}
+
+/// Write `keyword` with the quoted string `text`. Text of several lines is written a line to a string after an
+/// empty one, as `xgettext` writes it.
+fn render(keyword: &str, text: &str, ending: &str) -> String {
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t").replace('\r', "\\r");
+    if !text.trim_end_matches('\n').contains('\n') {
+        return format!("{} \"{}\"{}", keyword, escape(text), ending);
+    }
+    let mut rendered = format!("{} \"\"{}", keyword, ending);
+    for line in text.split_inclusive('\n') {
+        rendered.push_str(format!("\"{}\"{}", escape(line), ending).as_str());
+    }
+    rendered
+}
+
+#[test]
+fn test_translate_po() {
+    let po = "# Esperanto translations.\nmsgid \"\"\nmsgstr \"\"\n\"Language: eo\\n\"\n\n\
+        #. A greeting.\n#: src/main.rs:10\nmsgid \"Hello, \\\"%s\\\"!\"\nmsgstr \"\"\n\n\
+        #: src/main.rs:20\n#, c-format\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n\n\
+        msgctxt \"menu\"\nmsgid \"\"\n\"Open\\n\"\n\"Close\\n\"\nmsgstr \"\"\n\n\
+        msgid \"Yes\"\nmsgstr \"Jes\"\n\n\
+        #~ msgid \"Old\"\n#~ msgstr \"\"\n\n\
+        msgid \"Broken\"\nmsgstr \"\"\n\n\
+        msgid \"a\"\nmsgstr \"\"\n#: b.c:1\nmsgid \"b\"\nmsgstr \"\"\n";
+    let mut requests = Vec::new();
+    let output = translate_po(po, "eo", |text, offset| {
+        requests.push((text.to_string(), offset));
+        match text {
+            "Hello, \"%s\"!" => Some("Saluton, \"%s\"!".to_string()),
+            "1 file"         => Some("1 dosiero".to_string()),
+            "2 files"        => Some("2 dosieroj".to_string()),
+            "Open\nClose"    => Some("Malfermi\nFermi".to_string()),
+            "a" | "b"        => Some(text.to_uppercase()),
+            _ => None
+        }
+    });
+    assert_eq!(requests.iter().map(|(text, _)| text.as_str()).collect::<Vec<&str>>(),
+        vec!["Hello, \"%s\"!", "1 file", "2 files", "Open\nClose", "Broken", "a", "b"]);
+    assert_eq!(requests[0].1, 96);
+    assert_eq!(output, "# Esperanto translations.\nmsgid \"\"\nmsgstr \"\"\n\"Language: eo\\n\"\n\n\
+        #. A greeting.\n#: src/main.rs:10\n#, fuzzy\nmsgid \"Hello, \\\"%s\\\"!\"\nmsgstr \"Saluton, \\\"%s\\\"!\"\n\n\
+        #: src/main.rs:20\n#, c-format, fuzzy\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\nmsgstr[0] \"%d dosiero\"\nmsgstr[1] \"%d dosieroj\"\n\n\
+        #, fuzzy\nmsgctxt \"menu\"\nmsgid \"\"\n\"Open\\n\"\n\"Close\\n\"\nmsgstr \"\"\n\"Malfermi\\n\"\n\"Fermi\\n\"\n\n\
+        msgid \"Yes\"\nmsgstr \"Jes\"\n\n\
+        #~ msgid \"Old\"\n#~ msgstr \"\"\n\n\
+        msgid \"Broken\"\nmsgstr \"\"\n\n\
+        #, fuzzy\nmsgid \"a\"\nmsgstr \"A\"\n#: b.c:1\n#, fuzzy\nmsgid \"b\"\nmsgstr \"B\"\n");
+}
+
+#[test]
+fn test_translate_po_plural_forms() {
+    let entry = "msgid \"%d file\"\nmsgid_plural \"%d files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n";
+    let mut requests = Vec::new();
+    let output = translate_po(entry, "ru", |text, _| {
+        requests.push(text.to_string());
+        match text {
+            "1 file"   => Some("1 файл".to_string()),
+            "2 files"  => Some("2 файла".to_string()),
+            "5 files"  => Some("пять файлов".to_string()),
+            "%d files" => Some("%d файлов".to_string()),
+            _ => None
+        }
+    });
+    assert_eq!(requests, vec!["1 file", "2 files", "5 files", "%d files"]);
+    assert_eq!(output, "#, fuzzy\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\n\
+        msgstr[0] \"%d файл\"\nmsgstr[1] \"%d файла\"\nmsgstr[2] \"%d файлов\"\n");
+
+    let header = "msgid \"\"\nmsgstr \"\"\n\"Plural-Forms: nplurals=1; plural=0;\\n\"\n\n";
+    let output = translate_po(format!("{}{}", header, entry).as_str(), "ru", |text, _| match text {
+        "1 file" => Some("1 ファイル".to_string()),
+        _ => None
+    });
+    assert_eq!(output, format!("{}#, fuzzy\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\nmsgstr[0] \"%d ファイル\"\n", header));
+    assert_eq!(count_placeholder("%s has %1$lu files"), Some("%1$lu".to_string()));
+    assert_eq!(count_placeholder("{name} has {count} files"), Some("{name}".to_string()));
+    assert_eq!(find_number("12 of 2", "2"), Some(6));
+}
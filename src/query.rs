@@ -0,0 +1,30 @@
+//! Encoding of user-supplied text for use within the request URL.
+
+/// Percent-encode `input` for use as the value of a query parameter. Every byte of the UTF-8 encoding is
+/// escaped, apart from the unreserved characters of RFC 3986.
+pub fn percent_encode(input: &str) -> String {
+    const HEX: &'static [u8; 16] = b"0123456789ABCDEF";
+    let mut output = String::with_capacity(input.len());
+    for &byte in input.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => output.push(byte as char),
+            _ => {
+                output.push('%');
+                output.push(HEX[(byte >> 4) as usize] as char);
+                output.push(HEX[(byte & 0xF) as usize] as char);
+            }
+        }
+    }
+    output
+}
+
+#[test]
+fn test_percent_encode() {
+    assert_eq!(percent_encode("Mi estas ne vin."), "Mi%20estas%20ne%20vin.");
+    assert_eq!(percent_encode("Tom & Jerry #1: 100% + more?"), "Tom%20%26%20Jerry%20%231%3A%20100%25%20%2B%20more%3F");
+    assert_eq!(percent_encode("a=b/c"), "a%3Db%2Fc");
+    assert_eq!(percent_encode("我不是你"), "%E6%88%91%E4%B8%8D%E6%98%AF%E4%BD%A0");
+    assert_eq!(percent_encode("café"), "caf%C3%A9");
+    assert_eq!(percent_encode("👋🏽"), "%F0%9F%91%8B%F0%9F%8F%BD");
+    assert_eq!(percent_encode("zh-TW"), "zh-TW");
+}
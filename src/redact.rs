@@ -0,0 +1,131 @@
+//! Recognition of sensitive text, such as email addresses and card numbers, which should not leave the machine.
+//!
+//! The text found is masked in the same way as names, with `protect` before the text is sent and `restore`
+//! once the translation has arrived, so that Google only ever sees placeholders. A `Translator` built with
+//! `TranslatorBuilder::redact` does this itself for all the text that it sends.
+
+/// A kind of sensitive text that may be masked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sensitive {
+    /// An email address, such as `ada@example.com`.
+    Email,
+    /// A phone number of at least nine digits, written with a leading `+` or with separators.
+    Phone,
+    /// A payment card number of 13 to 19 digits, whose check digit is valid.
+    Card,
+}
+
+impl Sensitive {
+    /// Every kind of sensitive text.
+    pub const ALL: &'static [Sensitive] = &[Sensitive::Email, Sensitive::Phone, Sensitive::Card];
+
+    /// The kind of sensitive text with the given name: `email`, `phone` or `card`.
+    pub fn from_name(name: &str) -> Option<Sensitive> {
+        match name {
+            "email" => Some(Sensitive::Email),
+            "phone" => Some(Sensitive::Phone),
+            "card"  => Some(Sensitive::Card),
+            _       => None
+        }
+    }
+
+    /// The kinds of sensitive text named in a comma-separated list such as `email,card`, or every kind for `all`.
+    pub fn from_list(list: &str) -> Option<Vec<Sensitive>> {
+        match list.trim() {
+            "all" => Some(Sensitive::ALL.to_vec()),
+            list  => list.split(',').map(|name| Sensitive::from_name(name.trim())).collect()
+        }
+    }
+}
+
+/// Find the sensitive text of the given kinds in `input`, longest first, in the form expected by `protect`.
+pub fn find_sensitive(input: &str, kinds: &[Sensitive]) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut add = |text: &str| if !found.iter().any(|known| known == text) { found.push(text.to_string()) };
+
+    if kinds.contains(&Sensitive::Email) {
+        for token in input.split_whitespace() {
+            let token = token.trim_matches(|c: char| "()<>[]{}\"',;:.!?".contains(c));
+            if is_email(token) { add(token); }
+        }
+    }
+
+    let mut rest = input;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit() || c == '+' || c == '(') {
+        let continues = rest[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        let length = number_length(&rest[start..]);
+        let number = &rest[start..start + length];
+        let digits = number.chars().filter(char::is_ascii_digit).collect::<String>();
+        let separated = number.contains(|c: char| " -.()".contains(c));
+        let card = (13..=19).contains(&digits.len()) && luhn(digits.as_str()) && !number.contains(['.', '(', '+']);
+        let phone = (9..=15).contains(&digits.len()) && (number.starts_with('+') || separated);
+        // A number which passes as a card is not also taken for a phone number.
+        let wanted = if card { kinds.contains(&Sensitive::Card) } else { phone && kinds.contains(&Sensitive::Phone) };
+        if !continues && wanted {
+            add(number);
+        }
+        rest = &rest[start + length.max(1)..];
+    }
+
+    found.sort_by_key(|text| ::std::cmp::Reverse(text.len()));
+    found
+}
+
+/// Whether `token` is an email address: a local part, an `@`, and a domain of at least two labels ending in
+/// an alphabetic top-level domain.
+fn is_email(token: &str) -> bool {
+    let (local, domain) = match token.split_once('@') {
+        Some(parts) => parts,
+        None        => return false
+    };
+    let labels = domain.split('.').collect::<Vec<&str>>();
+    !local.is_empty() && local.chars().all(|c| c.is_alphanumeric() || "._%+-".contains(c))
+        && labels.len() >= 2
+        && labels.iter().all(|label| !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-'))
+        && labels[labels.len() - 1].len() >= 2 && labels[labels.len() - 1].chars().all(char::is_alphabetic)
+}
+
+/// The length of the number at the start of `input`, including the single spaces, hyphens, dots and
+/// parentheses that may separate its groups of digits, but not any trailing separator.
+fn number_length(input: &str) -> usize {
+    let mut end = 0;
+    let mut previous = None;
+    for (index, character) in input.char_indices() {
+        let separator = " -.()".contains(character) || (index == 0 && character == '+');
+        // Two separators in a row, other than around parentheses, end the number.
+        if !(character.is_ascii_digit() || separator)
+            || (separator && previous.is_some_and(|p: char| " -.".contains(p) && " -.".contains(character))) {
+            break
+        }
+        if character.is_ascii_digit() || (character == ')' && input[..index].contains('(')) { end = index + 1; }
+        previous = Some(character);
+    }
+    end
+}
+
+/// Whether the check digit of a card number is valid, according to the Luhn algorithm.
+fn luhn(digits: &str) -> bool {
+    let sum = digits.bytes().rev().enumerate().map(|(index, digit)| {
+        let digit = (digit - b'0') as u32;
+        match index % 2 {
+            0 => digit,
+            _ => if digit * 2 > 9 { digit * 2 - 9 } else { digit * 2 }
+        }
+    }).sum::<u32>();
+    sum % 10 == 0
+}
+
+#[test]
+fn test_find_sensitive() {
+    let input = "Write to ada.lovelace@example.co.uk, call +44 20 7946 0958 or (555) 123-4567, and pay with \
+        4111 1111 1111 1111 by 2024-01-05. Order 123456789012 is not a card, nor is 4111 1111 1111 1112.";
+    assert_eq!(find_sensitive(input, Sensitive::ALL), vec![
+        "ada.lovelace@example.co.uk", "4111 1111 1111 1111", "+44 20 7946 0958", "(555) 123-4567",
+    ]);
+    assert_eq!(find_sensitive(input, &[Sensitive::Card]), vec!["4111 1111 1111 1111"]);
+    assert_eq!(find_sensitive("Call me (on 555-123-4567).", Sensitive::ALL), vec!["555-123-4567"]);
+    assert!(find_sensitive("Version 1.2.3 costs 12.50, see me@home or @handle.", Sensitive::ALL).is_empty());
+    assert_eq!(Sensitive::from_list("email, card"), Some(vec![Sensitive::Email, Sensitive::Card]));
+    assert_eq!(Sensitive::from_list("all"), Some(Sensitive::ALL.to_vec()));
+    assert_eq!(Sensitive::from_list("email,address"), None);
+}
@@ -0,0 +1,112 @@
+//! How requests which fail for reasons that are likely to pass, such as a timeout, are tried again.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many times a request is attempted, and how long to wait between attempts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of times a request is attempted before giving up, including the first.
+    pub attempts: u32,
+    /// How long to wait before the first retry. Each later retry waits twice as long as the one before.
+    pub backoff: Duration,
+    /// The longest that any one wait may last.
+    pub max_backoff: Duration,
+    /// Whether each wait is shortened by a random amount of up to half, so that clients which failed at
+    /// the same time do not all retry at the same time.
+    pub jitter: bool,
+    /// The longest that Google may ask to be left alone for with `Retry-After` before a rate-limited request
+    /// is given up on, rather than waited out.
+    pub max_retry_after: Duration,
+}
+
+impl RetryPolicy {
+    /// Attempt each request once only.
+    pub fn never() -> RetryPolicy {
+        RetryPolicy { attempts: 1, ..RetryPolicy::default() }
+    }
+
+    /// Five attempts with long waits between them, for long batch runs which should back off from Google
+    /// rather than press on. A rate limit of up to ten minutes is waited out.
+    pub fn polite() -> RetryPolicy {
+        RetryPolicy {
+            attempts:        5,
+            backoff:         Duration::from_secs(5),
+            max_backoff:     Duration::from_secs(120),
+            max_retry_after: Duration::from_secs(600),
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// How long to wait before the `retry`th retry, counting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self.backoff.checked_mul(1 << retry.saturating_sub(1).min(31)).unwrap_or(self.max_backoff).min(self.max_backoff);
+        if !self.jitter { return delay; }
+        // The time is as good a source of randomness as any for spreading out retries.
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.subsec_nanos());
+        delay / 2 + (delay / 2).mul_f64(nanos as f64 / 1e9)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, waiting about half a second and then about a second between them.
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            attempts:        3,
+            backoff:         Duration::from_millis(500),
+            max_backoff:     Duration::from_secs(8),
+            jitter:          true,
+            max_retry_after: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Read the value of a `Retry-After` header, which is either a number of seconds or an HTTP date such as
+/// `Wed, 21 Oct 2015 07:28:00 GMT`, as the time to wait from `now` in seconds since the Unix epoch.
+pub fn parse_retry_after(value: &str, now: u64) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    // Only the fixed-length date format is accepted, which is the one that servers are required to send.
+    let fields = value.split_whitespace().collect::<Vec<&str>>();
+    if fields.len() != 6 || fields[5] != "GMT" {
+        return None;
+    }
+    const MONTHS: [&'static str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let day = fields[1].parse::<i64>().ok()?;
+    let month = MONTHS.iter().position(|&month| month == fields[2])? as i64 + 1;
+    let year = fields[3].parse::<i64>().ok()?;
+    let time = fields[4].split(':').map(|field| field.parse::<i64>().ok()).collect::<Option<Vec<i64>>>()?;
+    if time.len() != 3 {
+        return None;
+    }
+    // Count days since the epoch in 400-year eras which begin on the 1st of March, as `format_timestamp` does.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    let then = days * 86400 + time[0] * 3600 + time[1] * 60 + time[2];
+    Some(Duration::from_secs((then - now as i64).max(0) as u64))
+}
+
+#[test]
+fn test_parse_retry_after() {
+    assert_eq!(parse_retry_after(" 120 ", 0), Some(Duration::from_secs(120)));
+    assert_eq!(parse_retry_after("Thu, 15 Oct 2026 18:00:30 GMT", 1792087200), Some(Duration::from_secs(30)));
+    assert_eq!(parse_retry_after("Tue, 29 Feb 2000 00:00:00 GMT", 951782400), Some(Duration::from_secs(0)));
+    assert_eq!(parse_retry_after("Thu, 15 Oct 2026 17:00:00 GMT", 1792087200), Some(Duration::from_secs(0)));
+    assert_eq!(parse_retry_after("soon", 0), None);
+}
+
+#[test]
+fn test_delay() {
+    let policy = RetryPolicy { jitter: false, ..RetryPolicy::default() };
+    assert_eq!(policy.delay(1), Duration::from_millis(500));
+    assert_eq!(policy.delay(3), Duration::from_secs(2));
+    assert_eq!(policy.delay(10), Duration::from_secs(8));
+    assert_eq!(policy.delay(100), Duration::from_secs(8));
+    let delay = RetryPolicy::default().delay(2);
+    assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+}
@@ -0,0 +1,181 @@
+//! Splitting of documents into pieces which may be translated independently.
+
+/// The length, in characters, of the longest piece of text that should be sent to Google in one request.
+pub const MAX_SEGMENT_LENGTH: usize = 4000;
+
+/// A piece of a document: either text to be translated, or the whitespace that separates it from the next
+/// piece of text, which is kept as it is so that the layout of the document survives translation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Space(&'a str),
+}
+
+/// Split a document into paragraphs, which are separated by blank lines. Paragraphs longer than `max`
+/// characters are split further, preferably at the end of a sentence, then at whitespace.
+pub fn split_document(input: &str, max: usize) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = input;
+    loop {
+        let trimmed = rest.trim_start();
+        if trimmed.len() != rest.len() {
+            segments.push(Segment::Space(&rest[..rest.len() - trimmed.len()]));
+        }
+        rest = trimmed;
+        if rest.is_empty() { break }
+
+        let end = paragraph_end(rest);
+        split_paragraph(&rest[..end], max, &mut segments);
+        rest = &rest[end..];
+    }
+    segments
+}
+
+/// Split text into pieces of at most `max` characters, preferably at the end of a sentence, then at
+/// whitespace. Unlike `split_document`, paragraphs are kept together wherever they fit.
+pub fn split_text(input: &str, max: usize) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let trimmed = input.trim_start();
+    if trimmed.len() != input.len() {
+        segments.push(Segment::Space(&input[..input.len() - trimmed.len()]));
+    }
+    let end = trimmed.trim_end().len();
+    split_paragraph(&trimmed[..end], max, &mut segments);
+    if end != trimmed.len() {
+        segments.push(Segment::Space(&trimmed[end..]));
+    }
+    segments
+}
+
+/// Find where the first paragraph of `input` ends, excluding any trailing whitespace.
+fn paragraph_end(input: &str) -> usize {
+    for (index, _) in input.match_indices('\n') {
+        let after = &input[index + 1..];
+        let whitespace = after.len() - after.trim_start().len();
+        if whitespace == after.len() || after[..whitespace].contains('\n') {
+            return input[..index].trim_end().len();
+        }
+    }
+    input.trim_end().len()
+}
+
+/// Split a paragraph into pieces of at most `max` characters.
+fn split_paragraph<'a>(paragraph: &'a str, max: usize, segments: &mut Vec<Segment<'a>>) {
+    let mut rest = paragraph;
+    while rest.chars().count() > max {
+        // The byte offset just past the `max`th character, beyond which the piece may not extend.
+        let limit = rest.char_indices().nth(max).map_or(rest.len(), |(index, _)| index);
+        let mut sentence = None;
+        let mut space = None;
+        let mut previous = ' ';
+        for (index, character) in rest[..limit].char_indices() {
+            if character.is_whitespace() && index != 0 {
+                space = Some(index);
+                if is_sentence_end(previous) { sentence = Some(index); }
+            }
+            previous = character;
+        }
+
+        // Full-width terminators are not followed by a space, so the split may come directly after them.
+        let split = match sentence.or(space) {
+            Some(split) => split,
+            None => rest[..limit].char_indices()
+                .filter(|&(index, character)| index != 0 && is_full_width_sentence_end(character))
+                .map(|(index, character)| index + character.len_utf8())
+                .next_back()
+                .unwrap_or(limit)
+        };
+
+        let (text, after) = rest.split_at(split);
+        let trimmed = after.trim_start();
+        segments.push(Segment::Text(text));
+        if trimmed.len() != after.len() {
+            segments.push(Segment::Space(&after[..after.len() - trimmed.len()]));
+        }
+        rest = trimmed;
+    }
+    if !rest.is_empty() { segments.push(Segment::Text(rest)); }
+}
+
+/// Split text into its sentences, each with the whitespace after it, so that joined together they make up the
+/// text again. A sentence ends at a line break, at a full-width terminator, or at a terminator which is followed
+/// by whitespace, after any closing quotes or brackets, which belong to the sentence.
+pub fn split_sentences(input: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+    while let Some(character) = input[index..].chars().next() {
+        index += character.len_utf8();
+        let end = if character == '\n' || is_full_width_sentence_end(character) {
+            Some(index)
+        } else if is_sentence_end(character) {
+            let closed = input[index..].trim_start_matches(['"', '\'', '”', '’', '»', ')', ']']);
+            let end = input.len() - closed.len();
+            if closed.is_empty() || closed.starts_with(char::is_whitespace) { Some(end) } else { None }
+        } else {
+            None
+        };
+        if let Some(end) = end {
+            let rest = &input[end..];
+            index = end + rest.len() - rest.trim_start().len();
+            sentences.push(&input[start..index]);
+            start = index;
+        }
+    }
+    if start < input.len() { sentences.push(&input[start..]); }
+    sentences
+}
+
+pub fn is_sentence_end(character: char) -> bool {
+    match character {
+        '.' | '!' | '?' | '…' => true,
+        _ => is_full_width_sentence_end(character)
+    }
+}
+
+pub fn is_full_width_sentence_end(character: char) -> bool {
+    matches!(character, '。' | '！' | '？')
+}
+
+#[test]
+fn test_split_document() {
+    let document = "\u{feff}Title\n\nFirst line.\nSecond line.\n \n\nLast. Paragraph is long!  Really.\n";
+    assert_eq!(split_document(document, 1000), vec![
+        Segment::Text("\u{feff}Title"),
+        Segment::Space("\n\n"),
+        Segment::Text("First line.\nSecond line."),
+        Segment::Space("\n \n\n"),
+        Segment::Text("Last. Paragraph is long!  Really."),
+        Segment::Space("\n"),
+    ]);
+    assert_eq!(split_document("Last. Paragraph is long!  Really.", 26), vec![
+        Segment::Text("Last. Paragraph is long!"),
+        Segment::Space("  "),
+        Segment::Text("Really."),
+    ]);
+    assert_eq!(split_document("One two three four", 9), vec![
+        Segment::Text("One two"),
+        Segment::Space(" "),
+        Segment::Text("three"),
+        Segment::Space(" "),
+        Segment::Text("four"),
+    ]);
+    assert_eq!(split_document("我不是你。你不是我。", 7), vec![Segment::Text("我不是你。"), Segment::Text("你不是我。")]);
+    assert_eq!(split_document("abcdef", 4), vec![Segment::Text("abcd"), Segment::Text("ef")]);
+    assert_eq!(split_text(" One. Two.\n\nThree four. Five.\n", 23), vec![
+        Segment::Space(" "),
+        Segment::Text("One. Two.\n\nThree four."),
+        Segment::Space(" "),
+        Segment::Text("Five."),
+        Segment::Space("\n"),
+    ]);
+}
+
+#[test]
+fn test_split_sentences() {
+    assert_eq!(split_sentences("Saluton! \"Kiel vi fartas?\" Bone.\nv1.2 estas nova"),
+        vec!["Saluton! ", "\"Kiel vi fartas?\" ", "Bone.\n", "v1.2 estas nova"]);
+    assert_eq!(split_sentences("我不是你。你不是我。  "), vec!["我不是你。", "你不是我。  "]);
+    assert_eq!(split_sentences("  Wait...  "), vec!["  Wait...  "]);
+    assert!(split_sentences("").is_empty());
+}
@@ -0,0 +1,399 @@
+//! Translation of subtitles in the SubRip (SRT) and WebVTT formats, in which only the text of each cue is
+//! translated and the cue numbers, timings, cue settings, notes and styles are kept exactly as they were.
+//!
+//! Subtitles in the Advanced SubStation Alpha (ASS) and SubStation Alpha (SSA) formats are translated in the
+//! same way. Only the text of each `Dialogue:` event is translated; the script info, styles, comments and the
+//! other fields of each event, such as its timing, style and margins, are kept. Override blocks such as
+//! `{\an8}` and `{\i1}` are kept where they were, and the karaoke timing of a line, which marks syllables
+//! that no longer exist once it is translated, is spread over the words of the translation.
+//!
+//! A film has a thousand or more cues of a few words each, so cues are not translated one at a time. They are
+//! gathered into batches of up to a chunk's length, one cue to a line, and each batch is sent as a single
+//! request. Google keeps line breaks, so the lines of the translation are the translations of the cues, and a
+//! batch which comes back with a different number of lines is translated again a cue at a time.
+
+use entity::{find_bracketed_numbers, restore};
+
+/// A subtitle file, divided into the text of its cues and everything around them.
+pub struct Subtitles<'a> {
+    pieces: Vec<Piece<'a>>,
+    /// Whether the file is in the ASS or SSA format, whose cues are single lines broken with `\N`.
+    ass: bool,
+}
+
+enum Piece<'a> {
+    /// Text which is kept as it is, such as cue numbers, timings and the blank lines between cues.
+    Verbatim(&'a str),
+    /// The text of a cue, without the line ending after its last line.
+    Cue(&'a str, usize),
+}
+
+impl<'a> Subtitles<'a> {
+    /// Divide the SRT, WebVTT, ASS or SSA file `input` into its cues. The text of an SRT or WebVTT cue is the
+    /// lines after its timing line, up to the next blank line, and that of an ASS or SSA cue is the last field
+    /// of a `Dialogue:` line in the `[Events]` section. Anything which is not a cue, including input which is
+    /// not subtitles at all, is kept as it is.
+    pub fn parse(input: &'a str) -> Subtitles<'a> {
+        if input.trim_start_matches('\u{feff}').trim_start().starts_with("[Script Info]") {
+            return Subtitles::parse_ass(input);
+        }
+        let mut pieces = Vec::new();
+        // Where the text which is kept as it is begins, and where the text of the cue being read begins and ends.
+        let mut verbatim = 0;
+        let mut cue: Option<(usize, usize)> = None;
+        let mut offset = 0;
+        for line in input.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\n', '\r']);
+            match cue {
+                Some((start, end)) if content.trim().is_empty() => {
+                    if end > start {
+                        pieces.push(Piece::Verbatim(&input[verbatim..start]));
+                        pieces.push(Piece::Cue(&input[start..end], start));
+                        verbatim = end;
+                    }
+                    cue = None;
+                },
+                Some((start, _)) => cue = Some((start, offset + content.len())),
+                None if is_timing(content) => cue = Some((offset + line.len(), offset + line.len())),
+                None => ()
+            }
+            offset += line.len();
+        }
+        if let Some((start, end)) = cue.filter(|&(start, end)| end > start) {
+            pieces.push(Piece::Verbatim(&input[verbatim..start]));
+            pieces.push(Piece::Cue(&input[start..end], start));
+            verbatim = end;
+        }
+        pieces.push(Piece::Verbatim(&input[verbatim..]));
+        Subtitles { pieces, ass: false }
+    }
+
+    /// Divide the ASS or SSA file `input` into its cues. The fields of an event are listed by the `Format:`
+    /// line of the `[Events]` section, of which the text is the last, so it alone may contain commas.
+    fn parse_ass(input: &'a str) -> Subtitles<'a> {
+        let mut pieces = Vec::new();
+        let mut verbatim = 0;
+        let mut events = false;
+        let mut fields = DEFAULT_ASS_FIELDS;
+        let mut offset = 0;
+        for line in input.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let trimmed = content.trim_start();
+            if trimmed.starts_with('[') {
+                events = trimmed.trim_end().eq_ignore_ascii_case("[Events]");
+            } else if let Some(format) = trimmed.strip_prefix("Format:").filter(|_| events) {
+                fields = format.split(',').count().max(1);
+            } else if let Some(event) = trimmed.strip_prefix("Dialogue:").filter(|_| events) {
+                let text = event.splitn(fields, ',').nth(fields - 1);
+                if let Some(text) = text.filter(|text| !text.trim().is_empty()) {
+                    let start = offset + content.len() - text.len();
+                    pieces.push(Piece::Verbatim(&input[verbatim..start]));
+                    pieces.push(Piece::Cue(text, start));
+                    verbatim = offset + content.len();
+                }
+            }
+            offset += line.len();
+        }
+        pieces.push(Piece::Verbatim(&input[verbatim..]));
+        Subtitles { pieces, ass: true }
+    }
+
+    /// The text of each cue, as it is written in the file.
+    pub fn cues(&self) -> Vec<&'a str> {
+        self.pieces.iter().filter_map(|piece| match *piece {
+            Piece::Cue(text, _) => Some(text),
+            Piece::Verbatim(_)  => None
+        }).collect()
+    }
+
+    /// Translate the text of the cues with `translate`, and return the file with the translations in their
+    /// place. `translate` is given batches of cues of at most `max` characters, one cue to a line, with their
+    /// lines joined and their formatting tags, such as `<i>`, replaced by placeholders such as `[0]`, along
+    /// with the offset in the input of the first cue in the batch, and returns their translation. Each cue
+    /// is broken into as many lines as it had, as evenly as possible, unless its lines each begin with a dash
+    /// for a different speaker, in which case they are kept apart.
+    pub fn translate<F: FnMut(&str, usize) -> String>(&self, max: usize, mut translate: F) -> String {
+        let cues = self.pieces.iter().filter_map(|piece| match *piece {
+            Piece::Cue(text, offset) => Some((prepare(text, self.ass), offset)),
+            Piece::Verbatim(_)       => None
+        }).collect::<Vec<(Prepared, usize)>>();
+
+        // Cues with nothing to translate, such as a music symbol, are left out of the batches.
+        let pending = (0..cues.len()).filter(|&index| cues[index].0.translatable()).collect::<Vec<usize>>();
+        let mut translations: Vec<Option<String>> = vec![None; cues.len()];
+        let mut batch_start = 0;
+        while batch_start < pending.len() {
+            let mut batch_end = batch_start + 1;
+            let mut length = cues[pending[batch_start]].0.text.chars().count();
+            while batch_end < pending.len() && length + 1 + cues[pending[batch_end]].0.text.chars().count() <= max {
+                length += 1 + cues[pending[batch_end]].0.text.chars().count();
+                batch_end += 1;
+            }
+            let batch = &pending[batch_start..batch_end];
+            let text = batch.iter().map(|&index| cues[index].0.text.as_str()).collect::<Vec<&str>>().join("\n");
+            let translation = translate(text.as_str(), cues[batch[0]].1);
+            let lines = translation.trim_matches('\n').split('\n').collect::<Vec<&str>>();
+            if lines.len() == batch.len() {
+                for (&index, line) in batch.iter().zip(lines) {
+                    translations[index] = Some(line.to_string());
+                }
+            } else {
+                for &index in batch {
+                    translations[index] = Some(translate(cues[index].0.text.as_str(), cues[index].1).replace('\n', " "));
+                }
+            }
+            batch_start = batch_end;
+        }
+
+        let mut output = String::new();
+        let mut translations = cues.iter().zip(translations);
+        for piece in &self.pieces {
+            match *piece {
+                Piece::Verbatim(text) => output.push_str(text),
+                Piece::Cue(text, _) => match translations.next() {
+                    Some(((prepared, _), Some(translation))) => {
+                        let (ending, count) = match self.ass {
+                            true  => ("\\N", text.split("\\N").count()),
+                            false => (if text.contains("\r\n") { "\r\n" } else { "\n" }, text.lines().count())
+                        };
+                        output.push_str(prepared.finish(translation.as_str(), count).join(ending).as_str());
+                    },
+                    _ => output.push_str(text)
+                }
+            }
+        }
+        output
+    }
+}
+
+/// The text of a cue as it is sent to be translated.
+struct Prepared {
+    /// The text on a single line, with its tags replaced by placeholders.
+    text: String,
+    /// The tags which the placeholders stand for. The breaks between the lines of a dialogue are among them.
+    tags: Vec<String>,
+    /// The karaoke tag of an ASS cue, such as `k` or `kf`, and the centiseconds which its syllables took in all.
+    karaoke: Option<(String, u64)>,
+}
+
+impl Prepared {
+    /// Add `text` from the cue, masking any bracketed number in it, such as `[1]`, which would be taken for the
+    /// placeholder of a tag.
+    fn push_text(&mut self, text: &str) {
+        let mut last = 0;
+        for (start, end) in find_bracketed_numbers(text) {
+            self.text.push_str(&text[last..start]);
+            self.text.push_str(format!("[{}]", self.tags.len()).as_str());
+            self.tags.push(text[start..end].to_string());
+            last = end;
+        }
+        self.text.push_str(&text[last..]);
+    }
+
+    /// Add the override block `block` of an ASS cue, such as `{\\k20\\i1}`, taking its karaoke timing out to be
+    /// spread over the words of the translation.
+    fn push_override(&mut self, block: &str) {
+        let mut parts = block.trim_start_matches('{').trim_end_matches('}').split('\\');
+        let mut kept = parts.next().unwrap_or("").to_string();
+        for part in parts {
+            let timing = KARAOKE_TAGS.iter().filter_map(|&tag| {
+                part.strip_prefix(tag).and_then(|duration| duration.parse::<u64>().ok()).map(|duration| (tag, duration))
+            }).next();
+            match timing {
+                Some((tag, duration)) => {
+                    let karaoke = self.karaoke.get_or_insert_with(|| (tag.to_string(), 0));
+                    karaoke.1 += duration;
+                },
+                None => { kept.push('\\'); kept.push_str(part); }
+            }
+        }
+        if !kept.is_empty() {
+            self.text.push_str(format!("[{}]", self.tags.len()).as_str());
+            self.tags.push(format!("{{{}}}", kept));
+        }
+    }
+
+    fn translatable(&self) -> bool {
+        restore(self.text.as_str(), &vec![String::new(); self.tags.len()]).0.chars().any(char::is_alphabetic)
+    }
+
+    /// Put the tags back into `translation` and break it into `count` lines, or at the breaks of a dialogue.
+    fn finish(&self, translation: &str, count: usize) -> Vec<String> {
+        let (mut text, missing) = restore(translation, &self.tags);
+        // Tags which were lost go at the end, but a lost line break would leave a blank line, ending the cue.
+        for tag in missing.iter().filter(|tag| tag.as_str() != "\n") {
+            text.push_str(tag.as_str());
+        }
+        let lines = if text.contains('\n') {
+            text.split('\n').map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect()
+        } else {
+            break_lines(text.trim(), count)
+        };
+        match self.karaoke {
+            Some((ref tag, duration)) => time_words(&lines, tag, duration),
+            None => lines
+        }
+    }
+}
+
+/// Give each word of `lines` a karaoke tag `tag`, sharing `duration` between them by the length of their text
+/// outside override blocks.
+fn time_words(lines: &[String], tag: &str, duration: u64) -> Vec<String> {
+    let length = |word: &str| word.split('{').enumerate()
+        .map(|(index, part)| if index == 0 { part } else { part.split_once('}').map_or(part, |(_, text)| text) })
+        .map(|text| text.chars().count() as u64).sum::<u64>();
+    let total = lines.iter().flat_map(|line| line.split_whitespace()).map(length).sum::<u64>().max(1);
+    let mut elapsed = 0;
+    let mut timed = 0;
+    lines.iter().map(|line| line.split_whitespace().map(|word| {
+        // Each word ends where its share of the duration so far ends, so the rounding never adds up.
+        elapsed += length(word);
+        let end = duration * elapsed / total;
+        let word = format!("{{\\{}{}}}{}", tag, end - timed, word);
+        timed = end;
+        word
+    }).collect::<Vec<String>>().join(" ")).collect()
+}
+
+/// Join the lines of the cue `text` and mask its formatting tags, such as `<i>` and `{\an8}`. The lines of an
+/// ASS cue are broken with `\N`, and its tags are the override blocks in braces, soft line breaks (`\n`) and
+/// hard spaces (`\h`).
+fn prepare(text: &str, ass: bool) -> Prepared {
+    let lines = match ass {
+        true  => text.split("\\N").collect::<Vec<&str>>(),
+        false => text.lines().collect::<Vec<&str>>()
+    };
+    let dialogue = lines.len() > 1 && lines.iter().all(|line| line.trim_start().starts_with(['-', '–', '—']));
+    let openers: &[char] = if ass { &['{', '\\'] } else { &['<', '{'] };
+    let mut prepared = Prepared { text: String::with_capacity(text.len()), tags: Vec::new(), karaoke: None };
+    for (index, line) in lines.into_iter().enumerate() {
+        if index != 0 && dialogue {
+            prepared.text.push_str(format!("[{}]", prepared.tags.len()).as_str());
+            prepared.tags.push("\n".to_string());
+        } else if index != 0 {
+            prepared.text.push(' ');
+        }
+        let mut rest = line.trim();
+        while let Some(start) = rest.find(openers) {
+            let tag = match rest[start..].chars().next() {
+                Some('<') => rest[start..].find('>').map(|end| end + 1),
+                Some('{') => rest[start..].find('}').filter(|_| ass || rest[start + 1..].starts_with('\\')).map(|end| end + 1),
+                _ => Some(2).filter(|_| rest[start + 1..].starts_with(['n', 'h']))
+            };
+            match tag {
+                Some(length) if ass && rest[start..].starts_with('{') => {
+                    prepared.push_text(&rest[..start]);
+                    prepared.push_override(&rest[start..start + length]);
+                    rest = &rest[start + length..];
+                },
+                Some(length) => {
+                    prepared.push_text(&rest[..start]);
+                    prepared.text.push_str(format!("[{}]", prepared.tags.len()).as_str());
+                    prepared.tags.push(rest[start..start + length].to_string());
+                    rest = &rest[start + length..];
+                },
+                None => {
+                    prepared.push_text(&rest[..start + 1]);
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        prepared.push_text(rest);
+    }
+    prepared
+}
+
+/// The fields of an ASS or SSA event when the file does not list them.
+const DEFAULT_ASS_FIELDS: usize = 10;
+
+/// The override tags which time the syllables of a karaoke line, longest first.
+const KARAOKE_TAGS: &'static [&'static str] = &["kf", "ko", "K", "k"];
+
+/// Break `text` at spaces into at most `count` lines of about the same length.
+fn break_lines(text: &str, count: usize) -> Vec<String> {
+    let total = text.chars().count();
+    let mut lines = vec![String::new()];
+    let mut written = 0;
+    for word in text.split_whitespace() {
+        let length = word.chars().count();
+        // A new line is begun once the middle of the word would pass where the current line should end.
+        let target = total * lines.len() / count.max(1);
+        let begun = !lines[lines.len() - 1].is_empty();
+        if begun && lines.len() < count && written + 1 + length / 2 > target {
+            lines.push(String::new());
+        } else if begun {
+            lines.last_mut().unwrap().push(' ');
+            written += 1;
+        }
+        lines.last_mut().unwrap().push_str(word);
+        written += length;
+    }
+    lines
+}
+
+/// Whether `line` is the timing line of a cue, such as `00:00:01,600 --> 00:00:04,200` in SRT or
+/// `00:01.600 --> 00:04.200 align:start` in WebVTT.
+fn is_timing(line: &str) -> bool {
+    let timestamp = |text: &str| !text.is_empty() && text.contains(':') && text.chars().all(|c| c.is_ascii_digit() || ":,.".contains(c));
+    let mut parts = line.splitn(2, "-->");
+    let (start, end) = (parts.next().unwrap_or(""), parts.next());
+    timestamp(start.trim()) && end.and_then(|end| end.split_whitespace().next()).is_some_and(timestamp)
+}
+
+#[test]
+fn test_translate_subtitles() {
+    let srt = "1\r\n00:00:01,000 --> 00:00:03,000\r\n<i>Mi estas ne vin,</i>\r\nvi estas ne min.\r\n\r\n\
+        2\r\n00:00:04,000 --> 00:00:05,000\r\n♪ ♪\r\n\r\n\
+        3\r\n00:00:06,000 --> 00:00:08,000\r\n- Saluton!\r\n- {\\an8}Ĝis!\r\n";
+    let subtitles = Subtitles::parse(srt);
+    assert_eq!(subtitles.cues(), vec!["<i>Mi estas ne vin,</i>\r\nvi estas ne min.", "♪ ♪", "- Saluton!\r\n- {\\an8}Ĝis!"]);
+    let mut batches = Vec::new();
+    let output = subtitles.translate(1000, |text, offset| {
+        batches.push((text.to_string(), offset));
+        text.replace("Mi estas ne vin,", "I am not you,").replace("vi estas ne min.", "you are not me.")
+            .replace("Saluton!", "Hello!").replace("Ĝis!", "Bye!")
+    });
+    assert_eq!(batches, vec![("[0]Mi estas ne vin,[1] vi estas ne min.\n- Saluton![0]- [1]Ĝis!".to_string(), 34)]);
+    assert_eq!(output, "1\r\n00:00:01,000 --> 00:00:03,000\r\n<i>I am not you,</i>\r\nyou are not me.\r\n\r\n\
+        2\r\n00:00:04,000 --> 00:00:05,000\r\n♪ ♪\r\n\r\n\
+        3\r\n00:00:06,000 --> 00:00:08,000\r\n- Hello!\r\n- {\\an8}Bye!\r\n");
+
+    // A batch which comes back with its cues run together is translated again a cue at a time.
+    let vtt = "WEBVTT\n\nNOTE 00:01 --> not a cue\n\n00:01.000 --> 00:02.000 align:start\nSaluton\n\n00:03.000 --> 00:04.000\nĜis";
+    let mut requests = 0;
+    let output = Subtitles::parse(vtt).translate(1000, |text, _| {
+        requests += 1;
+        if text.contains('\n') { "Hello Bye".to_string() } else { text.to_uppercase() }
+    });
+    assert_eq!(requests, 3);
+    assert_eq!(output, "WEBVTT\n\nNOTE 00:01 --> not a cue\n\n00:01.000 --> 00:02.000 align:start\nSALUTON\n\n00:03.000 --> 00:04.000\nĜIS");
+    let output = Subtitles::parse("1\n00:00:01,000 --> 00:00:02,000\n<i>Saluton</i> [1]\n").translate(1000, |text, _| {
+        assert_eq!(text, "[0]Saluton[1] [2]");
+        text.replace("Saluton", "Hello")
+    });
+    assert_eq!(output, "1\n00:00:01,000 --> 00:00:02,000\n<i>Hello</i> [1]\n");
+    assert_eq!(break_lines("I am not you and you are not me", 2), vec!["I am not you and", "you are not me"]);
+}
+
+#[test]
+fn test_translate_ass() {
+    let ass = "\u{feff}[Script Info]\r\nTitle: Saluton, mondo\r\n\r\n[V4+ Styles]\r\n\
+        Format: Name, Fontname, Fontsize\r\nStyle: Default,Arial,20\r\n\r\n[Events]\r\n\
+        Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\r\n\
+        Dialogue: 0,0:00:01.00,0:00:03.00,Default,,0,0,0,,{\\an8}Saluton,\\Nmia amiko\r\n\
+        Comment: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,Saluton\r\n\
+        Dialogue: 0,0:00:04.00,0:00:06.00,Karaoke,,0,0,0,,{\\k20}Ĝis{\\k30\\i1}re{\\k50}vi!\r\n";
+    let subtitles = Subtitles::parse(ass);
+    assert_eq!(subtitles.cues(), vec!["{\\an8}Saluton,\\Nmia amiko", "{\\k20}Ĝis{\\k30\\i1}re{\\k50}vi!"]);
+    let output = subtitles.translate(1000, |text, _| {
+        assert_eq!(text, "[0]Saluton, mia amiko\nĜis[0]revi!");
+        text.replace("Saluton, mia amiko", "Hello, my friend").replace("Ĝis[0]revi!", "See [0]you again!")
+    });
+    assert_eq!(output, "\u{feff}[Script Info]\r\nTitle: Saluton, mondo\r\n\r\n[V4+ Styles]\r\n\
+        Format: Name, Fontname, Fontsize\r\nStyle: Default,Arial,20\r\n\r\n[Events]\r\n\
+        Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\r\n\
+        Dialogue: 0,0:00:01.00,0:00:03.00,Default,,0,0,0,,{\\an8}Hello,\\Nmy friend\r\n\
+        Comment: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,Saluton\r\n\
+        Dialogue: 0,0:00:04.00,0:00:06.00,Karaoke,,0,0,0,,{\\k25}See {\\k25}{\\i1}you {\\k50}again!\r\n");
+    assert_eq!(time_words(&["a bb".to_string(), "ccc".to_string()], "kf", 100), vec!["{\\kf16}a {\\kf34}bb", "{\\kf50}ccc"]);
+}
@@ -0,0 +1,98 @@
+//! Extractive summarisation, which trims long text down to its most representative sentences so that
+//! only the gist of it needs to be translated.
+
+use std::collections::HashMap;
+
+use segment::{is_full_width_sentence_end, is_sentence_end};
+
+/// Shorten `input` to roughly `ratio` (between 0 and 1) of its length by keeping only its most
+/// representative sentences. A sentence is scored by how frequently its words occur throughout the
+/// whole text, and the best sentences are kept in their original order. At least one sentence is
+/// always kept, and sentences which began a paragraph still do so.
+pub fn summarize(input: &str, ratio: f64) -> String {
+    let sentences = split_sentences(input);
+    let length = input.chars().count();
+    let target = (length as f64 * ratio).round() as usize;
+    if sentences.len() < 2 || target >= length { return input.trim().to_string(); }
+
+    let mut frequencies = HashMap::new();
+    for &(sentence, _) in &sentences {
+        for word in words(sentence) {
+            *frequencies.entry(word).or_insert(0usize) += 1;
+        }
+    }
+
+    // The average frequency of a sentence's words, so that long sentences are not favoured merely for their length.
+    let scores = sentences.iter().map(|&(sentence, _)| {
+        let words = words(sentence);
+        let total = words.iter().map(|word| frequencies[word]).sum::<usize>();
+        if words.is_empty() { 0.0 } else { total as f64 / words.len() as f64 }
+    }).collect::<Vec<f64>>();
+
+    let mut ranking = (0..sentences.len()).collect::<Vec<usize>>();
+    ranking.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap_or(::std::cmp::Ordering::Equal).then(a.cmp(&b)));
+
+    let mut keep = vec![false; sentences.len()];
+    let mut kept = 0;
+    for index in ranking {
+        let size = sentences[index].0.chars().count();
+        if kept != 0 && kept + size > target { continue }
+        keep[index] = true;
+        kept += size;
+    }
+
+    let mut summary = String::with_capacity(kept * 4);
+    for (_, &(sentence, before)) in sentences.iter().enumerate().filter(|&(index, _)| keep[index]) {
+        if !summary.is_empty() {
+            match before.matches('\n').count() {
+                0 if before.is_empty() => (),
+                0 | 1 => summary.push(' '),
+                _ => summary.push_str("\n\n")
+            }
+        }
+        summary.push_str(sentence);
+    }
+    summary
+}
+
+/// Split text into its sentences, each paired with the whitespace which came before it.
+fn split_sentences(input: &str) -> Vec<(&str, &str)> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut before = "";
+    let mut characters = input.char_indices().peekable();
+    while let Some((index, character)) = characters.next() {
+        let end = index + character.len_utf8();
+        let next = characters.peek().map(|&(_, next)| next);
+        let boundary = match next {
+            None => true,
+            Some(next) => is_full_width_sentence_end(character) || (is_sentence_end(character) && next.is_whitespace())
+        };
+        if !boundary { continue }
+
+        let sentence = input[start..end].trim();
+        if !sentence.is_empty() { sentences.push((sentence, before)); }
+        let rest = &input[end..];
+        before = &rest[..rest.len() - rest.trim_start().len()];
+        start = end;
+    }
+    sentences
+}
+
+fn words(sentence: &str) -> Vec<String> {
+    sentence.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[test]
+fn test_summarize() {
+    let article = "The cat sat on the mat. Dogs bark. The cat likes the mat.\n\nThe mat is red and the cat is black. Weather is nice.";
+    assert_eq!(summarize(article, 1.0), article);
+    assert_eq!(summarize(article, 0.5), "The cat sat on the mat. Dogs bark. The cat likes the mat.");
+    assert_eq!(summarize("Cats purr.\n\nCats sleep. Weather is nice today.", 0.6), "Cats purr.\n\nCats sleep.");
+    assert_eq!(summarize(article, 0.01), "The cat likes the mat.");
+    assert_eq!(summarize("Just one sentence.", 0.2), "Just one sentence.");
+    assert_eq!(summarize("我是猫。名字还没有。我是猫。", 0.6), "我是猫。我是猫。");
+}
@@ -0,0 +1,62 @@
+//! Pacing of requests, so that a long batch run does not send them faster than a person using the web page
+//! would, which is what leads Google to rate limit or block the unofficial endpoint.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The shortest time between two requests sent by `Throttle::polite`.
+pub const POLITE_INTERVAL: Duration = Duration::from_millis(1500);
+/// Up to how much longer than `POLITE_INTERVAL` a polite throttle waits, chosen at random for each request.
+pub const POLITE_JITTER: Duration = Duration::from_millis(1500);
+
+/// Spaces out requests so that each begins at least `interval`, and up to `interval` plus `jitter`, after the
+/// one before. A throttle is shared by every thread sending requests, which take turns.
+#[derive(Debug)]
+pub struct Throttle {
+    interval: Duration,
+    jitter: Duration,
+    /// The earliest that the next request may be sent.
+    next: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    pub fn new(interval: Duration, jitter: Duration) -> Throttle {
+        Throttle { interval, jitter, next: Mutex::new(None) }
+    }
+
+    /// A throttle which sends a request every two to three seconds, or twenty to forty a minute.
+    pub fn polite() -> Throttle {
+        Throttle::new(POLITE_INTERVAL, POLITE_JITTER)
+    }
+
+    /// Block until a request may be sent, and hold back the next one until its turn.
+    pub fn wait(&self) {
+        // The time is as good a source of randomness as any for varying the delay.
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.subsec_nanos());
+        let wait = self.reserve(Instant::now(), nanos as f64 / 1e9);
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Take the next turn to send a request at `now`, returning how long to wait for it. `random`, from 0 to
+    /// 1, is how much of the jitter is added to the time until the turn after.
+    fn reserve(&self, now: Instant, random: f64) -> Duration {
+        let mut next = self.next.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let turn = next.map_or(now, |next| next.max(now));
+        *next = Some(turn + self.interval + self.jitter.mul_f64(random));
+        turn - now
+    }
+}
+
+#[test]
+fn test_throttle() {
+    let throttle = Throttle::new(Duration::from_secs(2), Duration::from_secs(2));
+    let now = Instant::now();
+    assert_eq!(throttle.reserve(now, 0.5), Duration::from_secs(0));
+    assert_eq!(throttle.reserve(now, 0.0), Duration::from_secs(3));
+    assert_eq!(throttle.reserve(now + Duration::from_secs(1), 1.0), Duration::from_secs(4));
+    // A request long after the last waits for nothing.
+    assert_eq!(throttle.reserve(now + Duration::from_secs(60), 0.0), Duration::from_secs(0));
+}
@@ -0,0 +1,129 @@
+//! The TLS settings of the connection to Google, for environments which intercept or restrict HTTPS, and the
+//! creation of the HTTP client which follows them.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+#[cfg(feature = "tls-rustls")]
+use std::sync::Arc;
+
+use hyper::Client;
+use hyper::client::ProxyConfig;
+use hyper::net::{HttpConnector, HttpsConnector, SslClient};
+
+use proxy::{Proxy, Socks5Connector};
+
+/// The implementation of TLS which secures the connection, each of which is available only if the crate
+/// was built with its feature: `tls-native` for the platform's own TLS library, and `tls-rustls` for rustls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TlsBackend {
+    Native,
+    Rustls,
+}
+
+/// How the HTTPS connection to Google is secured.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsConfig {
+    pub backend: TlsBackend,
+    /// A file of PEM certificates to trust in addition to the usual ones, such as a corporate root certificate.
+    pub ca_bundle: Option<PathBuf>,
+    /// Whether the certificate presented by the server is checked. Turning this off exposes the text being
+    /// translated to anyone able to intercept the connection.
+    pub verify: bool,
+}
+
+impl Default for TlsConfig {
+    /// The platform's TLS library if it is available, trusting only the usual certificates.
+    fn default() -> TlsConfig {
+        TlsConfig {
+            backend:   if cfg!(feature = "tls-native") { TlsBackend::Native } else { TlsBackend::Rustls },
+            ca_bundle: None,
+            verify:    true,
+        }
+    }
+}
+
+/// Create an HTTP client which connects over TLS as configured by `config`, through `proxy` if one is given.
+pub fn https_client(config: &TlsConfig, proxy: Option<&Proxy>) -> Result<Client, String> {
+    let mut bundle = Vec::new();
+    if let Some(ref path) = config.ca_bundle {
+        File::open(path).and_then(|mut file| file.read_to_end(&mut bundle))
+            .map_err(|why| format!("unable to read {}: {}", path.display(), why))?;
+    }
+    match config.backend {
+        TlsBackend::Native => native_tls(&bundle, config.verify).map(|tls| connect(tls, proxy)),
+        TlsBackend::Rustls => rustls_tls(&bundle, config.verify).map(|tls| connect(tls, proxy))
+    }
+}
+
+/// Create a client which wraps its connections with `tls`, connecting directly or through `proxy`. An HTTP
+/// proxy is asked to tunnel the connection, so that it never sees what is sent through it.
+fn connect<S: SslClient + Send + Sync + 'static>(tls: S, proxy: Option<&Proxy>) -> Client {
+    match proxy {
+        None => Client::with_connector(HttpsConnector::new(tls)),
+        Some(&Proxy::Http { ref host, port }) => {
+            Client::with_proxy_config(ProxyConfig::new("http", host.clone(), port, HttpConnector, tls))
+        },
+        Some(&Proxy::Socks5 { ref host, port }) => {
+            Client::with_connector(HttpsConnector::with_connector(tls, Socks5Connector { host: host.clone(), port }))
+        }
+    }
+}
+
+#[cfg(feature = "tls-native")]
+fn native_tls(bundle: &[u8], verify: bool) -> Result<::hyper_native_tls::NativeTlsClient, String> {
+    use hyper_native_tls::NativeTlsClient;
+    use native_tls::{Certificate, TlsConnector};
+
+    let mut builder = TlsConnector::builder();
+    // The platform's library takes one certificate at a time, so the bundle is split up.
+    let bundle = String::from_utf8_lossy(bundle);
+    for certificate in bundle.split("-----BEGIN CERTIFICATE-----").skip(1) {
+        let pem = format!("-----BEGIN CERTIFICATE-----{}", certificate);
+        let certificate = Certificate::from_pem(pem.as_bytes()).map_err(|why| format!("invalid certificate: {}", why))?;
+        builder.add_root_certificate(certificate);
+    }
+    builder.danger_accept_invalid_certs(!verify);
+    let connector = builder.build().map_err(|why| format!("unable to set up TLS: {}", why))?;
+    Ok(NativeTlsClient::from(connector))
+}
+
+#[cfg(not(feature = "tls-native"))]
+fn native_tls(_: &[u8], _: bool) -> Result<Unavailable, String> {
+    Err("this build does not include the native TLS backend".to_string())
+}
+
+#[cfg(feature = "tls-rustls")]
+fn rustls_tls(bundle: &[u8], verify: bool) -> Result<::hyper_rustls::TlsClient, String> {
+    use hyper_rustls::TlsClient;
+
+    if !verify {
+        return Err("the rustls TLS backend always verifies certificates".to_string());
+    }
+    let mut tls = TlsClient::new();
+    if !bundle.is_empty() {
+        let mut config = (*tls.cfg).clone();
+        config.root_store.add_pem_file(&mut &bundle[..]).map_err(|_| "invalid certificate bundle".to_string())?;
+        tls.cfg = Arc::new(config);
+    }
+    Ok(tls)
+}
+
+#[cfg(not(feature = "tls-rustls"))]
+fn rustls_tls(_: &[u8], _: bool) -> Result<Unavailable, String> {
+    Err("this build does not include the rustls TLS backend".to_string())
+}
+
+/// Stands in for a TLS backend which was left out of the build, and so can never be created.
+#[cfg(not(all(feature = "tls-native", feature = "tls-rustls")))]
+#[derive(Clone)]
+enum Unavailable {}
+
+#[cfg(not(all(feature = "tls-native", feature = "tls-rustls")))]
+impl SslClient for Unavailable {
+    type Stream = ::hyper::net::HttpStream;
+
+    fn wrap_client(&self, _: ::hyper::net::HttpStream, _: &str) -> ::hyper::Result<Self::Stream> {
+        match *self {}
+    }
+}
@@ -0,0 +1,960 @@
+//! The `Translator`, which sends text to Google Translate either blocking or in the background.
+
+use std::io::Read;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::str;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hyper::Client;
+use hyper::header::{Connection, ContentType, UserAgent};
+
+use audit::AuditLog;
+use cache::ResponseCache;
+use circuit::{CircuitBreaker, CircuitState, DEFAULT_CIRCUIT_COOLDOWN, DEFAULT_CIRCUIT_THRESHOLD};
+use entity::{protect, restore};
+use error::Error;
+use glossary::Glossary;
+use json::Json;
+use language::{serbian_to_latin, split_script, Script};
+use memory::{TranslationMemory, TranslationUnit, DEFAULT_MATCH_THRESHOLD};
+use parse::{is_blocked, parse_detection, parse_dictionary, parse_response, Alternative, Detection, DictionaryEntry, Sentence};
+use proxy::Proxy;
+use query::percent_encode;
+use redact::{find_sensitive, Sensitive};
+use retry::{parse_retry_after, RetryPolicy};
+use segment::{split_sentences, split_text, Segment, MAX_SEGMENT_LENGTH};
+use throttle::Throttle;
+use tls::{https_client, TlsConfig};
+use usage::{Usage, UsageMeter};
+
+/// The server that requests are sent to, unless configured otherwise.
+pub const DEFAULT_ENDPOINT: &'static str = "https://translate.googleapis.com";
+
+/// The User-Agent sent with every request, unless configured otherwise. Google is quick to refuse requests
+/// which identify themselves as coming from an HTTP library, so a common browser is named instead.
+pub const DEFAULT_USER_AGENT: &'static str = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+
+/// The name under which responses from Google are cached, to keep them apart from those of any other engine.
+const ENGINE: &'static str = "google";
+
+/// The path of the translation service on an endpoint.
+const TRANSLATE: &'static str = "/translate_a/single?client=gtx";
+
+/// The length of the longest URL that is sent with GET. Servers and proxies commonly refuse URLs much longer
+/// than this, so larger requests carry the text in the body of a POST instead.
+const MAX_URL_LENGTH: usize = 2000;
+
+/// How long to wait on the network before giving up on a request, unless configured otherwise.
+pub const DEFAULT_TIMEOUT: u64 = 10;
+
+/// The most requests for the chunks of one piece of input that are on their way at once when they are sent
+/// concurrently, so that long input does not reach Google as a burst which it answers by limiting or blocking.
+pub const MAX_CONCURRENT_CHUNKS: usize = 4;
+
+/// How confident Google must be that text is written in a language other than those hinted at for its detection
+/// to stand.
+pub const HINT_CONFIDENCE: f64 = 0.9;
+
+/// The outcome of translating a piece of text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TranslationResult {
+    /// The translated text.
+    pub text: String,
+    /// The language that Google detected the input to be written in, if it reported one.
+    pub source_language: Option<String>,
+    /// How confident Google is in the detected source language, from 0 to 1.
+    pub confidence: Option<f64>,
+    /// The sentences that the translation is made up of.
+    pub sentences: Vec<Sentence>,
+    /// Other candidate translations for segments of the input.
+    pub alternatives: Vec<Alternative>,
+    /// Where the translation came from.
+    pub origin: Origin,
+    /// How similar the text was to the text whose translation the translation memory held, from 0 to 1, if
+    /// the translation came from the memory. A translation of several pieces has the lowest of their similarities.
+    pub similarity: Option<f64>,
+}
+
+/// Where a translation came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Origin {
+    /// Google translated the text when it was asked to.
+    #[default]
+    Google,
+    /// Google's response to an earlier request for the same text was kept in the response cache.
+    Cache,
+    /// The translation memory held a translation of the same or nearly the same text.
+    Memory,
+}
+
+/// A run of sentences written in the same language, as found by `Translator::segment_by_language`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanguageRun {
+    /// The text of the run, with the whitespace after it, so that the runs joined together make up the text
+    /// they were found in.
+    pub text: String,
+    /// The code of the detected language, such as `eo`, or `None` if the text has no letters in it.
+    pub language: Option<String>,
+}
+
+/// Writes the result as an object with the members `text`, `source_language`, `confidence`, `sentences`,
+/// `alternatives` and `similarity`. Members which Google did not report are `null` rather than left out.
+impl From<TranslationResult> for Json {
+    fn from(result: TranslationResult) -> Json {
+        Json::Object(vec![
+            ("text".to_string(),            result.text.into()),
+            ("source_language".to_string(), result.source_language.into()),
+            ("confidence".to_string(),      result.confidence.into()),
+            ("sentences".to_string(),       result.sentences.into()),
+            ("alternatives".to_string(),    result.alternatives.into()),
+            ("similarity".to_string(),      result.similarity.into()),
+        ])
+    }
+}
+
+/// Told that a request failed and will be tried again, with why it failed and how long until it is tried again.
+pub type RetryObserver = Arc<dyn Fn(&Error, Duration) + Send + Sync>;
+
+/// Configures a `Translator` before it is created.
+pub struct TranslatorBuilder {
+    timeout: Option<Duration>,
+    chunk_length: usize,
+    concurrent_chunks: bool,
+    audit_log: Option<Arc<AuditLog>>,
+    tls: TlsConfig,
+    proxy: Option<Proxy>,
+    retry: RetryPolicy,
+    endpoints: Vec<String>,
+    user_agents: Vec<String>,
+    cache: Option<Arc<ResponseCache>>,
+    memory: Option<Arc<TranslationMemory>>,
+    memory_threshold: f64,
+    throttle: Option<Arc<Throttle>>,
+    circuit_threshold: u32,
+    circuit_cooldown: Duration,
+    redact: Vec<Sensitive>,
+    glossary: Glossary,
+    character_limit: Option<u64>,
+    hints: Vec<String>,
+    on_retry: Option<RetryObserver>,
+}
+
+impl TranslatorBuilder {
+    /// How long to wait when sending a request or reading its response. `None` waits indefinitely.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> TranslatorBuilder {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The length, in characters, above which input is split into several requests. Defaults to
+    /// `MAX_SEGMENT_LENGTH`, as Google rejects or truncates much longer queries.
+    pub fn chunk_length(mut self, chunk_length: usize) -> TranslatorBuilder {
+        self.chunk_length = chunk_length.max(1);
+        self
+    }
+
+    /// Whether the requests for long input are sent concurrently, up to `MAX_CONCURRENT_CHUNKS` at a time,
+    /// rather than one after another.
+    pub fn concurrent_chunks(mut self, concurrent: bool) -> TranslatorBuilder {
+        self.concurrent_chunks = concurrent;
+        self
+    }
+
+    /// Record every piece of text before it is sent in `audit_log`, refusing to send any which cannot be recorded.
+    pub fn audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> TranslatorBuilder {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /// How the HTTPS connection to Google is secured.
+    pub fn tls(mut self, tls: TlsConfig) -> TranslatorBuilder {
+        self.tls = tls;
+        self
+    }
+
+    /// Send requests through `proxy`, rather than connecting to Google directly.
+    pub fn proxy(mut self, proxy: Option<Proxy>) -> TranslatorBuilder {
+        self.proxy = proxy;
+        self
+    }
+
+    /// How requests which fail for reasons that may pass, such as a timeout or a server error, are tried again.
+    pub fn retry(mut self, retry: RetryPolicy) -> TranslatorBuilder {
+        self.retry = retry;
+        self
+    }
+
+    /// The servers that requests are sent to, such as `https://translate.googleapis.com`, a regional mirror or
+    /// a proxy which forwards to Google. Requests go to the first, moving on to the next whenever one fails in
+    /// a way that another server might not, such as by timing out or rate limiting. The scheme may be left
+    /// out, in which case HTTPS is used, and a path is kept so that a proxy may serve Google under it.
+    pub fn endpoints(mut self, endpoints: Vec<String>) -> TranslatorBuilder {
+        self.endpoints = endpoints.iter().map(|endpoint| normalize_endpoint(endpoint)).filter(|endpoint| !endpoint.is_empty()).collect();
+        if self.endpoints.is_empty() {
+            self.endpoints.push(DEFAULT_ENDPOINT.to_string());
+        }
+        self
+    }
+
+    /// The User-Agent headers to send. When several are given, each request uses the next in turn.
+    pub fn user_agents(mut self, user_agents: Vec<String>) -> TranslatorBuilder {
+        self.user_agents = user_agents.into_iter().filter(|user_agent| !user_agent.trim().is_empty()).collect();
+        if self.user_agents.is_empty() {
+            self.user_agents.push(DEFAULT_USER_AGENT.to_string());
+        }
+        self
+    }
+
+    /// Keep Google's responses in `cache`, and answer requests for the same text and languages from it
+    /// for as long as they have not expired.
+    pub fn cache(mut self, cache: Option<Arc<ResponseCache>>) -> TranslatorBuilder {
+        self.cache = cache;
+        self
+    }
+
+    /// Answer requests from the translations in `memory` whose text is at least `threshold` similar, from 0 to
+    /// 1, to the text being translated, and add every translation that Google makes to it.
+    pub fn memory(mut self, memory: Option<Arc<TranslationMemory>>, threshold: f64) -> TranslatorBuilder {
+        self.memory = memory;
+        self.memory_threshold = threshold.max(0.0).min(1.0);
+        self
+    }
+
+    /// Space out the requests sent to Google with `throttle`, including retries. Requests answered from the
+    /// cache or the translation memory are not held back.
+    pub fn throttle(mut self, throttle: Option<Arc<Throttle>>) -> TranslatorBuilder {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Mask the sensitive text of the given kinds in everything that is sent, putting it back into translations
+    /// once they arrive, so that it never leaves the machine, nor is it written to the audit log or the memory.
+    pub fn redact(mut self, kinds: Vec<Sensitive>) -> TranslatorBuilder {
+        self.redact = kinds;
+        self
+    }
+
+    /// Keep the terms of `glossary` from being translated, putting each back into translations as its
+    /// translation into the target language if the glossary has one, or as it was otherwise.
+    pub fn glossary(mut self, glossary: Glossary) -> TranslatorBuilder {
+        self.glossary = glossary;
+        self
+    }
+
+    /// Refuse to send any request which would take the characters sent by the translator and its clones past
+    /// `limit`, failing it with `Error::LimitReached` instead. Every attempt counts against the limit, while
+    /// translations answered from the cache or the translation memory do not. `None` sends without limit.
+    pub fn character_limit(mut self, limit: Option<u64>) -> TranslatorBuilder {
+        self.character_limit = limit;
+        self
+    }
+
+    /// The languages which text is likely to be written in, such as `ES` and `PT`, most likely first. When the
+    /// source language is detected, a detection of any other language which Google is less than
+    /// `HINT_CONFIDENCE` sure of gives way to the first hint, so that short phrases which could belong to
+    /// several related languages are taken to be in the one expected.
+    pub fn hints(mut self, hints: Vec<String>) -> TranslatorBuilder {
+        self.hints = hints.into_iter().filter(|hint| !hint.trim().is_empty()).collect();
+        self
+    }
+
+    /// Tell `observer` each time a request fails and is about to be tried again, such as when Google is limiting
+    /// requests, so that a long job can say why it has stalled.
+    pub fn on_retry(mut self, observer: Option<RetryObserver>) -> TranslatorBuilder {
+        self.on_retry = observer;
+        self
+    }
+
+    /// Rest an endpoint for `cooldown` once `threshold` requests in a row have failed on it for reasons of its
+    /// own, such as timing out or limiting requests, sending requests to the other endpoints meanwhile. A
+    /// threshold of zero never rests an endpoint.
+    pub fn circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> TranslatorBuilder {
+        self.circuit_threshold = threshold;
+        self.circuit_cooldown = cooldown;
+        self
+    }
+
+    /// Create the translator. If the TLS configuration cannot be used, such as when a certificate bundle cannot
+    /// be read, every request made by the translator fails with `Error::Tls`.
+    pub fn build(self) -> Translator {
+        let client = https_client(&self.tls, self.proxy.as_ref()).map(|mut client| {
+            client.set_read_timeout(self.timeout);
+            client.set_write_timeout(self.timeout);
+            Arc::new(client)
+        });
+        Translator {
+            client:            client,
+            chunk_length:      self.chunk_length,
+            concurrent_chunks: self.concurrent_chunks,
+            audit_log:         self.audit_log,
+            retry:             self.retry,
+            cancelled:         None,
+            circuits:          Arc::new(CircuitBreaker::new(self.endpoints.len(), self.circuit_threshold, self.circuit_cooldown)),
+            usage:             Arc::new(UsageMeter::new(self.endpoints.len(), self.character_limit)),
+            endpoints:         Arc::new(self.endpoints),
+            endpoint:          Arc::new(AtomicUsize::new(0)),
+            user_agents:       Arc::new(self.user_agents),
+            user_agent:        Arc::new(AtomicUsize::new(0)),
+            cache:             self.cache,
+            memory:            self.memory,
+            memory_threshold:  self.memory_threshold,
+            throttle:          self.throttle,
+            redact:            Arc::new(self.redact),
+            glossary:          Arc::new(self.glossary),
+            hints:             Arc::new(self.hints),
+            on_retry:          self.on_retry,
+        }
+    }
+}
+
+/// Sends text to Google Translate. A `Translator` is cheap to clone, and clones share the same connection pool.
+#[derive(Clone)]
+pub struct Translator {
+    client: Result<Arc<Client>, String>,
+    chunk_length: usize,
+    concurrent_chunks: bool,
+    audit_log: Option<Arc<AuditLog>>,
+    retry: RetryPolicy,
+    /// Set when the background translation that this translator is carrying out has been cancelled.
+    cancelled: Option<Arc<AtomicBool>>,
+    endpoints: Arc<Vec<String>>,
+    /// The index of the endpoint currently in use, which is shared by every clone.
+    endpoint: Arc<AtomicUsize>,
+    /// Which of the endpoints are being rested after failing repeatedly, which is shared by every clone.
+    circuits: Arc<CircuitBreaker>,
+    /// How much text has been sent to each endpoint, which is shared by every clone.
+    usage: Arc<UsageMeter>,
+    user_agents: Arc<Vec<String>>,
+    /// The number of requests sent so far by this translator and its clones, which picks the next User-Agent.
+    user_agent: Arc<AtomicUsize>,
+    cache: Option<Arc<ResponseCache>>,
+    memory: Option<Arc<TranslationMemory>>,
+    memory_threshold: f64,
+    throttle: Option<Arc<Throttle>>,
+    /// The kinds of sensitive text which are masked before anything is sent.
+    redact: Arc<Vec<Sensitive>>,
+    glossary: Arc<Glossary>,
+    /// The languages which text is likely to be written in, most likely first.
+    hints: Arc<Vec<String>>,
+    on_retry: Option<RetryObserver>,
+}
+
+impl Translator {
+    /// Create a translator with the default configuration.
+    pub fn new() -> Translator {
+        Translator::builder().build()
+    }
+
+    pub fn builder() -> TranslatorBuilder {
+        TranslatorBuilder {
+            timeout:           Some(Duration::from_secs(DEFAULT_TIMEOUT)),
+            chunk_length:      MAX_SEGMENT_LENGTH,
+            concurrent_chunks: false,
+            audit_log:         None,
+            tls:               TlsConfig::default(),
+            proxy:             None,
+            retry:             RetryPolicy::default(),
+            endpoints:         vec![DEFAULT_ENDPOINT.to_string()],
+            user_agents:       vec![DEFAULT_USER_AGENT.to_string()],
+            cache:             None,
+            memory:            None,
+            memory_threshold:  DEFAULT_MATCH_THRESHOLD,
+            throttle:          None,
+            circuit_threshold: DEFAULT_CIRCUIT_THRESHOLD,
+            circuit_cooldown:  DEFAULT_CIRCUIT_COOLDOWN,
+            redact:            Vec::new(),
+            glossary:          Glossary::new(),
+            character_limit:   None,
+            hints:             Vec::new(),
+            on_retry:          None,
+        }
+    }
+
+    /// The length in characters beyond which input is split into chunks and translated a piece at a time.
+    pub fn chunk_length(&self) -> usize {
+        self.chunk_length
+    }
+
+    /// Each endpoint that requests are sent to, with whether it is being rested after failing repeatedly.
+    pub fn circuits(&self) -> Vec<(String, CircuitState)> {
+        self.endpoints.iter().cloned().zip(self.circuits.states(Instant::now())).collect()
+    }
+
+    /// How much text this translator and its clones have sent to Google so far, in all and to each endpoint.
+    pub fn usage(&self) -> Usage {
+        self.usage.usage(&self.endpoints)
+    }
+
+    /// Send text to Google Translate and translate it into `language`, which is a language code such as
+    /// `EN` or one of the script variants understood by `split_script`. The language of the input is
+    /// detected automatically unless a `source` language is given. This blocks until the translation
+    /// is complete; see `translate_async` for a non-blocking equivalent.
+    ///
+    /// Input longer than the configured chunk length is split at the ends of sentences, translated a piece
+    /// at a time, and joined back together with the whitespace that separated the pieces.
+    pub fn translate(&self, input: &str, source: Option<&str>, language: &str) -> Result<TranslationResult, Error> {
+        let (input, masks) = self.mask(input);
+        let mut result = self.translate_masked(input.as_str(), source, language)?;
+        if !masks.is_empty() {
+            // Glossary terms are put back as their translation into the language, if they have one.
+            let translated = masks.iter().map(|mask| self.glossary.translation(mask, language)).collect::<Vec<String>>();
+            result.text = restore(result.text.as_str(), &translated).0;
+            for sentence in &mut result.sentences {
+                sentence.translation = restore(sentence.translation.as_str(), &translated).0;
+                sentence.source = restore(sentence.source.as_str(), &masks).0;
+            }
+        }
+        Ok(result)
+    }
+
+    /// `input` with the sensitive text that is to be redacted and the glossary terms masked, along with the text
+    /// behind each mask. Input with neither is left as it is, rather than having any placeholders in it masked
+    /// in turn.
+    fn mask(&self, input: &str) -> (String, Vec<String>) {
+        let mut masked = find_sensitive(input, &self.redact);
+        for term in self.glossary.find(input) {
+            if !masked.contains(&term) { masked.push(term); }
+        }
+        if masked.is_empty() {
+            return (input.to_string(), Vec::new());
+        }
+        masked.sort_by_key(|text| ::std::cmp::Reverse(text.len()));
+        protect(input, &masked)
+    }
+
+    /// Translate input whose sensitive text and glossary terms have been masked, as `translate` does.
+    fn translate_masked(&self, input: &str, source: Option<&str>, language: &str) -> Result<TranslationResult, Error> {
+        if input.chars().count() <= self.chunk_length {
+            return self.translate_chunk(input, source, language);
+        }
+        let segments = split_text(input, self.chunk_length);
+        let texts = segments.iter().filter_map(|segment| match *segment {
+            Segment::Text(text) => Some(text),
+            Segment::Space(_)   => None
+        });
+        let results = if self.concurrent_chunks {
+            self.translate_chunks(texts.collect(), source, language)?
+        } else {
+            texts.map(|text| self.translate_chunk(text, source, language)).collect::<Result<Vec<TranslationResult>, Error>>()?
+        };
+        Ok(join_results(&segments, results))
+    }
+
+    /// Translate each of `texts` on a pool of up to `MAX_CONCURRENT_CHUNKS` threads, returning the results in the
+    /// same order. Once one has failed, the chunks which no thread has begun on yet are not sent.
+    fn translate_chunks(&self, texts: Vec<&str>, source: Option<&str>, language: &str) -> Result<Vec<TranslationResult>, Error> {
+        let (job_sender, job_receiver) = mpsc::channel::<(usize, String)>();
+        let (result_sender, result_receiver) = mpsc::channel::<(usize, Result<TranslationResult, Error>)>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for _ in 0..texts.len().min(MAX_CONCURRENT_CHUNKS) {
+            let job_receiver = job_receiver.clone();
+            let translator = self.clone();
+            let result_sender = result_sender.clone();
+            let source = source.map(String::from);
+            let language = language.to_string();
+            thread::spawn(move || loop {
+                let job = job_receiver.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).recv();
+                match job {
+                    Ok((index, text)) => {
+                        let result = translator.translate_chunk(text.as_str(), source.as_deref(), language.as_str());
+                        if result_sender.send((index, result)).is_err() { break }
+                    },
+                    Err(_) => break
+                }
+            });
+        }
+        drop(result_sender);
+        for (index, text) in texts.iter().enumerate() {
+            let _ = job_sender.send((index, text.to_string()));
+        }
+        drop(job_sender);
+
+        // Returning at the first failure drops the receiver, so that each thread stops once its request is done.
+        let mut results = texts.iter().map(|_| None).collect::<Vec<Option<TranslationResult>>>();
+        for (index, result) in result_receiver {
+            results[index] = Some(result?);
+        }
+        results.into_iter().map(|result| result.ok_or(Error::Interrupted)).collect()
+    }
+
+    /// Translate a piece of text in a single request, unless the translation memory holds a translation of it.
+    fn translate_chunk(&self, input: &str, source: Option<&str>, language: &str) -> Result<TranslationResult, Error> {
+        let memory = match self.memory {
+            Some(ref memory) => memory,
+            None             => return self.request_chunk(input, source, language)
+        };
+        if let Some(found) = memory.lookup(source, language, input, self.memory_threshold) {
+            // The whitespace around the text is kept, as it is when Google translates it.
+            let start = &input[..input.len() - input.trim_start().len()];
+            let end = &input[input.trim_end().len()..];
+            return Ok(TranslationResult {
+                text:            format!("{}{}{}", start, found.unit.target.trim(), end),
+                source_language: found.unit.source_language,
+                origin:          Origin::Memory,
+                similarity:      Some(found.similarity),
+                ..TranslationResult::default()
+            });
+        }
+        let result = self.request_chunk(input, source, language)?;
+        if !input.trim().is_empty() {
+            // The translation is no less correct for not being remembered, so a memory which cannot be written to is ignored.
+            let _ = memory.add(TranslationUnit {
+                source_language: source.map(String::from).or_else(|| result.source_language.clone()),
+                target_language: language.to_string(),
+                source:          input.to_string(),
+                target:          result.text.clone(),
+            });
+        }
+        Ok(result)
+    }
+
+    /// Translate a piece of text with a request to Google. If its language is detected as one other than those
+    /// hinted at without much confidence, it is translated again from the first hint.
+    fn request_chunk(&self, input: &str, source: Option<&str>, language: &str) -> Result<TranslationResult, Error> {
+        let result = self.request_translation(input, source, language)?;
+        match self.hint(result.source_language.as_deref(), result.confidence) {
+            Some(hint) if source.is_none() => self.request_translation(input, Some(hint), language),
+            _ => Ok(result)
+        }
+    }
+
+    /// The hint which text detected as `detected` with `confidence` should be taken to be written in instead, if
+    /// there are hints and Google is not confident enough of a language which none of them is.
+    fn hint(&self, detected: Option<&str>, confidence: Option<f64>) -> Option<&str> {
+        let primary = |language: &str| split_script(language).0.split('-').next().unwrap_or("").to_string();
+        let hinted = detected.is_some_and(|detected| self.hints.iter().any(|hint| primary(hint) == primary(detected)));
+        if hinted || confidence.is_some_and(|confidence| confidence >= HINT_CONFIDENCE) {
+            return None;
+        }
+        self.hints.first().map(String::as_str)
+    }
+
+    /// Translate a piece of text in a single request to Google.
+    fn request_translation(&self, input: &str, source: Option<&str>, language: &str) -> Result<TranslationResult, Error> {
+        let (language, script) = split_script(language);
+        let source = source.map_or_else(|| "auto".to_string(), |source| split_script(source).0);
+        let mut search = String::new();
+        search.push_str("&sl=");
+        search.push_str(&percent_encode(source.as_str()));
+        search.push_str("&tl=");
+        search.push_str(&percent_encode(language.as_str()));
+        search.push_str("&dt=t&dt=at");
+
+        let (response, origin) = self.fetch_parsed(search.as_str(), input, parse_response)?;
+
+        // Serbian Latin is delivered by transliterating Google's Cyrillic output.
+        let convert = |text: String| match script {
+            Some(Script::Latin) => {
+                let mut converted = String::with_capacity(text.len());
+                serbian_to_latin(text.as_str(), &mut converted);
+                converted
+            },
+            None => text
+        };
+
+        let mut result = TranslationResult {
+            source_language: response.source_language,
+            confidence:      response.confidence,
+            origin,
+            ..TranslationResult::default()
+        };
+        for mut sentence in response.sentences {
+            sentence.translation = convert(sentence.translation);
+            result.text.push_str(sentence.translation.as_str());
+            result.sentences.push(sentence);
+        }
+        for mut alternative in response.alternatives {
+            alternative.candidates = alternative.candidates.into_iter().map(&convert).collect();
+            result.alternatives.push(alternative);
+        }
+        Ok(result)
+    }
+
+    /// Detect the language that text is written in without translating it. A language other than those hinted
+    /// at which Google is not confident enough of gives way to the first hint, whose confidence is unknown.
+    pub fn detect(&self, input: &str) -> Result<Detection, Error> {
+        // Without any 'dt' parameters, Google responds with the detected language alone.
+        let (detection, _) = self.fetch_parsed("&sl=auto&tl=en", self.mask(input).0.as_str(), parse_detection)?;
+        Ok(match self.hint(Some(detection.language.as_str()), detection.confidence) {
+            Some(hint) => Detection { language: hint.to_lowercase(), confidence: None },
+            None => detection
+        })
+    }
+
+    /// Split `text` into runs of sentences written in the same language, detecting the language of each sentence
+    /// on its own, so that a document which mixes languages can be told apart. A sentence without any letters,
+    /// such as a number, belongs to the run before it.
+    pub fn segment_by_language(&self, text: &str) -> Result<Vec<LanguageRun>, Error> {
+        let mut sentences = Vec::new();
+        for sentence in split_sentences(text) {
+            let language = if sentence.chars().any(char::is_alphabetic) { Some(self.detect(sentence.trim())?.language) } else { None };
+            sentences.push((sentence, language));
+        }
+        Ok(join_runs(sentences))
+    }
+
+    /// Look up `word` in Google's dictionary, returning its translations into `language` for each part of speech
+    /// that it may be, or none if Google has no entry for it. The language of the word is detected unless a
+    /// `source` language is given.
+    pub fn look_up(&self, word: &str, source: Option<&str>, language: &str) -> Result<Vec<DictionaryEntry>, Error> {
+        let source = source.map_or_else(|| "auto".to_string(), |source| split_script(source).0);
+        let parameters = format!("&sl={}&tl={}&dt=bd", percent_encode(source.as_str()), percent_encode(split_script(language).0.as_str()));
+        self.fetch_parsed(parameters.as_str(), self.mask(word.trim()).0.as_str(), parse_dictionary).map(|(entries, _)| entries)
+    }
+
+    /// Fetch the response to `input` with `parameters` as `fetch` does and `parse` it, answering from the cache
+    /// if it holds the response, and say which it was. Only responses which can be parsed are cached.
+    fn fetch_parsed<T, F: Fn(&str) -> Result<T, String>>(&self, parameters: &str, input: &str, parse: F) -> Result<(T, Origin), Error> {
+        let key = format!("{}\n{}\n{}", ENGINE, parameters, input);
+        if let Some(parsed) = self.cache.as_ref().and_then(|cache| cache.get(key.as_str())).and_then(|body| parse(body.as_str()).ok()) {
+            return Ok((parsed, Origin::Cache));
+        }
+        let body = self.fetch(parameters, input)?;
+        let parsed = parse(body.as_str()).map_err(Error::Parse)?;
+        if let Some(ref cache) = self.cache {
+            // The translation is no less correct for not being cached, so a cache which cannot be written to is ignored.
+            let _ = cache.put(key.as_str(), body.as_str());
+        }
+        Ok((parsed, Origin::Google))
+    }
+
+    /// Send `input` as the `q` parameter to the translation service of the current endpoint, with the other
+    /// `parameters` given, in the URL if it fits within `MAX_URL_LENGTH` and as a form in the body of a POST
+    /// otherwise. Failures which another endpoint might not share move on to the next endpoint at once, and
+    /// once every endpoint has been tried, or if there is only one, transient failures are retried as the retry
+    /// policy allows. When rate limited, the time that Google asks for is waited out if it is not too long.
+    /// Endpoints whose circuit has opened are passed over, and if every one has, the request is not sent, nor is
+    /// it if it would take the characters sent past the translator's limit.
+    fn fetch(&self, parameters: &str, input: &str) -> Result<String, Error> {
+        let mut attempt = 1;
+        loop {
+            if self.cancelled.as_ref().is_some_and(|cancelled| cancelled.load(Ordering::SeqCst)) {
+                return Err(Error::Interrupted);
+            }
+            if let Some(throttle) = self.throttle.as_ref() {
+                throttle.wait();
+            }
+            let current = self.endpoint.load(Ordering::SeqCst) % self.endpoints.len();
+            let index = self.circuits.select(current, Instant::now()).map_err(Error::Unavailable)?;
+            if index != current {
+                let _ = self.endpoint.compare_exchange(current, index, Ordering::SeqCst, Ordering::SeqCst);
+            }
+            self.usage.charge(index, input.chars().count() as u64).map_err(Error::LimitReached)?;
+            let url = format!("{}{}{}", self.endpoints[index], TRANSLATE, parameters);
+            let why = match self.send(url.as_str(), input) {
+                Err(why) => why,
+                result   => {
+                    self.circuits.succeeded(index);
+                    return result
+                }
+            };
+            // Only failures which are the endpoint's own count against it, not those of the request or configuration.
+            match why {
+                Error::RateLimited(_) | Error::Blocked => self.circuits.failed(index, Instant::now()),
+                ref why if why.is_transient() => self.circuits.failed(index, Instant::now()),
+                _ => self.circuits.succeeded(index)
+            }
+            let rotating = self.endpoints.len() > 1;
+            let wait = match why {
+                _ if attempt >= self.attempts(&why) => return Err(why),
+                Error::RateLimited(_) | Error::Blocked if rotating => self.rotate(index, attempt),
+                Error::RateLimited(wait) => {
+                    let wait = wait.unwrap_or_else(|| self.retry.delay(attempt));
+                    if wait > self.retry.max_retry_after {
+                        return Err(Error::RateLimited(Some(wait)));
+                    }
+                    wait
+                },
+                ref why if why.is_transient() && rotating => self.rotate(index, attempt),
+                ref why if why.is_transient() => self.retry.delay(attempt),
+                _ => return Err(why)
+            };
+            if let Some(observer) = self.on_retry.as_ref() {
+                observer(&why, wait);
+            }
+            thread::sleep(wait);
+            attempt += 1;
+        }
+    }
+
+    /// How many attempts a request which fails with `why` may make in all. A failure which another endpoint might
+    /// not share moves on to the next endpoint, so each of the attempts that the retry policy allows is a whole
+    /// pass over the endpoints, and every endpoint is tried at least once.
+    fn attempts(&self, why: &Error) -> u32 {
+        let rotates = match *why {
+            Error::RateLimited(_) | Error::Blocked => true,
+            ref why => why.is_transient()
+        };
+        if rotates { self.retry.attempts.max(1).saturating_mul(self.endpoints.len() as u32) } else { self.retry.attempts }
+    }
+
+    /// Move on from the endpoint at `index` to the next, returning how long to wait before trying it. There is
+    /// no wait until every endpoint has been tried, after which the retry policy's backoff applies.
+    fn rotate(&self, index: usize, attempt: u32) -> Duration {
+        // Another thread may have already moved on from the same endpoint, in which case it is left as it is.
+        let _ = self.endpoint.compare_exchange(index, (index + 1) % self.endpoints.len(), Ordering::SeqCst, Ordering::SeqCst);
+        let tries = self.endpoints.len() as u32;
+        if attempt % tries == 0 { self.retry.delay(attempt / tries) } else { Duration::from_secs(0) }
+    }
+
+    /// Make a single attempt at the request described by `fetch`.
+    fn send(&self, url: &str, input: &str) -> Result<String, Error> {
+        let query = format!("q={}", percent_encode(input));
+        let post = url.len() + 1 + query.len() > MAX_URL_LENGTH;
+        let client = self.client.as_ref().map_err(|why| Error::Tls(why.clone()))?;
+        if let Some(ref audit_log) = self.audit_log {
+            audit_log.record(if post { "POST" } else { "GET" }, url, input).map_err(Error::Audit)?;
+        }
+        let user_agent = UserAgent(self.user_agents[self.user_agent.fetch_add(1, Ordering::SeqCst) % self.user_agents.len()].clone());
+        let mut response = if !post {
+            client.get(format!("{}&{}", url, query).as_str()).header(Connection::close()).header(user_agent).send()?
+        } else {
+            client.post(url).header(Connection::close()).header(user_agent).header(ContentType::form_url_encoded())
+                .body(query.as_str()).send()?
+        };
+        let mut body = Vec::new();
+        response.read_to_end(&mut body)?;
+        // The CAPTCHA page may come with any status, or after a redirect to it, so it is looked for first.
+        if response.url.path().starts_with("/sorry/") || is_blocked(String::from_utf8_lossy(&body).as_ref()) {
+            return Err(Error::Blocked);
+        }
+        if response.status.to_u16() == 429 {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs());
+            let wait = response.headers.get_raw("Retry-After").and_then(|values| values.first())
+                .and_then(|value| str::from_utf8(value).ok()).and_then(|value| parse_retry_after(value, now));
+            return Err(Error::RateLimited(wait));
+        }
+        if !response.status.is_success() {
+            return Err(Error::Status(response.status.to_u16()));
+        }
+        String::from_utf8(body).map_err(|why| Error::Parse(why.to_string()))
+    }
+
+    /// Translate the same text into each of `languages` at once, returning the results in the same order.
+    pub fn translate_all(&self, input: &str, source: Option<&str>, languages: &[&str]) -> Vec<Result<TranslationResult, Error>> {
+        let pending = languages.iter().map(|language| self.translate_async(input, source, language)).collect::<Vec<PendingTranslation>>();
+        pending.into_iter().map(PendingTranslation::wait).collect()
+    }
+
+    /// Begin translating text on a background thread, returning immediately with a handle to the result.
+    pub fn translate_async(&self, input: &str, source: Option<&str>, language: &str) -> PendingTranslation {
+        let translator = Translator { cancelled: Some(Arc::new(AtomicBool::new(false))), ..self.clone() };
+        translator.spawn(input, source, language)
+    }
+
+    /// Translate text on a background thread which shares this translator's cancellation, so that cancelling
+    /// a translation also cancels the translations of its chunks.
+    fn spawn(&self, input: &str, source: Option<&str>, language: &str) -> PendingTranslation {
+        let (sender, receiver) = mpsc::channel();
+        let translator = self.clone();
+        let cancelled = self.cancelled.clone().unwrap_or_default();
+        let input = input.to_string();
+        let source = source.map(String::from);
+        let language = language.to_string();
+        let flag = cancelled.clone();
+        thread::spawn(move || {
+            let result = translator.translate(input.as_str(), source.as_deref(), language.as_str());
+            let _ = sender.send(if flag.load(Ordering::SeqCst) { Err(Error::Interrupted) } else { result });
+        });
+        PendingTranslation { receiver, cancelled }
+    }
+}
+
+/// Write an endpoint given as configuration in the form in which paths are appended to it: with a scheme,
+/// which is HTTPS if none is given, and without a trailing slash.
+fn normalize_endpoint(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.is_empty() || endpoint.contains("://") {
+        endpoint.to_string()
+    } else {
+        format!("https://{}", endpoint)
+    }
+}
+
+/// Join consecutive sentences in the same language into runs. A sentence whose language is unknown joins the run
+/// before it, or the first run if it comes before any.
+fn join_runs(sentences: Vec<(&str, Option<String>)>) -> Vec<LanguageRun> {
+    let mut runs: Vec<LanguageRun> = Vec::new();
+    let mut leading = String::new();
+    for (sentence, language) in sentences {
+        if let Some(run) = runs.last_mut().filter(|run| language.is_none() || run.language == language) {
+            run.text.push_str(sentence);
+            continue;
+        }
+        match language {
+            Some(language) => runs.push(LanguageRun { text: mem::take(&mut leading) + sentence, language: Some(language) }),
+            None => leading.push_str(sentence)
+        }
+    }
+    if !leading.is_empty() {
+        runs.push(LanguageRun { text: leading, language: None });
+    }
+    runs
+}
+
+/// Join the translations of the pieces of text in `segments` back together, keeping the whitespace between
+/// them. The detected language is that of the first piece for which Google reported one.
+fn join_results(segments: &[Segment], results: Vec<TranslationResult>) -> TranslationResult {
+    let mut joined = TranslationResult::default();
+    // The translation came from Google if any piece of it did, and from the memory if any did but none from Google.
+    let mut origin: Option<Origin> = None;
+    let mut results = results.into_iter();
+    for segment in segments {
+        let result = match *segment {
+            Segment::Space(space) => { joined.text.push_str(space); continue },
+            Segment::Text(_) => match results.next() {
+                Some(result) => result,
+                None         => break
+            }
+        };
+        joined.text.push_str(result.text.as_str());
+        if joined.source_language.is_none() {
+            joined.source_language = result.source_language;
+            joined.confidence = result.confidence;
+        }
+        origin = Some(match (origin, result.origin) {
+            (None, origin) => origin,
+            (Some(Origin::Google), _) | (_, Origin::Google) => Origin::Google,
+            (Some(Origin::Memory), _) | (_, Origin::Memory) => Origin::Memory,
+            _ => Origin::Cache
+        });
+        joined.similarity = match (joined.similarity, result.similarity) {
+            (Some(joined), Some(similarity)) => Some(joined.min(similarity)),
+            (joined, similarity) => joined.or(similarity)
+        };
+        joined.sentences.extend(result.sentences);
+        joined.alternatives.extend(result.alternatives);
+    }
+    joined.origin = origin.unwrap_or_default();
+    joined
+}
+
+impl Default for Translator {
+    fn default() -> Translator { Translator::new() }
+}
+
+/// A translation which is being carried out in the background by `Translator::translate_async`.
+pub struct PendingTranslation {
+    receiver: Receiver<Result<TranslationResult, Error>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PendingTranslation {
+    /// Check whether the translation has finished without blocking, returning `None` if it has not.
+    pub fn poll(&self) -> Option<Result<TranslationResult, Error>> {
+        match self.receiver.try_recv() {
+            Ok(result)                      => Some(result),
+            Err(TryRecvError::Empty)        => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::Interrupted))
+        }
+    }
+
+    /// Give up on the translation because its result is no longer wanted, such as when the text has changed
+    /// since it began. Requests which have not been sent yet, such as those for later chunks or retries, are
+    /// not sent, and the translation finishes with `Error::Interrupted`. A request already on its way to
+    /// Google cannot be called back, but its response is thrown away.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Block until the translation has finished.
+    pub fn wait(self) -> Result<TranslationResult, Error> {
+        self.receiver.recv().unwrap_or(Err(Error::Interrupted))
+    }
+}
+
+#[test]
+fn test_join_results() {
+    let segments = [Segment::Space(" "), Segment::Text("Saluton."), Segment::Space("\n\n"), Segment::Text("Mi estas ne vin.")];
+    let results = vec![
+        TranslationResult { text: "Hello.".to_string(), origin: Origin::Cache, ..TranslationResult::default() },
+        TranslationResult { text: "I am not you.".to_string(), source_language: Some("eo".to_string()), origin: Origin::Memory,
+            similarity: Some(0.96), ..TranslationResult::default() },
+    ];
+    let joined = join_results(&segments, results.clone());
+    assert_eq!(joined.text.as_str(), " Hello.\n\nI am not you.");
+    assert_eq!(joined.source_language.as_deref(), Some("eo"));
+    assert_eq!(joined.origin, Origin::Memory);
+    assert_eq!(joined.similarity, Some(0.96));
+    let fetched = vec![results[0].clone(), TranslationResult { origin: Origin::Google, ..results[1].clone() }];
+    assert_eq!(join_results(&segments, fetched).origin, Origin::Google);
+}
+
+#[test]
+fn test_join_runs() {
+    let run = |text: &str, language: Option<&str>| LanguageRun { text: text.to_string(), language: language.map(String::from) };
+    let sentences = vec![("1. ", None), ("Saluton! ", Some("eo".to_string())), ("Kiel vi? ", Some("eo".to_string())),
+        ("42 ", None), ("Hola. ", Some("es".to_string())), ("Bone.", Some("eo".to_string()))];
+    assert_eq!(join_runs(sentences), vec![run("1. Saluton! Kiel vi? 42 ", Some("eo")), run("Hola. ", Some("es")), run("Bone.", Some("eo"))]);
+    assert_eq!(join_runs(vec![("123", None)]), vec![run("123", None)]);
+}
+
+#[test]
+fn test_hint() {
+    let translator = Translator::builder().hints(vec!["ES".to_string(), "pt-BR".to_string()]).build();
+    assert_eq!(translator.hint(Some("gl"), Some(0.6)), Some("ES"));
+    assert_eq!(translator.hint(Some("gl"), None), Some("ES"));
+    assert_eq!(translator.hint(Some("pt"), Some(0.5)), None);
+    assert_eq!(translator.hint(Some("fr"), Some(0.95)), None);
+    assert_eq!(Translator::new().hint(Some("gl"), Some(0.1)), None);
+}
+
+#[test]
+fn test_attempts() {
+    let endpoints = ["a.example", "b.example", "c.example", "d.example"].iter().map(|endpoint| endpoint.to_string()).collect::<Vec<String>>();
+    let translator = Translator::builder().endpoints(endpoints.clone()).build();
+    // Every endpoint is tried on each of the three attempts of the default retry policy.
+    assert_eq!(translator.attempts(&Error::Status(503)), 12);
+    assert_eq!(translator.attempts(&Error::Blocked), 12);
+    assert_eq!(translator.attempts(&Error::Status(400)), 3);
+    // Without retries, a failure still moves on to each of the other endpoints once.
+    let translator = Translator::builder().endpoints(endpoints).retry(RetryPolicy::never()).build();
+    assert_eq!(translator.attempts(&Error::RateLimited(None)), 4);
+    assert_eq!(Translator::builder().retry(RetryPolicy::never()).build().attempts(&Error::Status(503)), 1);
+}
+
+#[test]
+fn test_character_limit() {
+    let translator = Translator::builder().character_limit(Some(3)).build();
+    assert!(matches!(translator.translate("Saluton", None, "EN"), Err(Error::LimitReached(3))));
+    assert_eq!(translator.clone().usage(), Usage { characters: 0, requests: 0, endpoints: vec![(DEFAULT_ENDPOINT.to_string(), 0)] });
+}
+
+#[test]
+fn test_mask() {
+    let translator = Translator::builder().redact(vec![Sensitive::Email]).build();
+    assert_eq!(translator.mask("Write to ada@example.com, not [0]."),
+        ("Write to [0], not [1].".to_string(), vec!["ada@example.com".to_string(), "[0]".to_string()]));
+    assert_eq!(translator.mask("Nothing to hide in [0]."), ("Nothing to hide in [0].".to_string(), Vec::new()));
+    let glossary = Glossary::parse("Acme\ncheckout\tDE\tKasse").unwrap();
+    let translator = Translator::builder().redact(vec![Sensitive::Email]).glossary(glossary).build();
+    assert_eq!(translator.mask("Ask sales@acme.example about the Acme checkout."),
+        ("Ask [0] about the [2] [1].".to_string(), vec!["sales@acme.example".to_string(), "checkout".to_string(), "Acme".to_string()]));
+}
+
+#[test]
+fn test_normalize_endpoint() {
+    assert_eq!(normalize_endpoint("translate.googleapis.com").as_str(), DEFAULT_ENDPOINT);
+    assert_eq!(normalize_endpoint(" http://localhost:8080/google/ ").as_str(), "http://localhost:8080/google");
+}
+
+#[test]
+fn test_result_json() {
+    let result = TranslationResult {
+        text:            "I am not you.".to_string(),
+        source_language: Some("eo".to_string()),
+        sentences:       vec![Sentence { translation: "I am not you.".to_string(), source: "Mi estas ne vin.".to_string() }],
+        alternatives:    vec![Alternative { source: "vin".to_string(), candidates: vec!["you".to_string(), "thee".to_string()] }],
+        ..TranslationResult::default()
+    };
+    assert_eq!(Json::from(result).to_string(), concat!(
+        r#"{"text":"I am not you.","source_language":"eo","confidence":null,"#,
+        r#""sentences":[{"translation":"I am not you.","source":"Mi estas ne vin."}],"#,
+        r#""alternatives":[{"source":"vin","candidates":["you","thee"]}],"similarity":null}"#
+    ));
+}
@@ -0,0 +1,147 @@
+//! Tidying of translations which are to be pasted straight into documents: ending them with punctuation,
+//! starting them with a capital letter, and using the quotation marks of the language they are written in.
+//!
+//! Google usually leaves these as they were in the input, so that a note jotted down without a full stop
+//! or written with straight quotes stays that way in a translation which is otherwise ready to publish.
+
+use emoji::is_emoji;
+use segment::is_sentence_end;
+
+/// Which of the corrections made by `normalize` to apply. None are applied by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Normalization {
+    /// End the text with a full stop if it does not already end with punctuation.
+    pub punctuation: bool,
+    /// Begin the text with a capital letter.
+    pub capitalize: bool,
+    /// Replace straight and English quotation marks with those of the language.
+    pub quotes: bool,
+}
+
+impl Normalization {
+    /// Whether any correction is applied at all.
+    pub fn is_enabled(&self) -> bool {
+        self.punctuation || self.capitalize || self.quotes
+    }
+}
+
+/// Apply the corrections chosen in `normalization` to `text`, which is written in `language`.
+pub fn normalize(text: &str, language: &str, normalization: &Normalization) -> String {
+    let mut text = text.to_string();
+    if normalization.quotes { text = typographic_quotes(text.as_str(), language); }
+    if normalization.capitalize { text = capitalize(text.as_str()); }
+    if normalization.punctuation { text = punctuate(text.as_str(), language); }
+    text
+}
+
+/// End `text` with a full stop, or the ideographic full stop in Chinese and Japanese, unless it already
+/// ends with punctuation which may end a sentence, such as `?` or `:`, perhaps followed by closing quotation
+/// marks or brackets, or with an emoji. Trailing whitespace is kept after the full stop.
+pub fn punctuate(text: &str, language: &str) -> String {
+    let end = text.trim_end().len();
+    let last = text[..end].trim_end_matches(|character: char| CLOSING.contains(character)).chars().next_back();
+    match last {
+        Some(last) if !(is_sentence_end(last) || ":;…".contains(last) || is_emoji(last)) => {
+            let language = language.to_uppercase();
+            let stop = if language.starts_with("ZH") || language.starts_with("JA") { "。" } else { "." };
+            format!("{}{}{}", &text[..end], stop, &text[end..])
+        },
+        _ => text.to_string()
+    }
+}
+
+/// Begin `text` with a capital letter, passing over any opening punctuation such as `¿` or a quotation mark.
+pub fn capitalize(text: &str) -> String {
+    match text.char_indices().find(|&(_, character)| character.is_alphanumeric()) {
+        Some((index, first)) if first.is_lowercase() => {
+            format!("{}{}{}", &text[..index], first.to_uppercase(), &text[index + first.len_utf8()..])
+        },
+        _ => text.to_string()
+    }
+}
+
+/// Replace the straight (`"`) and curly (`“ ” „`) double quotation marks in `text` with those which `language`
+/// uses, such as `„…“` in German or `« … »` in French, and quotations within quotations with its secondary
+/// marks. Whether a mark opens or closes a quotation is decided by whether it follows a space. Text in a
+/// language whose quotation marks are not known is returned as it is.
+pub fn typographic_quotes(text: &str, language: &str) -> String {
+    let [open, close, inner_open, inner_close] = match quotation_marks(language) {
+        Some(marks) => marks,
+        None        => return text.to_string()
+    };
+    let mut quoted = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut previous: Option<char> = None;
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        if !"\"“”„".contains(character) {
+            quoted.push(character);
+            previous = Some(character);
+            continue;
+        }
+        // A mark after a space which is not followed by a word closes a quotation that is open, as in `" bonjour "`.
+        let spaced = previous.is_none_or(|previous| previous.is_whitespace() || "([{—–-/".contains(previous));
+        let word = characters.peek().is_some_and(|next| next.is_alphanumeric() || "\"“„‘'([".contains(*next));
+        let opening = spaced && (depth == 0 || word);
+        if opening {
+            quoted.push_str(if depth == 0 { open } else { inner_open });
+            depth += 1;
+            // Marks which are set apart from the quotation, as in French, replace any space already there.
+            if open.ends_with('\u{a0}') {
+                while characters.peek().is_some_and(|next| *next == ' ') { characters.next(); }
+            }
+        } else {
+            depth = if depth > 0 { depth - 1 } else { 0 };
+            let mark = if depth == 0 { close } else { inner_close };
+            if mark.starts_with('\u{a0}') {
+                quoted.truncate(quoted.trim_end_matches(' ').len());
+            }
+            quoted.push_str(mark);
+        }
+        previous = Some(character);
+    }
+    quoted
+}
+
+/// The characters which may follow the punctuation at the end of a sentence.
+const CLOSING: &'static str = "\"'“”‘’»«›‹」』)]}";
+
+/// The opening and closing quotation marks of `language`, followed by those for a quotation within a
+/// quotation, if they are known.
+fn quotation_marks(language: &str) -> Option<[&'static str; 4]> {
+    let language = language.to_uppercase();
+    let primary = language.split('-').next().unwrap_or("");
+    Some(match (language.as_str(), primary) {
+        ("DE-CH", _) | ("FR-CH", _) | ("IT-CH", _)           => ["«", "»", "‹", "›"],
+        ("PT-BR", _) | ("ZH-CN", _) | ("ZH-HANS", _)          => ["“", "”", "‘", "’"],
+        ("ZH-TW", _) | ("ZH-HANT", _) | (_, "JA")             => ["「", "」", "『", "』"],
+        (_, "FR")                                             => ["«\u{a0}", "\u{a0}»", "“", "”"],
+        (_, "EN") | (_, "EO") | (_, "ZH") | (_, "KO") | (_, "TR") | (_, "VI") | (_, "ID")
+                                                              => ["“", "”", "‘", "’"],
+        (_, "DE") | (_, "CS") | (_, "SK") | (_, "SL") | (_, "LT") | (_, "BG") | (_, "SR") | (_, "IS")
+                                                              => ["„", "“", "‚", "‘"],
+        (_, "PL") | (_, "HU") | (_, "RO") | (_, "HR") | (_, "NL") => ["„", "”", "‚", "’"],
+        (_, "RU") | (_, "UK") | (_, "BE")                     => ["«", "»", "„", "“"],
+        (_, "ES") | (_, "IT") | (_, "PT") | (_, "CA") | (_, "EL") | (_, "NO") | (_, "AR") | (_, "FA")
+                                                              => ["«", "»", "“", "”"],
+        (_, "SV") | (_, "FI")                                 => ["”", "”", "’", "’"],
+        (_, "DA")                                             => ["»", "«", "›", "‹"],
+        _                                                     => return None
+    })
+}
+
+#[test]
+fn test_normalize() {
+    let all = Normalization { punctuation: true, capitalize: true, quotes: true };
+    assert_eq!(normalize("er sagte \"Hallo\"\n", "DE", &all).as_str(), "Er sagte „Hallo“.\n");
+    assert_eq!(normalize("il a dit \" bonjour \"", "fr", &all).as_str(), "Il a dit «\u{a0}bonjour\u{a0}».");
+    assert_eq!(normalize("¿qué \"dijo\"?", "ES", &all).as_str(), "¿Qué «dijo»?");
+    assert_eq!(normalize("“she said ‘hi’ and \"left\"”", "EN", &Normalization { quotes: true, ..Normalization::default() }).as_str(),
+        "“she said ‘hi’ and ‘left’”");
+    assert_eq!(normalize("我不是你", "ZH-CN", &all).as_str(), "我不是你。");
+    assert_eq!(punctuate("Note:", "EN").as_str(), "Note:");
+    assert_eq!(punctuate("See you 👋", "EN").as_str(), "See you 👋");
+    assert_eq!(punctuate("", "EN").as_str(), "");
+    assert_eq!(typographic_quotes("\"x\"", "XX").as_str(), "\"x\"");
+    assert!(!Normalization::default().is_enabled());
+}
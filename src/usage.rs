@@ -0,0 +1,73 @@
+//! Accounting of how much text a translator sends to Google, so that an application embedding it can keep to
+//! a budget of its own, along with an optional limit past which nothing more is sent.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How much text a translator and its clones have sent. Every attempt counts, including those which failed
+/// and were tried again, while translations answered from the cache or the translation memory do not.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Usage {
+    /// The characters sent in all.
+    pub characters: u64,
+    /// The requests sent in all.
+    pub requests: u64,
+    /// The characters sent to each endpoint, in the order in which the endpoints were given.
+    pub endpoints: Vec<(String, u64)>,
+}
+
+/// Counts the characters sent to each endpoint, refusing any which would take the total past the limit.
+pub struct UsageMeter {
+    characters: Vec<AtomicU64>,
+    total: AtomicU64,
+    requests: AtomicU64,
+    limit: Option<u64>,
+}
+
+impl UsageMeter {
+    pub fn new(endpoints: usize, limit: Option<u64>) -> UsageMeter {
+        UsageMeter {
+            characters: (0..endpoints).map(|_| AtomicU64::new(0)).collect(),
+            total:      AtomicU64::new(0),
+            requests:   AtomicU64::new(0),
+            limit,
+        }
+    }
+
+    /// Count a request of `characters` to the endpoint at `index`, unless it would take the total past the
+    /// limit, in which case nothing is counted and the limit is returned.
+    pub fn charge(&self, index: usize, characters: u64) -> Result<(), u64> {
+        let limit = self.limit;
+        self.total.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |total| {
+            total.checked_add(characters).filter(|&sum| limit.is_none_or(|limit| sum <= limit))
+        }).map_err(|_| limit.unwrap_or(u64::MAX))?;
+        self.characters[index].fetch_add(characters, Ordering::SeqCst);
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// The usage so far, with the characters sent to each of `endpoints`.
+    pub fn usage(&self, endpoints: &[String]) -> Usage {
+        Usage {
+            characters: self.total.load(Ordering::SeqCst),
+            requests:   self.requests.load(Ordering::SeqCst),
+            endpoints:  endpoints.iter().cloned().zip(self.characters.iter().map(|characters| characters.load(Ordering::SeqCst))).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_usage_meter() {
+    let endpoints = vec!["https://a.example".to_string(), "https://b.example".to_string()];
+    let meter = UsageMeter::new(2, Some(10));
+    assert_eq!(meter.charge(0, 4), Ok(()));
+    assert_eq!(meter.charge(1, 6), Ok(()));
+    assert_eq!(meter.charge(0, 1), Err(10));
+    assert_eq!(meter.usage(&endpoints), Usage {
+        characters: 10,
+        requests:   2,
+        endpoints:  vec![("https://a.example".to_string(), 4), ("https://b.example".to_string(), 6)],
+    });
+    let unlimited = UsageMeter::new(1, None);
+    assert_eq!(unlimited.charge(0, u64::MAX), Ok(()));
+    assert_eq!(unlimited.usage(&endpoints[..1]).characters, u64::MAX);
+}